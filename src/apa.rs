@@ -0,0 +1,336 @@
+use cudd::*;
+use libc::{c_int, c_uint, c_void};
+use std::cmp::Ordering;
+use std::ffi::CStr;
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use std::ops::{Add, Shl, Shr};
+use std::slice;
+
+#[cfg(feature = "num-bigint")]
+use num_bigint::BigUint;
+use {DdApaDigit, DdApaNumber, DdManager, DdNode};
+
+/// The number of bits of a `DdApaDigit`.
+const DIGIT_BITS: u32 = 32;
+
+/// An owned arbitrary precision unsigned integer (a `DdApaNumber` together with its number
+/// of digits), which is freed using `Cudd_FreeApaNumber` when dropped.
+///
+/// The digits are stored with the most significant digit first, as in CUDD. With the
+/// `num-bigint` feature enabled, the number can be converted from and into a `BigUint`.
+pub struct ApaNumber {
+    digits: c_int,
+    number: DdApaNumber,
+}
+
+/// Allocate a number with the given number of digits, all set to zero.
+fn alloc(digits: c_int) -> ApaNumber {
+    let digits = digits.max(1);
+    let number = unsafe { Cudd_NewApaNumber(digits) };
+    if number.is_null() {
+        panic!("CUDD ran out of memory.");
+    }
+    unsafe { Cudd_ApaSetToLiteral(digits, number, 0) };
+    ApaNumber { digits, number }
+}
+
+/// Create a number from its digits, ordered from the least significant one.
+fn from_le_digits(digits: &[DdApaDigit]) -> ApaNumber {
+    let mut result = alloc(digits.len() as c_int);
+    for (target, digit) in result.digits_mut().iter_mut().rev().zip(digits) {
+        *target = *digit;
+    }
+    result
+}
+
+impl ApaNumber {
+    /// A number with the given number of digits, equal to `2^power` (see `Cudd_ApaPowerOfTwo`).
+    /// The number is zero if `power` does not fit in the digits.
+    pub fn power_of_two(digits: c_int, power: u32) -> ApaNumber {
+        let result = alloc(digits);
+        // CUDD writes the digit `digits - 1 - power / DD_APA_BITS` without checking it.
+        if power <= c_int::MAX as u32 && power / DIGIT_BITS < result.digits as u32 {
+            unsafe { Cudd_ApaPowerOfTwo(result.digits, result.number, power as c_int) };
+        }
+        result
+    }
+
+    /// The exact number of minterms of the BDD `node` over `nvars` variables (see
+    /// `Cudd_ApaCountMinterm`).
+    ///
+    /// # Safety
+    ///
+    /// The `manager` and the `node` must be valid.
+    pub unsafe fn count_minterm(
+        manager: *mut DdManager,
+        node: *mut DdNode,
+        nvars: c_int,
+    ) -> Result<ApaNumber> {
+        let mut digits = 0;
+        let number = Cudd_ApaCountMinterm(manager, node, nvars, &mut digits);
+        if number.is_null() {
+            return Err(Error::new(ErrorKind::Other, "CUDD ran out of memory."));
+        }
+        Ok(ApaNumber::from_raw(digits, number))
+    }
+
+    /// Take the ownership of a number allocated by CUDD.
+    ///
+    /// # Safety
+    ///
+    /// The `number` must be a valid `DdApaNumber` with the given number of `digits`,
+    /// which is not freed by anybody else.
+    pub unsafe fn from_raw(digits: c_int, number: DdApaNumber) -> ApaNumber {
+        ApaNumber { digits, number }
+    }
+
+    /// Release the ownership of the number, returning its number of digits and the digits,
+    /// which must be freed using `Cudd_FreeApaNumber`.
+    pub fn into_raw(self) -> (c_int, DdApaNumber) {
+        let raw = (self.digits, self.number);
+        std::mem::forget(self);
+        raw
+    }
+
+    /// The number of digits of the number (including leading zeros).
+    pub fn digit_count(&self) -> c_int {
+        self.digits
+    }
+
+    /// The digits of the number, starting with the most significant one.
+    pub fn digits(&self) -> &[DdApaDigit] {
+        unsafe { slice::from_raw_parts(self.number, self.digits as usize) }
+    }
+
+    fn digits_mut(&mut self) -> &mut [DdApaDigit] {
+        unsafe { slice::from_raw_parts_mut(self.number, self.digits as usize) }
+    }
+
+    /// The digits without the leading zeros, starting with the least significant one.
+    fn le_digits(&self) -> Vec<DdApaDigit> {
+        let mut digits: Vec<DdApaDigit> = self.digits().iter().rev().cloned().collect();
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        digits
+    }
+
+    /// A copy of the number with the given number of digits, which must be enough to store
+    /// the number.
+    fn resized(&self, digits: c_int) -> ApaNumber {
+        let mut result = alloc(digits);
+        let offset = (result.digits - self.digits).max(0) as usize;
+        let skip = (self.digits - result.digits).max(0) as usize;
+        result.digits_mut()[offset..].copy_from_slice(&self.digits()[skip..]);
+        result
+    }
+
+    /// True if the number is zero.
+    pub fn is_zero(&self) -> bool {
+        self.digits().iter().all(|digit| *digit == 0)
+    }
+
+    /// The value of the number if it fits into `u128`.
+    pub fn to_u128(&self) -> Option<u128> {
+        let digits = self.le_digits();
+        if digits.len() * DIGIT_BITS as usize > 128 {
+            return None;
+        }
+        let value = digits.iter().rev().fold(0u128, |value, digit| {
+            (value << DIGIT_BITS) | u128::from(*digit)
+        });
+        Some(value)
+    }
+
+    /// Compare the ratios `self / denominator` and `other / other_denominator` (see
+    /// `Cudd_ApaCompareRatios`).
+    pub fn compare_ratios(
+        &self,
+        denominator: c_uint,
+        other: &ApaNumber,
+        other_denominator: c_uint,
+    ) -> Ordering {
+        let result = unsafe {
+            Cudd_ApaCompareRatios(
+                self.digits,
+                self.number,
+                denominator,
+                other.digits,
+                other.number,
+                other_denominator,
+            )
+        };
+        result.cmp(&0)
+    }
+
+    /// The difference `self - other`, or `None` if `other` is greater than `self`.
+    pub fn checked_sub(&self, other: &ApaNumber) -> Option<ApaNumber> {
+        if self < other {
+            return None;
+        }
+        let digits = self.digits.max(other.digits);
+        let (first, second) = (self.resized(digits), other.resized(digits));
+        let result = alloc(digits);
+        unsafe { Cudd_ApaSubtract(digits, first.number, second.number, result.number) };
+        Some(result)
+    }
+}
+
+impl Drop for ApaNumber {
+    fn drop(&mut self) {
+        unsafe { Cudd_FreeApaNumber(self.number) };
+    }
+}
+
+impl Clone for ApaNumber {
+    fn clone(&self) -> ApaNumber {
+        let result = alloc(self.digits);
+        unsafe { Cudd_ApaCopy(self.digits, self.number, result.number) };
+        result
+    }
+}
+
+impl From<u128> for ApaNumber {
+    fn from(value: u128) -> ApaNumber {
+        let digits: Vec<DdApaDigit> = (0..128 / DIGIT_BITS)
+            .map(|i| (value >> (i * DIGIT_BITS)) as DdApaDigit)
+            .collect();
+        from_le_digits(&digits)
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl<'a> From<&'a ApaNumber> for BigUint {
+    fn from(number: &'a ApaNumber) -> BigUint {
+        BigUint::from_slice(&number.le_digits())
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl<'a> From<&'a BigUint> for ApaNumber {
+    fn from(number: &'a BigUint) -> ApaNumber {
+        from_le_digits(&number.to_u32_digits())
+    }
+}
+
+impl PartialEq for ApaNumber {
+    fn eq(&self, other: &ApaNumber) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ApaNumber {}
+
+impl PartialOrd for ApaNumber {
+    fn partial_cmp(&self, other: &ApaNumber) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ApaNumber {
+    /// Compares the numbers using `Cudd_ApaCompare`.
+    fn cmp(&self, other: &ApaNumber) -> Ordering {
+        let result =
+            unsafe { Cudd_ApaCompare(self.digits, self.number, other.digits, other.number) };
+        result.cmp(&0)
+    }
+}
+
+impl Add for &ApaNumber {
+    type Output = ApaNumber;
+
+    /// Adds the numbers using `Cudd_ApaAdd`. The result has one more digit than the longer
+    /// operand, so it cannot overflow.
+    fn add(self, other: &ApaNumber) -> ApaNumber {
+        let digits = self.digits.max(other.digits) + 1;
+        let (first, second) = (self.resized(digits), other.resized(digits));
+        let result = alloc(digits);
+        unsafe { Cudd_ApaAdd(digits, first.number, second.number, result.number) };
+        result
+    }
+}
+
+impl Add for ApaNumber {
+    type Output = ApaNumber;
+
+    fn add(self, other: ApaNumber) -> ApaNumber {
+        &self + &other
+    }
+}
+
+impl Shl<u32> for ApaNumber {
+    type Output = ApaNumber;
+
+    /// Multiplies the number by `2^shift`, adding digits as needed.
+    fn shl(self, shift: u32) -> ApaNumber {
+        let (words, bits) = ((shift / DIGIT_BITS) as usize, shift % DIGIT_BITS);
+        let mut digits = vec![0; words];
+        let mut carry = 0;
+        for digit in self.le_digits() {
+            digits.push((digit << bits) | carry);
+            carry = if bits == 0 {
+                0
+            } else {
+                digit >> (DIGIT_BITS - bits)
+            };
+        }
+        digits.push(carry);
+        from_le_digits(&digits)
+    }
+}
+
+impl Shr<u32> for ApaNumber {
+    type Output = ApaNumber;
+
+    /// Divides the number by `2^shift` (rounding down), keeping the number of digits.
+    fn shr(self, shift: u32) -> ApaNumber {
+        let (words, bits) = ((shift / DIGIT_BITS) as usize, shift % DIGIT_BITS);
+        let source = self.le_digits();
+        let digits: Vec<DdApaDigit> = (words..source.len())
+            .map(|i| {
+                let high = match source.get(i + 1) {
+                    Some(high) if bits > 0 => high << (DIGIT_BITS - bits),
+                    _ => 0,
+                };
+                (source[i] >> bits) | high
+            })
+            .collect();
+        from_le_digits(&digits).resized(self.digits)
+    }
+}
+
+impl fmt::Display for ApaNumber {
+    /// Formats the number in decimal using `Cudd_ApaStringDecimal`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        unsafe {
+            let string = Cudd_ApaStringDecimal(self.digits, self.number);
+            if string.is_null() {
+                return Err(fmt::Error);
+            }
+            let result = f.pad_integral(true, "", &CStr::from_ptr(string).to_string_lossy());
+            libc::free(string as *mut c_void);
+            result
+        }
+    }
+}
+
+impl fmt::LowerHex for ApaNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.le_digits();
+        let mut string = match digits.last() {
+            Some(digit) => format!("{:x}", digit),
+            None => "0".to_string(),
+        };
+        for digit in digits.iter().rev().skip(1) {
+            string.push_str(&format!("{:08x}", digit));
+        }
+        f.pad_integral(true, "0x", &string)
+    }
+}
+
+impl fmt::Debug for ApaNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -0,0 +1,408 @@
+use cudd::*;
+use libc::{c_int, FILE};
+use std::io::{Error, ErrorKind, Result};
+use std::ptr::null_mut;
+use {DdConstApaNumber, DdManager, DdNode};
+
+/// An in-memory C stream. Everything written into the stream can be recovered
+/// as a byte vector once the stream is closed using `MemStream::into_bytes`.
+///
+/// On unix platforms, the stream is backed by `open_memstream`. Elsewhere, an anonymous
+/// temporary file (`tmpfile`) is used instead.
+pub(crate) struct MemStream {
+    file: *mut FILE,
+    #[cfg(unix)]
+    buffer: Box<MemBuffer>,
+}
+
+#[cfg(unix)]
+struct MemBuffer {
+    data: *mut libc::c_char,
+    size: libc::size_t,
+}
+
+impl MemStream {
+    #[cfg(unix)]
+    pub fn new() -> Result<MemStream> {
+        // The buffer has to stay at a fixed address until the stream is closed.
+        let mut buffer = Box::new(MemBuffer {
+            data: null_mut(),
+            size: 0,
+        });
+        let file = unsafe { libc::open_memstream(&mut buffer.data, &mut buffer.size) };
+        if file.is_null() {
+            return Err(Error::last_os_error());
+        }
+        Ok(MemStream { file, buffer })
+    }
+
+    #[cfg(not(unix))]
+    pub fn new() -> Result<MemStream> {
+        let file = unsafe { libc::tmpfile() };
+        if file.is_null() {
+            return Err(Error::last_os_error());
+        }
+        Ok(MemStream { file })
+    }
+
+    /// The underlying C stream. It stays valid until the `MemStream` is dropped or closed.
+    pub fn as_ptr(&self) -> *mut FILE {
+        self.file
+    }
+
+    /// Close the stream and return its contents.
+    #[cfg(unix)]
+    pub fn into_bytes(mut self) -> Result<Vec<u8>> {
+        let file = std::mem::replace(&mut self.file, null_mut());
+        if unsafe { libc::fclose(file) } != 0 {
+            return Err(Error::last_os_error());
+        }
+        if self.buffer.data.is_null() {
+            return Ok(Vec::new());
+        }
+        let data = self.buffer.data as *const u8;
+        Ok(unsafe { std::slice::from_raw_parts(data, self.buffer.size) }.to_vec())
+    }
+
+    /// Close the stream and return its contents.
+    #[cfg(not(unix))]
+    pub fn into_bytes(mut self) -> Result<Vec<u8>> {
+        let file = std::mem::replace(&mut self.file, null_mut());
+        let mut bytes = Vec::new();
+        let mut chunk = [0u8; 4096];
+        unsafe {
+            libc::fflush(file);
+            libc::rewind(file);
+            loop {
+                let read = libc::fread(
+                    chunk.as_mut_ptr() as *mut libc::c_void,
+                    1,
+                    chunk.len(),
+                    file,
+                );
+                bytes.extend_from_slice(&chunk[..read]);
+                if read < chunk.len() {
+                    break;
+                }
+            }
+            let failed = libc::ferror(file) != 0;
+            libc::fclose(file);
+            if failed {
+                return Err(Error::new(ErrorKind::Other, "cannot read temporary file"));
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Close the stream and return its contents as UTF-8 text.
+    pub fn into_string(self) -> Result<String> {
+        String::from_utf8(self.into_bytes()?).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+impl Drop for MemStream {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.file.is_null() {
+                libc::fclose(self.file);
+            }
+            #[cfg(unix)]
+            libc::free(self.buffer.data as *mut libc::c_void);
+        }
+    }
+}
+
+/// Restores the original output streams of a manager, even if the captured action panics.
+struct StreamGuard {
+    manager: *mut DdManager,
+    stdout: *mut FILE,
+    stderr: *mut FILE,
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        unsafe {
+            Cudd_SetStdout(self.manager, self.stdout);
+            Cudd_SetStderr(self.manager, self.stderr);
+        }
+    }
+}
+
+/// Convert the integer status returned by most CUDD printing functions into a `Result`.
+fn check_status(status: c_int, message: String) -> Result<()> {
+    if status == 0 {
+        let message = if message.trim().is_empty() {
+            "CUDD function reported a failure".to_string()
+        } else {
+            message.trim().to_string()
+        };
+        Err(Error::new(ErrorKind::Other, message))
+    } else {
+        Ok(())
+    }
+}
+
+/// Run `action` with a fresh in-memory C stream and return everything it writes into
+/// the stream as a `String`. The action should return the status code of the underlying
+/// CUDD function (`0` indicates a failure).
+///
+/// # Safety
+///
+/// The action must only use the stream during its execution and must not close it.
+pub unsafe fn capture<F: FnOnce(*mut FILE) -> c_int>(action: F) -> Result<String> {
+    let stream = MemStream::new()?;
+    let status = action(stream.as_ptr());
+    let output = stream.into_string()?;
+    check_status(status, String::new())?;
+    Ok(output)
+}
+
+/// Run `action` while the standard output and error streams of the `manager` are temporarily
+/// replaced by in-memory streams (see `Cudd_SetStdout` and `Cudd_SetStderr`), and return
+/// the captured standard output. The original streams are restored afterwards.
+///
+/// If the action returns `0`, the result is an error whose message contains the captured
+/// error output of the manager (if any).
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager. The action must not change the output
+/// streams of the manager.
+pub unsafe fn capture_output<F: FnOnce() -> c_int>(
+    manager: *mut DdManager,
+    action: F,
+) -> Result<String> {
+    let stdout = MemStream::new()?;
+    let stderr = MemStream::new()?;
+    let guard = StreamGuard {
+        manager,
+        stdout: Cudd_ReadStdout(manager),
+        stderr: Cudd_ReadStderr(manager),
+    };
+    Cudd_SetStdout(manager, stdout.as_ptr());
+    Cudd_SetStderr(manager, stderr.as_ptr());
+    let status = action();
+    drop(guard);
+    let output = stdout.into_string()?;
+    check_status(status, stderr.into_string()?)?;
+    Ok(output)
+}
+
+/// Safe variant of `Cudd_PrintVersion`.
+pub fn print_version() -> Result<String> {
+    unsafe {
+        capture(|fp| {
+            Cudd_PrintVersion(fp);
+            1
+        })
+    }
+}
+
+/// Variant of `Cudd_PrintInfo` which returns the report as a `String`.
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager.
+pub unsafe fn print_info(manager: *mut DdManager) -> Result<String> {
+    capture(|fp| Cudd_PrintInfo(manager, fp))
+}
+
+/// Variant of `Cudd_PrintDebug` which returns the output as a `String`.
+///
+/// # Safety
+///
+/// Same as `Cudd_PrintDebug`.
+pub unsafe fn print_debug(
+    manager: *mut DdManager,
+    f: *mut DdNode,
+    n: c_int,
+    pr: c_int,
+) -> Result<String> {
+    capture_output(manager, || Cudd_PrintDebug(manager, f, n, pr))
+}
+
+/// Variant of `Cudd_PrintSummary` which returns the output as a `String`.
+///
+/// # Safety
+///
+/// Same as `Cudd_PrintSummary`.
+pub unsafe fn print_summary(
+    manager: *mut DdManager,
+    f: *mut DdNode,
+    n: c_int,
+    mode: c_int,
+) -> Result<String> {
+    capture_output(manager, || Cudd_PrintSummary(manager, f, n, mode))
+}
+
+/// Variant of `Cudd_PrintMinterm` which returns the output as a `String`.
+///
+/// # Safety
+///
+/// Same as `Cudd_PrintMinterm`.
+pub unsafe fn print_minterm(manager: *mut DdManager, node: *mut DdNode) -> Result<String> {
+    capture_output(manager, || Cudd_PrintMinterm(manager, node))
+}
+
+/// Variant of `Cudd_bddPrintCover` which returns the output as a `String`.
+///
+/// # Safety
+///
+/// Same as `Cudd_bddPrintCover`.
+pub unsafe fn print_cover(
+    manager: *mut DdManager,
+    l: *mut DdNode,
+    u: *mut DdNode,
+) -> Result<String> {
+    capture_output(manager, || Cudd_bddPrintCover(manager, l, u))
+}
+
+/// Variant of `Cudd_EpdPrintMinterm` which returns the output as a `String`.
+///
+/// # Safety
+///
+/// Same as `Cudd_EpdPrintMinterm`.
+pub unsafe fn epd_print_minterm(
+    manager: *mut DdManager,
+    node: *mut DdNode,
+    nvars: c_int,
+) -> Result<String> {
+    capture_output(manager, || Cudd_EpdPrintMinterm(manager, node, nvars))
+}
+
+/// Variant of `Cudd_PrintLinear` which returns the output as a `String`.
+///
+/// # Safety
+///
+/// Same as `Cudd_PrintLinear`.
+pub unsafe fn print_linear(manager: *mut DdManager) -> Result<String> {
+    capture_output(manager, || Cudd_PrintLinear(manager))
+}
+
+/// Variant of `Cudd_PrintTwoLiteralClauses` which returns the output as a `String`.
+///
+/// # Safety
+///
+/// Same as `Cudd_PrintTwoLiteralClauses`.
+pub unsafe fn print_two_literal_clauses(
+    manager: *mut DdManager,
+    f: *mut DdNode,
+    names: *mut *mut libc::c_char,
+) -> Result<String> {
+    capture(|fp| Cudd_PrintTwoLiteralClauses(manager, f, names, fp))
+}
+
+/// Variant of `Cudd_zddPrintMinterm` which returns the output as a `String`.
+///
+/// # Safety
+///
+/// Same as `Cudd_zddPrintMinterm`.
+pub unsafe fn zdd_print_minterm(manager: *mut DdManager, node: *mut DdNode) -> Result<String> {
+    capture_output(manager, || Cudd_zddPrintMinterm(manager, node))
+}
+
+/// Variant of `Cudd_zddPrintCover` which returns the output as a `String`.
+///
+/// # Safety
+///
+/// Same as `Cudd_zddPrintCover`.
+pub unsafe fn zdd_print_cover(manager: *mut DdManager, node: *mut DdNode) -> Result<String> {
+    capture_output(manager, || Cudd_zddPrintCover(manager, node))
+}
+
+/// Variant of `Cudd_zddPrintDebug` which returns the output as a `String`.
+///
+/// # Safety
+///
+/// Same as `Cudd_zddPrintDebug`.
+pub unsafe fn zdd_print_debug(
+    manager: *mut DdManager,
+    f: *mut DdNode,
+    n: c_int,
+    pr: c_int,
+) -> Result<String> {
+    capture_output(manager, || Cudd_zddPrintDebug(manager, f, n, pr))
+}
+
+/// Variant of `Cudd_zddPrintSubtable` which returns the output as a `String`.
+///
+/// # Safety
+///
+/// Same as `Cudd_zddPrintSubtable`.
+pub unsafe fn zdd_print_subtable(manager: *mut DdManager) -> Result<String> {
+    capture_output(manager, || {
+        Cudd_zddPrintSubtable(manager);
+        1
+    })
+}
+
+/// Variant of `Cudd_ApaPrintDecimal` which returns the number as a `String`.
+///
+/// # Safety
+///
+/// The `number` must be a valid arbitrary precision number with the given amount of `digits`.
+pub unsafe fn apa_print_decimal(digits: c_int, number: DdConstApaNumber) -> Result<String> {
+    capture(|fp| Cudd_ApaPrintDecimal(fp, digits, number))
+}
+
+/// Variant of `Cudd_ApaPrintHex` which returns the number as a `String`.
+///
+/// # Safety
+///
+/// The `number` must be a valid arbitrary precision number with the given amount of `digits`.
+pub unsafe fn apa_print_hex(digits: c_int, number: DdConstApaNumber) -> Result<String> {
+    capture(|fp| Cudd_ApaPrintHex(fp, digits, number))
+}
+
+/// Variant of `Cudd_ApaPrintExponential` which returns the number as a `String`.
+///
+/// # Safety
+///
+/// The `number` must be a valid arbitrary precision number with the given amount of `digits`.
+pub unsafe fn apa_print_exponential(
+    digits: c_int,
+    number: DdConstApaNumber,
+    precision: c_int,
+) -> Result<String> {
+    capture(|fp| Cudd_ApaPrintExponential(fp, digits, number, precision))
+}
+
+/// Variant of `Cudd_ApaPrintMinterm` which returns the output as a `String`.
+///
+/// # Safety
+///
+/// Same as `Cudd_ApaPrintMinterm`.
+pub unsafe fn apa_print_minterm(
+    manager: *mut DdManager,
+    node: *mut DdNode,
+    nvars: c_int,
+) -> Result<String> {
+    capture(|fp| Cudd_ApaPrintMinterm(fp, manager, node, nvars))
+}
+
+/// Variant of `Cudd_ApaPrintMintermExp` which returns the output as a `String`.
+///
+/// # Safety
+///
+/// Same as `Cudd_ApaPrintMintermExp`.
+pub unsafe fn apa_print_minterm_exp(
+    manager: *mut DdManager,
+    node: *mut DdNode,
+    nvars: c_int,
+    precision: c_int,
+) -> Result<String> {
+    capture(|fp| Cudd_ApaPrintMintermExp(fp, manager, node, nvars, precision))
+}
+
+/// Variant of `Cudd_ApaPrintDensity` which returns the output as a `String`.
+///
+/// # Safety
+///
+/// Same as `Cudd_ApaPrintDensity`.
+pub unsafe fn apa_print_density(
+    manager: *mut DdManager,
+    node: *mut DdNode,
+    nvars: c_int,
+) -> Result<String> {
+    capture(|fp| Cudd_ApaPrintDensity(fp, manager, node, nvars))
+}
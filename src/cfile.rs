@@ -0,0 +1,307 @@
+use capture::MemStream;
+use libc::{c_int, FILE};
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::ptr::null_mut;
+
+/// A C stream (`FILE*`) connected to a Rust `std::io::Read` or `std::io::Write` object,
+/// so that CUDD functions which expect a `FILE*` can read from and write into arbitrary
+/// Rust streams (buffers, sockets, compressed streams, ...).
+///
+/// With glibc, the stream is implemented using `fopencookie` and data is passed to the
+/// Rust object as it is produced. On other platforms, written data is buffered in memory
+/// and forwarded to the writer once the stream is closed, while the reader is consumed
+/// completely into a temporary file when the stream is opened.
+///
+/// The stream is closed when `CFile::close` is called or when the value is dropped. Use
+/// `close` to observe the errors that occurred while the C code was using the stream.
+pub struct CFile<'a> {
+    file: *mut FILE,
+    inner: Inner<'a>,
+}
+
+enum Inner<'a> {
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    Cookie(Box<Cookie<'a>>),
+    #[cfg_attr(all(target_os = "linux", target_env = "gnu"), allow(dead_code))]
+    Buffered {
+        stream: Option<MemStream>,
+        writer: Box<dyn Write + 'a>,
+    },
+    #[cfg_attr(all(target_os = "linux", target_env = "gnu"), allow(dead_code))]
+    Temporary,
+}
+
+impl<'a> CFile<'a> {
+    /// Open a C stream which writes into the given `writer`.
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    pub fn writer<W: Write + 'a>(writer: W) -> Result<CFile<'a>> {
+        Cookie::open(None, Some(Box::new(writer)), "w")
+    }
+
+    /// Open a C stream which writes into the given `writer`.
+    #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+    pub fn writer<W: Write + 'a>(writer: W) -> Result<CFile<'a>> {
+        let stream = MemStream::new()?;
+        Ok(CFile {
+            file: stream.as_ptr(),
+            inner: Inner::Buffered {
+                stream: Some(stream),
+                writer: Box::new(writer),
+            },
+        })
+    }
+
+    /// Open a C stream which reads from the given `reader`.
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    pub fn reader<R: Read + 'a>(reader: R) -> Result<CFile<'a>> {
+        Cookie::open(Some(Box::new(reader)), None, "r")
+    }
+
+    /// Open a C stream which reads from the given `reader`.
+    #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+    pub fn reader<R: Read + 'a>(mut reader: R) -> Result<CFile<'a>> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let file = unsafe { libc::tmpfile() };
+        if file.is_null() {
+            return Err(Error::last_os_error());
+        }
+        let result = CFile {
+            file,
+            inner: Inner::Temporary,
+        };
+        unsafe {
+            let data = bytes.as_ptr() as *const libc::c_void;
+            let written = libc::fwrite(data, 1, bytes.len(), file);
+            if written != bytes.len() {
+                return Err(Error::last_os_error());
+            }
+            libc::rewind(file);
+        }
+        Ok(result)
+    }
+
+    /// The underlying C stream. The pointer is valid until the `CFile` is closed or dropped.
+    pub fn as_ptr(&self) -> *mut FILE {
+        self.file
+    }
+
+    /// Close the C stream, flush the Rust writer (if any) and report any error which occurred
+    /// while transferring the data.
+    pub fn close(mut self) -> Result<()> {
+        self.finish()
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if self.file.is_null() {
+            return Ok(());
+        }
+        let file = std::mem::replace(&mut self.file, null_mut());
+        match self.inner {
+            #[cfg(all(target_os = "linux", target_env = "gnu"))]
+            Inner::Cookie(ref mut cookie) => {
+                let status = unsafe { libc::fclose(file) };
+                if let Some(error) = cookie.error.take() {
+                    return Err(error);
+                }
+                if status != 0 {
+                    return Err(Error::last_os_error());
+                }
+                match cookie.writer {
+                    Some(ref mut writer) => writer.flush(),
+                    None => Ok(()),
+                }
+            }
+            Inner::Buffered {
+                ref mut stream,
+                ref mut writer,
+            } => {
+                // The memory stream owns the file pointer, so closing it also closes `file`.
+                let bytes = stream
+                    .take()
+                    .map(|s| s.into_bytes())
+                    .unwrap_or(Ok(Vec::new()))?;
+                writer.write_all(&bytes)?;
+                writer.flush()
+            }
+            Inner::Temporary => {
+                if unsafe { libc::fclose(file) } != 0 {
+                    Err(Error::last_os_error())
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Drop for CFile<'a> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// Run `action` with a C stream connected to the given `writer`. The action should return
+/// the status code of the underlying CUDD function (`0` indicates a failure).
+///
+/// # Safety
+///
+/// The action must only use the stream during its execution and must not close it.
+pub unsafe fn write_with<W: Write, F: FnOnce(*mut FILE) -> c_int>(
+    writer: W,
+    action: F,
+) -> Result<()> {
+    let file = CFile::writer(writer)?;
+    let status = action(file.as_ptr());
+    file.close()?;
+    if status == 0 {
+        Err(Error::new(
+            ErrorKind::Other,
+            "CUDD function reported a failure",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Run `action` with a C stream connected to the given `reader` and return its result.
+///
+/// # Safety
+///
+/// The action must only use the stream during its execution and must not close it.
+pub unsafe fn read_with<R: Read, T, F: FnOnce(*mut FILE) -> T>(reader: R, action: F) -> Result<T> {
+    let file = CFile::reader(reader)?;
+    let result = action(file.as_ptr());
+    file.close()?;
+    Ok(result)
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+use self::cookie::Cookie;
+
+/// Bindings for the glibc `fopencookie` function.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+mod cookie {
+    use super::{CFile, Inner};
+    use libc::{c_char, c_int, c_void, off64_t, size_t, ssize_t, FILE};
+    use std::ffi::CString;
+    use std::io::{Error, ErrorKind, Read, Result, Write};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    type ReadFunction = unsafe extern "C" fn(*mut c_void, *mut c_char, size_t) -> ssize_t;
+    type WriteFunction = unsafe extern "C" fn(*mut c_void, *const c_char, size_t) -> ssize_t;
+    type SeekFunction = unsafe extern "C" fn(*mut c_void, *mut off64_t, c_int) -> c_int;
+    type CloseFunction = unsafe extern "C" fn(*mut c_void) -> c_int;
+
+    #[repr(C)]
+    struct cookie_io_functions_t {
+        read: Option<ReadFunction>,
+        write: Option<WriteFunction>,
+        seek: Option<SeekFunction>,
+        close: Option<CloseFunction>,
+    }
+
+    extern "C" {
+        fn fopencookie(
+            cookie: *mut c_void,
+            mode: *const c_char,
+            functions: cookie_io_functions_t,
+        ) -> *mut FILE;
+    }
+
+    /// The state shared with the C stream. The first error produced by the Rust
+    /// stream is saved and later reported by `CFile::close`.
+    pub struct Cookie<'a> {
+        reader: Option<Box<dyn Read + 'a>>,
+        pub writer: Option<Box<dyn Write + 'a>>,
+        pub error: Option<Error>,
+    }
+
+    impl<'a> Cookie<'a> {
+        pub fn open(
+            reader: Option<Box<dyn Read + 'a>>,
+            writer: Option<Box<dyn Write + 'a>>,
+            mode: &str,
+        ) -> Result<CFile<'a>> {
+            let mut cookie = Box::new(Cookie {
+                reader,
+                writer,
+                error: None,
+            });
+            let functions = cookie_io_functions_t {
+                read: Some(read_cookie),
+                write: Some(write_cookie),
+                seek: None,
+                close: None,
+            };
+            let mode = CString::new(mode).unwrap();
+            let pointer = &mut *cookie as *mut Cookie as *mut c_void;
+            let file = unsafe { fopencookie(pointer, mode.as_ptr(), functions) };
+            if file.is_null() {
+                return Err(Error::last_os_error());
+            }
+            Ok(CFile {
+                file,
+                inner: Inner::Cookie(cookie),
+            })
+        }
+
+        fn fail(&mut self, error: Error) {
+            if self.error.is_none() {
+                self.error = Some(error);
+            }
+        }
+    }
+
+    fn panicked() -> Error {
+        Error::new(ErrorKind::Other, "Rust stream panicked")
+    }
+
+    unsafe extern "C" fn read_cookie(
+        cookie: *mut c_void,
+        buf: *mut c_char,
+        size: size_t,
+    ) -> ssize_t {
+        let cookie = &mut *(cookie as *mut Cookie);
+        let buffer = std::slice::from_raw_parts_mut(buf as *mut u8, size);
+        let result = match cookie.reader {
+            Some(ref mut reader) => catch_unwind(AssertUnwindSafe(|| loop {
+                match reader.read(buffer) {
+                    Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                    result => return result,
+                }
+            }))
+            .unwrap_or_else(|_| Err(panicked())),
+            None => Err(Error::from(ErrorKind::PermissionDenied)),
+        };
+        match result {
+            Ok(read) => read as ssize_t,
+            Err(error) => {
+                cookie.fail(error);
+                -1
+            }
+        }
+    }
+
+    unsafe extern "C" fn write_cookie(
+        cookie: *mut c_void,
+        buf: *const c_char,
+        size: size_t,
+    ) -> ssize_t {
+        let cookie = &mut *(cookie as *mut Cookie);
+        let buffer = std::slice::from_raw_parts(buf as *const u8, size);
+        let result = match cookie.writer {
+            Some(ref mut writer) => catch_unwind(AssertUnwindSafe(|| writer.write_all(buffer)))
+                .unwrap_or_else(|_| Err(panicked())),
+            None => Err(Error::from(ErrorKind::PermissionDenied)),
+        };
+        match result {
+            Ok(()) => size as ssize_t,
+            Err(error) => {
+                // glibc treats a short write as an error.
+                cookie.fail(error);
+                0
+            }
+        }
+    }
+}
@@ -0,0 +1,811 @@
+use cudd::*;
+use libc::c_int;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+use {DdManager, DdNode};
+
+/// A combinational or sequential circuit read from a BLIF or AIGER file, which can be
+/// converted into BDDs using `Circuit::build`.
+#[derive(Clone, Debug)]
+pub struct Circuit {
+    names: Vec<String>,
+    definitions: Vec<Option<Definition>>,
+    inputs: Vec<usize>,
+    latches: Vec<LatchDefinition>,
+    outputs: Vec<(String, usize)>,
+}
+
+#[derive(Clone, Debug)]
+enum Definition {
+    Input,
+    Latch,
+    Gate(Gate),
+}
+
+/// A sum of cubes over the `fanin` signals (a `.names` table). The gate is the complement
+/// of the sum if `on_set` is false.
+#[derive(Clone, Debug)]
+struct Gate {
+    fanin: Vec<usize>,
+    cubes: Vec<Vec<Option<bool>>>,
+    on_set: bool,
+}
+
+#[derive(Clone, Debug)]
+struct LatchDefinition {
+    output: usize,
+    input: usize,
+    init: Option<bool>,
+}
+
+/// Options of `Circuit::build`.
+#[derive(Clone, Debug)]
+pub struct CircuitOptions {
+    /// If set, a next-state variable is created right after the present-state variable of
+    /// every latch, and the variables are registered as present and next-state variables
+    /// (`Cudd_bddSetPsVar`, `Cudd_bddSetNsVar` and `Cudd_bddSetPairIndex`).
+    pub next_state_vars: bool,
+    /// The dynamic reordering method enabled while the BDDs are constructed (sifting by
+    /// default). The previous reordering status of the manager is restored afterwards.
+    pub reordering: Option<Cudd_ReorderingType>,
+}
+
+impl Default for CircuitOptions {
+    fn default() -> Self {
+        CircuitOptions {
+            next_state_vars: false,
+            reordering: Some(Cudd_ReorderingType::CUDD_REORDER_SIFT),
+        }
+    }
+}
+
+impl CircuitOptions {
+    /// Create default options.
+    pub fn new() -> CircuitOptions {
+        CircuitOptions::default()
+    }
+
+    /// Create next-state variables interleaved with the present-state variables.
+    pub fn next_state_vars(mut self, next_state_vars: bool) -> CircuitOptions {
+        self.next_state_vars = next_state_vars;
+        self
+    }
+
+    /// Use the given dynamic reordering method during construction (`None` disables it).
+    pub fn reordering(mut self, method: Option<Cudd_ReorderingType>) -> CircuitOptions {
+        self.reordering = method;
+        self
+    }
+}
+
+/// The BDDs of a latch created by `Circuit::build`.
+#[derive(Clone, Debug)]
+pub struct LatchBdds {
+    /// The name of the latch (its output signal).
+    pub name: String,
+    /// The index of the present-state variable.
+    pub present: c_int,
+    /// The index of the next-state variable, if requested by `CircuitOptions`.
+    pub next: Option<c_int>,
+    /// The next-state function (referenced).
+    pub function: *mut DdNode,
+    /// The initial value, or `None` if it is not specified.
+    pub init: Option<bool>,
+}
+
+/// The BDDs created by `Circuit::build`. All nodes are referenced and can be released
+/// using `CircuitBdds::release`.
+#[derive(Clone, Debug)]
+pub struct CircuitBdds {
+    /// The names and variable indices of the primary inputs.
+    pub inputs: Vec<(String, c_int)>,
+    /// The latches, in the order of the file.
+    pub latches: Vec<LatchBdds>,
+    /// The names and functions of the outputs.
+    pub outputs: Vec<(String, *mut DdNode)>,
+}
+
+impl CircuitBdds {
+    /// The names of all created variables with their indices. Next-state variables are
+    /// named after their latch with an appended `'`.
+    pub fn var_names(&self) -> Vec<(c_int, String)> {
+        let mut names: Vec<(c_int, String)> =
+            self.inputs.iter().map(|(n, i)| (*i, n.clone())).collect();
+        for latch in &self.latches {
+            names.push((latch.present, latch.name.clone()));
+            if let Some(next) = latch.next {
+                names.push((next, format!("{}'", latch.name)));
+            }
+        }
+        names.sort();
+        names
+    }
+
+    /// Dereference all the output and next-state functions.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be the manager used to build the BDDs.
+    pub unsafe fn release(self, manager: *mut DdManager) {
+        for (_, node) in self.outputs {
+            Cudd_RecursiveDeref(manager, node);
+        }
+        for latch in self.latches {
+            Cudd_RecursiveDeref(manager, latch.function);
+        }
+    }
+}
+
+fn invalid_data<S: Into<String>>(message: S) -> Error {
+    Error::new(ErrorKind::InvalidData, message.into())
+}
+
+impl Circuit {
+    /// The names of the primary inputs.
+    pub fn input_names(&self) -> Vec<&str> {
+        self.inputs
+            .iter()
+            .map(|i| self.names[*i].as_str())
+            .collect()
+    }
+
+    /// The names of the latches (their output signals).
+    pub fn latch_names(&self) -> Vec<&str> {
+        self.latches
+            .iter()
+            .map(|l| self.names[l.output].as_str())
+            .collect()
+    }
+
+    /// The names of the outputs.
+    pub fn output_names(&self) -> Vec<&str> {
+        self.outputs.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    fn new() -> Circuit {
+        Circuit {
+            names: Vec::new(),
+            definitions: Vec::new(),
+            inputs: Vec::new(),
+            latches: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    fn add_signal(&mut self, name: String) -> usize {
+        self.names.push(name);
+        self.definitions.push(None);
+        self.names.len() - 1
+    }
+
+    fn define(&mut self, signal: usize, definition: Definition) -> Result<()> {
+        if self.definitions[signal].is_some() {
+            let message = format!("Signal `{}` is defined more than once.", self.names[signal]);
+            return Err(invalid_data(message));
+        }
+        if let Definition::Input = definition {
+            self.inputs.push(signal);
+        }
+        self.definitions[signal] = Some(definition);
+        Ok(())
+    }
+
+    /// Parse the first model of a BLIF file. Only `.inputs`, `.outputs`, `.names` and
+    /// `.latch` are supported (hierarchical models and library gates are not).
+    pub fn parse_blif(blif: &str) -> Result<Circuit> {
+        let mut circuit = Circuit::new();
+        let mut signals: HashMap<String, usize> = HashMap::new();
+        let mut signal = |circuit: &mut Circuit, name: &str| -> usize {
+            if let Some(id) = signals.get(name) {
+                return *id;
+            }
+            let id = circuit.add_signal(name.to_string());
+            signals.insert(name.to_string(), id);
+            id
+        };
+        let mut outputs = Vec::new();
+        let mut gate: Option<(usize, Gate)> = None;
+        let mut in_model = false;
+
+        for line in blif.replace("\\\r\n", " ").replace("\\\n", " ").lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.is_empty() {
+                continue;
+            }
+            if !tokens[0].starts_with('.') {
+                // A row of the current cover.
+                let (output, gate) = match gate {
+                    Some((output, ref mut gate)) => (output, gate),
+                    None => return Err(invalid_data(format!("Unexpected line `{}`.", line))),
+                };
+                let (pattern, value) = match tokens.len() {
+                    1 if gate.fanin.is_empty() => ("", tokens[0]),
+                    2 => (tokens[0], tokens[1]),
+                    _ => return Err(invalid_data(format!("Invalid cover row `{}`.", line))),
+                };
+                let on_set = match value {
+                    "1" => true,
+                    "0" => false,
+                    _ => return Err(invalid_data(format!("Invalid cover row `{}`.", line))),
+                };
+                if !gate.cubes.is_empty() && on_set != gate.on_set {
+                    let message = format!(
+                        "The cover of `{}` mixes on-set and off-set rows.",
+                        circuit.names[output]
+                    );
+                    return Err(invalid_data(message));
+                }
+                gate.on_set = on_set;
+                if pattern.len() != gate.fanin.len() {
+                    return Err(invalid_data(format!("Invalid cover row `{}`.", line)));
+                }
+                let cube = pattern
+                    .chars()
+                    .map(|c| match c {
+                        '1' => Ok(Some(true)),
+                        '0' => Ok(Some(false)),
+                        '-' => Ok(None),
+                        _ => Err(invalid_data(format!("Invalid cover row `{}`.", line))),
+                    })
+                    .collect::<Result<Vec<Option<bool>>>>()?;
+                gate.cubes.push(cube);
+                continue;
+            }
+
+            if let Some((output, finished)) = gate.take() {
+                circuit.define(output, Definition::Gate(finished))?;
+            }
+            match tokens[0] {
+                ".model" if in_model => break,
+                ".model" => in_model = true,
+                ".end" | ".exdc" => break,
+                ".inputs" => {
+                    for name in &tokens[1..] {
+                        let id = signal(&mut circuit, name);
+                        circuit.define(id, Definition::Input)?;
+                    }
+                }
+                ".outputs" => outputs.extend(tokens[1..].iter().map(|s| s.to_string())),
+                ".names" => {
+                    let (output, fanin) = match tokens[1..].split_last() {
+                        Some(split) => split,
+                        None => return Err(invalid_data("Missing `.names` output.")),
+                    };
+                    let fanin = fanin
+                        .iter()
+                        .map(|name| signal(&mut circuit, name))
+                        .collect();
+                    let output = signal(&mut circuit, output);
+                    gate = Some((
+                        output,
+                        Gate {
+                            fanin,
+                            cubes: Vec::new(),
+                            on_set: true,
+                        },
+                    ));
+                }
+                ".latch" => {
+                    if tokens.len() < 3 {
+                        return Err(invalid_data(format!("Invalid latch `{}`.", line)));
+                    }
+                    // The optional type and control signal precede the initial value.
+                    let init = match tokens.get(3..).and_then(|t| t.last()).cloned() {
+                        Some("0") => Some(false),
+                        Some("1") => Some(true),
+                        _ => None,
+                    };
+                    let input = signal(&mut circuit, tokens[1]);
+                    let output = signal(&mut circuit, tokens[2]);
+                    circuit.define(output, Definition::Latch)?;
+                    circuit.latches.push(LatchDefinition {
+                        output,
+                        input,
+                        init,
+                    });
+                }
+                ".subckt" | ".gate" | ".mlatch" | ".search" => {
+                    let message = format!("`{}` is not supported.", tokens[0]);
+                    return Err(invalid_data(message));
+                }
+                // Timing and other information is ignored.
+                _ => (),
+            }
+        }
+        if let Some((output, finished)) = gate.take() {
+            circuit.define(output, Definition::Gate(finished))?;
+        }
+
+        for name in outputs {
+            let id = signal(&mut circuit, &name);
+            circuit.outputs.push((name, id));
+        }
+        circuit.check_defined()?;
+        Ok(circuit)
+    }
+
+    /// Parse an AIGER file in the ASCII (`aag`) or binary (`aig`) format. Bad state
+    /// properties are treated as additional outputs; invariant constraints, justice
+    /// properties and fairness constraints are not supported.
+    pub fn parse_aiger(bytes: &[u8]) -> Result<Circuit> {
+        let mut cursor = Cursor { bytes, position: 0 };
+        let header = cursor
+            .line()
+            .ok_or_else(|| invalid_data("Empty AIGER file."))?;
+        let fields: Vec<&str> = header.split_whitespace().collect();
+        let binary = match fields.first() {
+            Some(&"aag") => false,
+            Some(&"aig") => true,
+            _ => return Err(invalid_data("Invalid AIGER header.")),
+        };
+        let counts = fields[1..]
+            .iter()
+            .map(|f| {
+                f.parse::<usize>()
+                    .map_err(|_| invalid_data("Invalid AIGER header."))
+            })
+            .collect::<Result<Vec<usize>>>()?;
+        if counts.len() < 5 {
+            return Err(invalid_data("Invalid AIGER header."));
+        }
+        let (max_var, input_count, latch_count, output_count, and_count) =
+            (counts[0], counts[1], counts[2], counts[3], counts[4]);
+        let bad_count = counts.get(5).cloned().unwrap_or(0);
+        if counts.iter().skip(6).any(|c| *c != 0) {
+            return Err(invalid_data(
+                "AIGER constraints and liveness properties are not supported.",
+            ));
+        }
+
+        let defined = input_count
+            .checked_add(latch_count)
+            .and_then(|count| count.checked_add(and_count))
+            .ok_or_else(|| invalid_data("Invalid AIGER header."))?;
+        if max_var < defined {
+            return Err(invalid_data(format!(
+                "The maximum variable index {} is smaller than the {} defined variables.",
+                max_var, defined
+            )));
+        }
+        // Except for the implicit inputs of the binary format, every variable takes up at
+        // least one byte, so headers far beyond the file size are rejected before the
+        // signals are allocated.
+        let explicit_vars = if binary {
+            max_var - input_count
+        } else {
+            max_var
+        };
+        if explicit_vars / 8 > bytes.len() {
+            return Err(invalid_data(format!(
+                "The maximum variable index {} is too large for a file of {} bytes.",
+                max_var,
+                bytes.len()
+            )));
+        }
+
+        // Signal `v` corresponds to the AIGER variable `v` (variable 0 is the constant).
+        let mut circuit = Circuit::new();
+        for var in 0..=max_var {
+            circuit.add_signal(format!("_{}", var));
+        }
+        circuit.definitions[0] = Some(Definition::Gate(Gate {
+            fanin: Vec::new(),
+            cubes: Vec::new(),
+            on_set: true,
+        }));
+        let literal = |cursor: &mut Cursor| -> Result<usize> {
+            let token = cursor.token()?;
+            let literal: usize = token
+                .parse()
+                .map_err(|_| invalid_data(format!("Invalid literal `{}`.", token)))?;
+            if literal / 2 > max_var {
+                return Err(invalid_data(format!("Invalid literal `{}`.", literal)));
+            }
+            Ok(literal)
+        };
+
+        for i in 0..input_count {
+            let lit = if binary {
+                2 * (i + 1)
+            } else {
+                let lit = literal(&mut cursor)?;
+                cursor.rest_of_line();
+                lit
+            };
+            if lit & 1 == 1 {
+                return Err(invalid_data(format!("Invalid input {}.", lit)));
+            }
+            circuit.define(lit / 2, Definition::Input)?;
+            circuit.names[lit / 2] = format!("i{}", i);
+        }
+        let mut latch_literals = Vec::new();
+        for i in 0..latch_count {
+            let lit = if binary {
+                2 * (input_count + i + 1)
+            } else {
+                literal(&mut cursor)?
+            };
+            if lit & 1 == 1 {
+                return Err(invalid_data(format!("Invalid latch {}.", lit)));
+            }
+            let next = literal(&mut cursor)?;
+            let rest = cursor.rest_of_line();
+            let init = match rest.trim() {
+                "" | "0" => Some(false),
+                "1" => Some(true),
+                reset if reset == lit.to_string() => None,
+                reset => return Err(invalid_data(format!("Invalid reset value `{}`.", reset))),
+            };
+            circuit.define(lit / 2, Definition::Latch)?;
+            circuit.names[lit / 2] = format!("l{}", i);
+            latch_literals.push((lit, next, init));
+        }
+        let mut output_literals = Vec::new();
+        let output_total = output_count
+            .checked_add(bad_count)
+            .ok_or_else(|| invalid_data("Invalid AIGER header."))?;
+        for _ in 0..output_total {
+            output_literals.push(literal(&mut cursor)?);
+            cursor.rest_of_line();
+        }
+        for i in 0..and_count {
+            let (lhs, rhs0, rhs1) = if binary {
+                let lhs = 2 * (input_count + latch_count + i + 1);
+                let rhs0 = lhs
+                    .checked_sub(cursor.delta()?)
+                    .ok_or_else(|| invalid_data("Invalid AND gate."))?;
+                let rhs1 = rhs0
+                    .checked_sub(cursor.delta()?)
+                    .ok_or_else(|| invalid_data("Invalid AND gate."))?;
+                (lhs, rhs0, rhs1)
+            } else {
+                let lhs = literal(&mut cursor)?;
+                let gate = (lhs, literal(&mut cursor)?, literal(&mut cursor)?);
+                cursor.rest_of_line();
+                gate
+            };
+            if lhs & 1 == 1 || lhs / 2 > max_var {
+                return Err(invalid_data(format!("Invalid AND gate {}.", lhs)));
+            }
+            let gate = Gate {
+                fanin: vec![rhs0 / 2, rhs1 / 2],
+                cubes: vec![vec![Some(rhs0 & 1 == 0), Some(rhs1 & 1 == 0)]],
+                on_set: true,
+            };
+            circuit.define(lhs / 2, Definition::Gate(gate))?;
+        }
+
+        // Symbol table.
+        let mut output_names: Vec<String> = (0..output_literals.len())
+            .map(|i| {
+                if i < output_count {
+                    format!("o{}", i)
+                } else {
+                    format!("b{}", i - output_count)
+                }
+            })
+            .collect();
+        while let Some(line) = cursor.line() {
+            if line == "c" {
+                break;
+            }
+            let mut parts = line.splitn(2, ' ');
+            let symbol = parts.next().unwrap_or("");
+            let name = parts.next().unwrap_or("").to_string();
+            let position = symbol.get(1..).and_then(|p| p.parse::<usize>().ok());
+            match (symbol.chars().next(), position) {
+                (Some('i'), Some(p)) if p < input_count => {
+                    let signal = circuit.inputs[p];
+                    circuit.names[signal] = name;
+                }
+                (Some('l'), Some(p)) if p < latch_count => {
+                    circuit.names[latch_literals[p].0 / 2] = name;
+                }
+                (Some('o'), Some(p)) if p < output_count => output_names[p] = name,
+                (Some('b'), Some(p)) if p < bad_count => output_names[output_count + p] = name,
+                _ => (),
+            }
+        }
+
+        for (lit, next, init) in latch_literals {
+            let input = circuit.literal_signal(next);
+            circuit.latches.push(LatchDefinition {
+                output: lit / 2,
+                input,
+                init,
+            });
+        }
+        for (name, lit) in output_names.into_iter().zip(output_literals) {
+            let signal = circuit.literal_signal(lit);
+            circuit.outputs.push((name, signal));
+        }
+        circuit.check_defined()?;
+        Ok(circuit)
+    }
+
+    /// The signal computing the AIGER literal `lit` (an inverter is added for negative literals).
+    fn literal_signal(&mut self, lit: usize) -> usize {
+        if lit & 1 == 0 {
+            return lit / 2;
+        }
+        let name = format!("!{}", self.names[lit / 2]);
+        let signal = self.add_signal(name);
+        self.definitions[signal] = Some(Definition::Gate(Gate {
+            fanin: vec![lit / 2],
+            cubes: vec![vec![Some(false)]],
+            on_set: true,
+        }));
+        signal
+    }
+
+    fn check_defined(&self) -> Result<()> {
+        match self.definitions.iter().position(|d| d.is_none()) {
+            Some(signal) => {
+                let message = format!("Signal `{}` is not defined.", self.names[signal]);
+                Err(invalid_data(message))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Create the BDDs of the outputs and of the next-state functions of the circuit.
+    /// New variables are created for the inputs and the latches (in this order).
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager.
+    pub unsafe fn build(
+        &self,
+        manager: *mut DdManager,
+        options: &CircuitOptions,
+    ) -> Result<CircuitBdds> {
+        let mut previous = Cudd_ReorderingType::CUDD_REORDER_NONE;
+        let was_enabled = Cudd_ReorderingStatus(manager, &mut previous) != 0;
+        match options.reordering {
+            Some(method) => {
+                Cudd_AutodynEnable(manager, method);
+            }
+            None => {
+                Cudd_AutodynDisable(manager);
+            }
+        }
+        let mut nodes: Vec<*mut DdNode> = vec![std::ptr::null_mut(); self.names.len()];
+        let result = self.build_nodes(manager, options, &mut nodes);
+        for node in nodes.into_iter().filter(|n| !n.is_null()) {
+            Cudd_RecursiveDeref(manager, node);
+        }
+        if was_enabled {
+            Cudd_AutodynEnable(manager, previous);
+        } else {
+            Cudd_AutodynDisable(manager);
+        }
+        result
+    }
+
+    /// Build the BDDs, keeping a reference to the node of every evaluated signal in `nodes`.
+    unsafe fn build_nodes(
+        &self,
+        manager: *mut DdManager,
+        options: &CircuitOptions,
+        nodes: &mut [*mut DdNode],
+    ) -> Result<CircuitBdds> {
+        let new_var = |nodes: &mut [*mut DdNode], signal: usize| -> Result<c_int> {
+            let var = check_node(Cudd_bddNewVar(manager))?;
+            Cudd_Ref(var);
+            nodes[signal] = var;
+            Ok(Cudd_NodeReadIndex(var) as c_int)
+        };
+        let mut inputs = Vec::new();
+        for signal in &self.inputs {
+            let index = new_var(nodes, *signal)?;
+            Cudd_bddSetPiVar(manager, index);
+            inputs.push((self.names[*signal].clone(), index));
+        }
+        let mut latch_vars = Vec::new();
+        for latch in &self.latches {
+            let present = new_var(nodes, latch.output)?;
+            let next = if options.next_state_vars {
+                let next = Cudd_NodeReadIndex(check_node(Cudd_bddNewVar(manager))?) as c_int;
+                Cudd_bddSetPsVar(manager, present);
+                Cudd_bddSetNsVar(manager, next);
+                Cudd_bddSetPairIndex(manager, present, next);
+                Cudd_bddSetPairIndex(manager, next, present);
+                Some(next)
+            } else {
+                None
+            };
+            latch_vars.push((present, next));
+        }
+
+        let mut result = CircuitBdds {
+            inputs,
+            latches: Vec::new(),
+            outputs: Vec::new(),
+        };
+        for (latch, (present, next)) in self.latches.iter().zip(latch_vars) {
+            let function = match self.evaluate(manager, latch.input, nodes) {
+                Ok(function) => function,
+                Err(error) => {
+                    result.release(manager);
+                    return Err(error);
+                }
+            };
+            Cudd_Ref(function);
+            result.latches.push(LatchBdds {
+                name: self.names[latch.output].clone(),
+                present,
+                next,
+                function,
+                init: latch.init,
+            });
+        }
+        for (name, signal) in &self.outputs {
+            let function = match self.evaluate(manager, *signal, nodes) {
+                Ok(function) => function,
+                Err(error) => {
+                    result.release(manager);
+                    return Err(error);
+                }
+            };
+            Cudd_Ref(function);
+            result.outputs.push((name.clone(), function));
+        }
+        Ok(result)
+    }
+
+    /// Compute the BDD of the `signal` (and of all signals it depends on).
+    unsafe fn evaluate(
+        &self,
+        manager: *mut DdManager,
+        signal: usize,
+        nodes: &mut [*mut DdNode],
+    ) -> Result<*mut DdNode> {
+        // Depth-first traversal with an explicit stack, so that deep circuits do not
+        // overflow the call stack. A signal is on the stack at most twice: before and
+        // after its fanin is evaluated.
+        let mut visiting = vec![false; self.names.len()];
+        let mut stack = vec![(signal, false)];
+        while let Some((current, expanded)) = stack.pop() {
+            if !nodes[current].is_null() {
+                continue;
+            }
+            let gate = match self.definitions[current] {
+                Some(Definition::Gate(ref gate)) => gate,
+                _ => unreachable!("Inputs and latches are created before the evaluation."),
+            };
+            if expanded {
+                let node = gate_node(manager, gate, nodes)?;
+                nodes[current] = node;
+                continue;
+            }
+            if visiting[current] {
+                let message = format!(
+                    "Signal `{}` is in a combinational cycle.",
+                    self.names[current]
+                );
+                return Err(invalid_data(message));
+            }
+            visiting[current] = true;
+            stack.push((current, true));
+            for input in &gate.fanin {
+                if nodes[*input].is_null() {
+                    if visiting[*input] {
+                        let message = format!(
+                            "Signal `{}` is in a combinational cycle.",
+                            self.names[*input]
+                        );
+                        return Err(invalid_data(message));
+                    }
+                    stack.push((*input, false));
+                }
+            }
+        }
+        Ok(nodes[signal])
+    }
+}
+
+fn check_node(node: *mut DdNode) -> Result<*mut DdNode> {
+    if node.is_null() {
+        Err(Error::new(ErrorKind::Other, "CUDD ran out of memory."))
+    } else {
+        Ok(node)
+    }
+}
+
+/// Build the (referenced) BDD of a gate whose fanin is already evaluated.
+unsafe fn gate_node(
+    manager: *mut DdManager,
+    gate: &Gate,
+    nodes: &[*mut DdNode],
+) -> Result<*mut DdNode> {
+    let mut sum = Cudd_ReadLogicZero(manager);
+    Cudd_Ref(sum);
+    for cube in &gate.cubes {
+        let mut product = Cudd_ReadOne(manager);
+        Cudd_Ref(product);
+        for (input, literal) in gate.fanin.iter().zip(cube) {
+            let node = match *literal {
+                Some(true) => nodes[*input],
+                Some(false) => Cudd_Not(nodes[*input]),
+                None => continue,
+            };
+            let next = Cudd_bddAnd(manager, product, node);
+            if !next.is_null() {
+                Cudd_Ref(next);
+            }
+            Cudd_RecursiveDeref(manager, product);
+            if next.is_null() {
+                Cudd_RecursiveDeref(manager, sum);
+            }
+            product = check_node(next)?;
+        }
+        let next = Cudd_bddOr(manager, sum, product);
+        if !next.is_null() {
+            Cudd_Ref(next);
+        }
+        Cudd_RecursiveDeref(manager, sum);
+        Cudd_RecursiveDeref(manager, product);
+        sum = check_node(next)?;
+    }
+    Ok(if gate.on_set { sum } else { Cudd_Not(sum) })
+}
+
+/// Reads the lines, tokens and binary numbers of an AIGER file.
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn line(&mut self) -> Option<String> {
+        if self.position >= self.bytes.len() {
+            return None;
+        }
+        Some(self.rest_of_line())
+    }
+
+    /// The rest of the current line (without the line break).
+    fn rest_of_line(&mut self) -> String {
+        let rest = &self.bytes[self.position.min(self.bytes.len())..];
+        let length = rest.iter().position(|b| *b == b'\n').unwrap_or(rest.len());
+        self.position += (length + 1).min(rest.len());
+        String::from_utf8_lossy(&rest[..length]).trim().to_string()
+    }
+
+    /// The next token on the current line.
+    fn token(&mut self) -> Result<String> {
+        while self.bytes.get(self.position) == Some(&b' ') {
+            self.position += 1;
+        }
+        let start = self.position;
+        while let Some(byte) = self.bytes.get(self.position) {
+            if byte.is_ascii_whitespace() {
+                break;
+            }
+            self.position += 1;
+        }
+        if start == self.position {
+            return Err(invalid_data("Unexpected end of line."));
+        }
+        Ok(String::from_utf8_lossy(&self.bytes[start..self.position]).to_string())
+    }
+
+    /// A delta of the binary AND gates: 7 bits per byte, least significant group first,
+    /// with the highest bit set on all bytes but the last one.
+    fn delta(&mut self) -> Result<usize> {
+        let mut value = 0usize;
+        for shift in (0..).step_by(7).take(10) {
+            let byte = *self
+                .bytes
+                .get(self.position)
+                .ok_or_else(|| invalid_data("Unexpected end of AIGER file."))?;
+            self.position += 1;
+            value |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid_data("Invalid AIGER delta."))
+    }
+}
@@ -0,0 +1,871 @@
+use cudd::*;
+use libc::c_int;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use std::str::FromStr;
+use transition::{literals_cube, TraceStep, TransitionSystem};
+use {DdManager, DdNode};
+
+/// A formula of the computation tree logic (CTL) over named state variables.
+///
+/// The textual syntax extends the syntax of `Expr` (without `^` and quantifiers): from the
+/// lowest to the highest precedence, `<->`, `->` (right associative), `|`, `&` and the
+/// unary operators `!`, `EX`, `AX`, `EF`, `AF`, `EG` and `AG`. The until operators are
+/// written as `E[f U g]` and `A[f U g]`. The constants are `0`/`false` and `1`/`true`.
+/// Variable names start with a letter or `_` and contain letters, digits, `_`, `'` and `.`;
+/// the names of the operators (including `E`, `A` and `U`) are reserved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Ctl {
+    /// A constant (`0` or `1`).
+    Const(bool),
+    /// A state variable.
+    Var(String),
+    /// A negation (`!f`).
+    Not(Box<Ctl>),
+    /// A conjunction (`f & g`).
+    And(Box<Ctl>, Box<Ctl>),
+    /// A disjunction (`f | g`).
+    Or(Box<Ctl>, Box<Ctl>),
+    /// An implication (`f -> g`).
+    Implies(Box<Ctl>, Box<Ctl>),
+    /// An equivalence (`f <-> g`).
+    Iff(Box<Ctl>, Box<Ctl>),
+    /// Some successor satisfies the formula (`EX f`).
+    Ex(Box<Ctl>),
+    /// All successors satisfy the formula (`AX f`).
+    Ax(Box<Ctl>),
+    /// The formula holds eventually on some path (`EF f`).
+    Ef(Box<Ctl>),
+    /// The formula holds eventually on all paths (`AF f`).
+    Af(Box<Ctl>),
+    /// The formula holds globally on some path (`EG f`).
+    Eg(Box<Ctl>),
+    /// The formula holds globally on all paths (`AG f`).
+    Ag(Box<Ctl>),
+    /// The first formula holds until the second one holds on some path (`E[f U g]`).
+    Eu(Box<Ctl>, Box<Ctl>),
+    /// The first formula holds until the second one holds on all paths (`A[f U g]`).
+    Au(Box<Ctl>, Box<Ctl>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Const(bool),
+    Not,
+    And,
+    Or,
+    Implies,
+    Iff,
+    Ex,
+    Ax,
+    Ef,
+    Af,
+    Eg,
+    Ag,
+    E,
+    A,
+    Until,
+    Open,
+    Close,
+    OpenBracket,
+    CloseBracket,
+}
+
+fn invalid_input<S: Into<String>>(message: S) -> Error {
+    Error::new(ErrorKind::InvalidInput, message.into())
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_'.".contains(c)
+}
+
+/// Split the formula into tokens, each with its (byte) position.
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        let token = match c {
+            _ if c.is_whitespace() => continue,
+            '!' | '~' => Token::Not,
+            '&' => Token::And,
+            '|' => Token::Or,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '[' => Token::OpenBracket,
+            ']' => Token::CloseBracket,
+            '-' if text[position..].starts_with("->") => {
+                chars.next();
+                Token::Implies
+            }
+            '<' if text[position..].starts_with("<->") => {
+                chars.next();
+                chars.next();
+                Token::Iff
+            }
+            '0' | '1' if !text[position + 1..].starts_with(is_name_char) => Token::Const(c == '1'),
+            _ if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = position + c.len_utf8();
+                while let Some(&(next, c)) = chars.peek() {
+                    if !is_name_char(c) {
+                        break;
+                    }
+                    end = next + c.len_utf8();
+                    chars.next();
+                }
+                match &text[position..end] {
+                    "true" => Token::Const(true),
+                    "false" => Token::Const(false),
+                    "EX" => Token::Ex,
+                    "AX" => Token::Ax,
+                    "EF" => Token::Ef,
+                    "AF" => Token::Af,
+                    "EG" => Token::Eg,
+                    "AG" => Token::Ag,
+                    "E" => Token::E,
+                    "A" => Token::A,
+                    "U" => Token::Until,
+                    name => Token::Ident(name.to_string()),
+                }
+            }
+            _ => {
+                let message = format!("Unexpected `{}` at position {}.", c, position);
+                return Err(invalid_input(message));
+            }
+        };
+        tokens.push((position, token));
+    }
+    Ok(tokens)
+}
+
+/// A recursive descent parser over the tokens of a formula.
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    length: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn error(&self) -> Error {
+        match self.tokens.get(self.position) {
+            Some((position, _)) => {
+                invalid_input(format!("Unexpected token at position {}.", position))
+            }
+            None => invalid_input(format!(
+                "Unexpected end of formula at position {}.",
+                self.length
+            )),
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> Result<()> {
+        if self.peek() == Some(&token) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    fn iff(&mut self) -> Result<Ctl> {
+        let mut left = self.implies()?;
+        while self.peek() == Some(&Token::Iff) {
+            self.position += 1;
+            left = Ctl::Iff(Box::new(left), Box::new(self.implies()?));
+        }
+        Ok(left)
+    }
+
+    fn implies(&mut self) -> Result<Ctl> {
+        let left = self.or()?;
+        if self.peek() == Some(&Token::Implies) {
+            self.position += 1;
+            return Ok(Ctl::Implies(Box::new(left), Box::new(self.implies()?)));
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Ctl> {
+        let mut left = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            left = Ctl::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Ctl> {
+        let mut left = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.position += 1;
+            left = Ctl::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Ctl> {
+        let token = self.peek().cloned().ok_or_else(|| self.error())?;
+        self.position += 1;
+        let operator: fn(Box<Ctl>) -> Ctl = match token {
+            Token::Not => Ctl::Not,
+            Token::Ex => Ctl::Ex,
+            Token::Ax => Ctl::Ax,
+            Token::Ef => Ctl::Ef,
+            Token::Af => Ctl::Af,
+            Token::Eg => Ctl::Eg,
+            Token::Ag => Ctl::Ag,
+            Token::E | Token::A => {
+                self.expect(Token::OpenBracket)?;
+                let left = Box::new(self.iff()?);
+                self.expect(Token::Until)?;
+                let right = Box::new(self.iff()?);
+                self.expect(Token::CloseBracket)?;
+                return Ok(if token == Token::E {
+                    Ctl::Eu(left, right)
+                } else {
+                    Ctl::Au(left, right)
+                });
+            }
+            Token::Open => {
+                let formula = self.iff()?;
+                self.expect(Token::Close)?;
+                return Ok(formula);
+            }
+            Token::Ident(name) => return Ok(Ctl::Var(name)),
+            Token::Const(value) => return Ok(Ctl::Const(value)),
+            _ => {
+                self.position -= 1;
+                return Err(self.error());
+            }
+        };
+        Ok(operator(Box::new(self.unary()?)))
+    }
+}
+
+impl Ctl {
+    /// Parse a formula.
+    pub fn parse(text: &str) -> Result<Ctl> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+            length: text.len(),
+        };
+        let formula = parser.iff()?;
+        if parser.position < parser.tokens.len() {
+            return Err(parser.error());
+        }
+        Ok(formula)
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Ctl::Iff(..) => 1,
+            Ctl::Implies(..) => 2,
+            Ctl::Or(..) => 3,
+            Ctl::And(..) => 4,
+            Ctl::Not(..)
+            | Ctl::Ex(..)
+            | Ctl::Ax(..)
+            | Ctl::Ef(..)
+            | Ctl::Af(..)
+            | Ctl::Eg(..)
+            | Ctl::Ag(..) => 5,
+            Ctl::Const(..) | Ctl::Var(..) | Ctl::Eu(..) | Ctl::Au(..) => 6,
+        }
+    }
+}
+
+impl FromStr for Ctl {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Ctl> {
+        Ctl::parse(text)
+    }
+}
+
+impl fmt::Display for Ctl {
+    /// Print the formula in the syntax accepted by `Ctl::parse`, with as few parentheses
+    /// as possible.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operand = |f: &mut fmt::Formatter, formula: &Ctl, min: u8| {
+            if formula.precedence() < min {
+                write!(f, "({})", formula)
+            } else {
+                write!(f, "{}", formula)
+            }
+        };
+        let (left, right, operator) = match self {
+            Ctl::Const(value) => return write!(f, "{}", if *value { "1" } else { "0" }),
+            Ctl::Var(name) => return write!(f, "{}", name),
+            Ctl::Eu(left, right) => return write!(f, "E[{} U {}]", left, right),
+            Ctl::Au(left, right) => return write!(f, "A[{} U {}]", left, right),
+            Ctl::Not(inner)
+            | Ctl::Ex(inner)
+            | Ctl::Ax(inner)
+            | Ctl::Ef(inner)
+            | Ctl::Af(inner)
+            | Ctl::Eg(inner)
+            | Ctl::Ag(inner) => {
+                let operator = match self {
+                    Ctl::Not(..) => "!",
+                    Ctl::Ex(..) => "EX ",
+                    Ctl::Ax(..) => "AX ",
+                    Ctl::Ef(..) => "EF ",
+                    Ctl::Af(..) => "AF ",
+                    Ctl::Eg(..) => "EG ",
+                    _ => "AG ",
+                };
+                write!(f, "{}", operator)?;
+                return operand(f, inner, 5);
+            }
+            Ctl::And(left, right) => (left, right, "&"),
+            Ctl::Or(left, right) => (left, right, "|"),
+            Ctl::Implies(left, right) => (left, right, "->"),
+            Ctl::Iff(left, right) => (left, right, "<->"),
+        };
+        // Left associative operators need parentheses on the right (and `->` on the left).
+        let precedence = self.precedence();
+        let (left_min, right_min) = match self {
+            Ctl::Implies(..) => (precedence + 1, precedence),
+            _ => (precedence, precedence + 1),
+        };
+        operand(f, left, left_min)?;
+        write!(f, " {} ", operator)?;
+        operand(f, right, right_min)
+    }
+}
+
+/// A path of a transition system which demonstrates a formula (see `CtlChecker::witness`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CtlTrace {
+    /// The states of the path, starting with an initial state. The inputs of every step lead
+    /// to the next state (or back to the start of the loop for the last state of a loop).
+    pub steps: Vec<TraceStep>,
+    /// If the path ends in a loop (for `EG` formulas), the position of the state to which
+    /// the last state returns.
+    pub loop_start: Option<usize>,
+}
+
+/// A referenced BDD which is dereferenced when dropped.
+struct Bdd {
+    manager: *mut DdManager,
+    node: *mut DdNode,
+}
+
+impl Bdd {
+    /// Reference the result of a CUDD operation, or fail if CUDD ran out of memory.
+    unsafe fn new(manager: *mut DdManager, node: *mut DdNode) -> Result<Bdd> {
+        if node.is_null() {
+            return Err(Error::new(ErrorKind::Other, "CUDD ran out of memory."));
+        }
+        Cudd_Ref(node);
+        Ok(Bdd { manager, node })
+    }
+
+    /// Take the ownership of an already referenced node.
+    fn from_referenced(manager: *mut DdManager, node: *mut DdNode) -> Bdd {
+        Bdd { manager, node }
+    }
+
+    unsafe fn constant(manager: *mut DdManager, value: bool) -> Bdd {
+        let node = if value {
+            Cudd_ReadOne(manager)
+        } else {
+            Cudd_ReadLogicZero(manager)
+        };
+        Cudd_Ref(node);
+        Bdd { manager, node }
+    }
+
+    unsafe fn not(&self) -> Bdd {
+        let node = Cudd_Not(self.node);
+        Cudd_Ref(node);
+        Bdd::from_referenced(self.manager, node)
+    }
+
+    unsafe fn and(&self, other: &Bdd) -> Result<Bdd> {
+        Bdd::new(
+            self.manager,
+            Cudd_bddAnd(self.manager, self.node, other.node),
+        )
+    }
+
+    unsafe fn or(&self, other: &Bdd) -> Result<Bdd> {
+        Bdd::new(
+            self.manager,
+            Cudd_bddOr(self.manager, self.node, other.node),
+        )
+    }
+
+    unsafe fn is_zero(&self) -> bool {
+        self.node == Cudd_ReadLogicZero(self.manager)
+    }
+
+    unsafe fn leq(&self, other: &Bdd) -> bool {
+        Cudd_bddLeq(self.manager, self.node, other.node) != 0
+    }
+
+    fn into_raw(self) -> *mut DdNode {
+        let node = self.node;
+        std::mem::forget(self);
+        node
+    }
+}
+
+impl Clone for Bdd {
+    fn clone(&self) -> Bdd {
+        unsafe { Cudd_Ref(self.node) };
+        Bdd::from_referenced(self.manager, self.node)
+    }
+}
+
+impl Drop for Bdd {
+    fn drop(&mut self) {
+        unsafe { Cudd_RecursiveDeref(self.manager, self.node) };
+    }
+}
+
+fn no_witness() -> Error {
+    Error::new(ErrorKind::Other, "The witness cannot be completed.")
+}
+
+/// A CTL model checker over the Kripke structure given by a `TransitionSystem`: the states
+/// are the valuations of the present-state variables and there is a transition between two
+/// states if the relation allows it for some inputs.
+///
+/// The temporal operators are computed by the usual fixpoints over preimages (`EX`, `EU`
+/// and `EG`, from which the other operators are derived). With fairness constraints, the
+/// path quantifiers only range over the fair paths, which visit every constraint infinitely
+/// often (`EG` is computed using the Emerson-Lei fixpoint).
+///
+/// The BDDs of the checker are dereferenced when it is dropped.
+pub struct CtlChecker<'a> {
+    system: &'a TransitionSystem,
+    manager: *mut DdManager,
+    names: HashMap<String, c_int>,
+    fairness: Vec<Bdd>,
+    // The states from which some fair path starts.
+    fair: Bdd,
+}
+
+impl<'a> CtlChecker<'a> {
+    /// A checker of the `system`, where `names[i]` is the name of the variable with index
+    /// `i`. Only the present-state variables can be used in the formulas.
+    ///
+    /// # Safety
+    ///
+    /// The manager of the system must be valid until the checker is dropped.
+    pub unsafe fn new<S: AsRef<str>>(
+        system: &'a TransitionSystem,
+        names: &[S],
+    ) -> Result<CtlChecker<'a>> {
+        let mut indices = HashMap::new();
+        for (index, name) in names.iter().enumerate() {
+            if indices
+                .insert(name.as_ref().to_string(), index as c_int)
+                .is_some()
+            {
+                let message = format!("Name `{}` is used more than once.", name.as_ref());
+                return Err(invalid_input(message));
+            }
+        }
+        let manager = system.manager();
+        Ok(CtlChecker {
+            system,
+            manager,
+            names: indices,
+            fairness: Vec::new(),
+            fair: Bdd::constant(manager, true),
+        })
+    }
+
+    /// Restrict the checker to the fair paths, which visit the states satisfying every one
+    /// of the `constraints` infinitely often. The constraints are evaluated without
+    /// fairness.
+    ///
+    /// # Safety
+    ///
+    /// Same as `CtlChecker::new`.
+    pub unsafe fn with_fairness(mut self, constraints: &[Ctl]) -> Result<CtlChecker<'a>> {
+        self.fairness.clear();
+        self.fair = Bdd::constant(self.manager, true);
+        let mut fairness = Vec::new();
+        for constraint in constraints {
+            fairness.push(self.eval(constraint)?);
+        }
+        self.fairness = fairness;
+        self.fair = self.eg(&Bdd::constant(self.manager, true))?;
+        Ok(self)
+    }
+
+    /// The (referenced) set of states which satisfy the `formula`.
+    ///
+    /// # Safety
+    ///
+    /// The manager of the system must still be valid.
+    pub unsafe fn states(&self, formula: &Ctl) -> Result<*mut DdNode> {
+        Ok(self.eval(formula)?.into_raw())
+    }
+
+    /// The (referenced) set of states from which some fair path starts (all states which
+    /// have an infinite path if there are no fairness constraints).
+    ///
+    /// # Safety
+    ///
+    /// The manager of the system must still be valid.
+    pub unsafe fn fair_states(&self) -> *mut DdNode {
+        self.fair.clone().into_raw()
+    }
+
+    /// True if all initial states of the system satisfy the `formula`.
+    ///
+    /// # Safety
+    ///
+    /// The manager of the system must still be valid.
+    pub unsafe fn holds(&self, formula: &Ctl) -> Result<bool> {
+        Ok(self.init()?.leq(&self.eval(formula)?))
+    }
+
+    /// A path from an initial state which satisfies the `formula`, showing why the formula
+    /// holds, or `None` if no initial state satisfies it.
+    ///
+    /// The path follows the outermost existential operators of the formula (after pushing
+    /// negations inwards, so that `!AG f` is explained as `EF !f`): `EX` and `EU` (`EF`)
+    /// add a shortest path to a state satisfying the argument, which is then explained
+    /// further, and `EG` adds a loop which visits all fairness constraints. Only the first
+    /// temporal conjunct of a conjunction is explained. The states along the fixpoint rings
+    /// are chosen using `Cudd_bddPickOneMinterm`.
+    ///
+    /// # Safety
+    ///
+    /// The manager of the system must still be valid.
+    pub unsafe fn witness(&self, formula: &Ctl) -> Result<Option<CtlTrace>> {
+        let initial = self.eval(formula)?.and(&self.init()?)?;
+        if initial.is_zero() {
+            return Ok(None);
+        }
+        let state = self.pick(&initial)?;
+        let (states, loop_start) = self.explain(formula, state)?;
+        let mut steps = Vec::new();
+        for (i, state) in states.iter().enumerate() {
+            let successor = match states.get(i + 1) {
+                Some(successor) => Some(successor),
+                None => loop_start.map(|start| &states[start]),
+            };
+            let inputs = match successor {
+                Some(successor) => {
+                    let next = literals_cube(self.manager, self.system.next_vars(), successor)?;
+                    let next = Bdd::from_referenced(self.manager, next);
+                    self.system.transition_inputs(state, next.node)?
+                }
+                None => Vec::new(),
+            };
+            steps.push(TraceStep {
+                state: state.clone(),
+                inputs,
+            });
+        }
+        Ok(Some(CtlTrace { steps, loop_start }))
+    }
+
+    /// A path from an initial state which violates the `formula` (a witness of its
+    /// negation), or `None` if the formula holds.
+    ///
+    /// # Safety
+    ///
+    /// The manager of the system must still be valid.
+    pub unsafe fn counterexample(&self, formula: &Ctl) -> Result<Option<CtlTrace>> {
+        self.witness(&Ctl::Not(Box::new(formula.clone())))
+    }
+
+    unsafe fn init(&self) -> Result<Bdd> {
+        Bdd::new(self.manager, self.system.init())
+    }
+
+    unsafe fn eval(&self, formula: &Ctl) -> Result<Bdd> {
+        let manager = self.manager;
+        let not = |f: &Ctl| -> Result<Bdd> { Ok(self.eval(f)?.not()) };
+        match formula {
+            Ctl::Const(value) => Ok(Bdd::constant(manager, *value)),
+            Ctl::Var(name) => {
+                let index = match self.names.get(name) {
+                    Some(index) => *index,
+                    None => return Err(invalid_input(format!("Unknown variable `{}`.", name))),
+                };
+                if !self.system.present_vars().contains(&index) {
+                    let message = format!("Variable `{}` is not a present-state variable.", name);
+                    return Err(invalid_input(message));
+                }
+                Bdd::new(manager, Cudd_bddIthVar(manager, index))
+            }
+            Ctl::Not(f) => not(f),
+            Ctl::And(f, g) => self.eval(f)?.and(&self.eval(g)?),
+            Ctl::Or(f, g) => self.eval(f)?.or(&self.eval(g)?),
+            Ctl::Implies(f, g) => not(f)?.or(&self.eval(g)?),
+            Ctl::Iff(f, g) => {
+                let (f, g) = (self.eval(f)?, self.eval(g)?);
+                Bdd::new(manager, Cudd_bddXnor(manager, f.node, g.node))
+            }
+            Ctl::Ex(f) => self.ex(&self.eval(f)?),
+            Ctl::Ax(f) => Ok(self.ex(&not(f)?)?.not()),
+            Ctl::Ef(f) => self.eu(&Bdd::constant(manager, true), &self.eval(f)?),
+            Ctl::Af(f) => Ok(self.eg(&not(f)?)?.not()),
+            Ctl::Eg(f) => self.eg(&self.eval(f)?),
+            Ctl::Ag(f) => Ok(self.eu(&Bdd::constant(manager, true), &not(f)?)?.not()),
+            Ctl::Eu(f, g) => self.eu(&self.eval(f)?, &self.eval(g)?),
+            Ctl::Au(f, g) => {
+                // A[f U g] = !(E[!g U (!f & !g)] | EG !g)
+                let (not_f, not_g) = (not(f)?, not(g)?);
+                let blocked = self.eu(&not_g, &not_f.and(&not_g)?)?;
+                Ok(blocked.or(&self.eg(&not_g)?)?.not())
+            }
+        }
+    }
+
+    unsafe fn preimage(&self, states: &Bdd) -> Result<Bdd> {
+        let node = self.system.preimage(states.node)?;
+        Ok(Bdd::from_referenced(self.manager, node))
+    }
+
+    unsafe fn image(&self, states: &Bdd) -> Result<Bdd> {
+        let node = self.system.image(states.node)?;
+        Ok(Bdd::from_referenced(self.manager, node))
+    }
+
+    /// The states with a (fair) successor in `f`.
+    unsafe fn ex(&self, f: &Bdd) -> Result<Bdd> {
+        self.preimage(&f.and(&self.fair)?)
+    }
+
+    /// The states with a (fair) path through `f` to `g`.
+    unsafe fn eu(&self, f: &Bdd, g: &Bdd) -> Result<Bdd> {
+        self.until(f, &g.and(&self.fair)?)
+    }
+
+    /// The least fixpoint of `E[f U g]` (without fairness).
+    unsafe fn until(&self, f: &Bdd, g: &Bdd) -> Result<Bdd> {
+        let mut reached = g.clone();
+        let mut frontier = g.clone();
+        loop {
+            let new = f.and(&self.preimage(&frontier)?)?.and(&reached.not())?;
+            if new.is_zero() {
+                return Ok(reached);
+            }
+            reached = reached.or(&new)?;
+            frontier = new;
+        }
+    }
+
+    /// The greatest fixpoint of `EG f`, using the Emerson-Lei algorithm with fairness.
+    unsafe fn eg(&self, f: &Bdd) -> Result<Bdd> {
+        let mut z = f.clone();
+        loop {
+            let mut next = f.clone();
+            if self.fairness.is_empty() {
+                next = next.and(&self.preimage(&z)?)?;
+            }
+            for constraint in &self.fairness {
+                let target = z.and(constraint)?;
+                next = next.and(&self.preimage(&self.until(f, &target)?)?)?;
+            }
+            if next.node == z.node {
+                return Ok(z);
+            }
+            z = next;
+        }
+    }
+
+    /// The values of the present-state variables in one state of the (non-empty) set.
+    unsafe fn pick(&self, states: &Bdd) -> Result<Vec<bool>> {
+        let manager = self.manager;
+        let present = self.system.present_vars();
+        let mut vars: Vec<*mut DdNode> = present
+            .iter()
+            .map(|i| Cudd_bddIthVar(manager, *i))
+            .collect();
+        let minterm =
+            Cudd_bddPickOneMinterm(manager, states.node, vars.as_mut_ptr(), vars.len() as c_int);
+        let minterm = Bdd::new(manager, minterm)?;
+        Ok(vars
+            .iter()
+            .map(|var| Cudd_bddLeq(manager, minterm.node, *var) != 0)
+            .collect())
+    }
+
+    unsafe fn state(&self, state: &[bool]) -> Result<Bdd> {
+        Ok(Bdd::from_referenced(
+            self.manager,
+            self.system.state(state)?,
+        ))
+    }
+
+    unsafe fn satisfies(&self, state: &[bool], formula: &Ctl) -> Result<bool> {
+        Ok(self.state(state)?.leq(&self.eval(formula)?))
+    }
+
+    /// A shortest path of at least one step from the state `from` to a `target` state, where
+    /// all states except for the last one are in `within`, or `None` if there is no such
+    /// path. The path does not include `from`.
+    unsafe fn path(
+        &self,
+        from: &[bool],
+        within: &Bdd,
+        target: &Bdd,
+    ) -> Result<Option<Vec<Vec<bool>>>> {
+        let mut rings: Vec<Bdd> = Vec::new();
+        let mut visited = Bdd::constant(self.manager, false);
+        let mut source = self.state(from)?;
+        loop {
+            let ring = self.image(&source)?.and(&visited.not())?;
+            if ring.is_zero() {
+                return Ok(None);
+            }
+            visited = visited.or(&ring)?;
+            let hit = ring.and(target)?;
+            source = ring.and(within)?;
+            rings.push(ring);
+            if hit.is_zero() {
+                continue;
+            }
+            // Walk back through the rings, picking a predecessor in every ring.
+            let mut state = self.pick(&hit)?;
+            let mut path = vec![state.clone()];
+            for ring in rings[..rings.len() - 1].iter().rev() {
+                let predecessors = self.preimage(&self.state(&state)?)?;
+                state = self.pick(&ring.and(within)?.and(&predecessors)?)?;
+                path.push(state.clone());
+            }
+            path.reverse();
+            return Ok(Some(path));
+        }
+    }
+
+    /// A path from the `state` (which satisfies the `formula`) which explains the formula,
+    /// and the start of its loop.
+    unsafe fn explain(
+        &self,
+        formula: &Ctl,
+        state: Vec<bool>,
+    ) -> Result<(Vec<Vec<bool>>, Option<usize>)> {
+        let boxed = |formula: &Ctl| Box::new(formula.clone());
+        let negated = |formula: &Ctl| Box::new(Ctl::Not(boxed(formula)));
+        let manager = self.manager;
+        match formula {
+            Ctl::Ex(f) => {
+                let successors = self.image(&self.state(&state)?)?;
+                let target = self.eval(f)?.and(&self.fair)?;
+                let next = self.pick(&successors.and(&target)?)?;
+                self.extend(vec![state], f, next)
+            }
+            Ctl::Ef(g) => self.explain_until(&Bdd::constant(manager, true), g, state),
+            Ctl::Eu(f, g) => self.explain_until(&self.eval(f)?, g, state),
+            Ctl::Eg(f) => self.explain_globally(f, state),
+            Ctl::And(f, g) => {
+                let (path, loop_start) = self.explain(f, state.clone())?;
+                if path.len() == 1 && loop_start.is_none() {
+                    self.explain(g, state)
+                } else {
+                    Ok((path, loop_start))
+                }
+            }
+            Ctl::Or(f, g) => {
+                if self.satisfies(&state, f)? {
+                    self.explain(f, state)
+                } else {
+                    self.explain(g, state)
+                }
+            }
+            Ctl::Implies(f, g) => self.explain(&Ctl::Or(negated(f), g.clone()), state),
+            Ctl::Not(inner) => {
+                let formula = match &**inner {
+                    Ctl::Not(f) => return self.explain(f, state),
+                    Ctl::And(f, g) => Ctl::Or(negated(f), negated(g)),
+                    Ctl::Or(f, g) => Ctl::And(negated(f), negated(g)),
+                    Ctl::Implies(f, g) => Ctl::And(f.clone(), negated(g)),
+                    Ctl::Ax(f) => Ctl::Ex(negated(f)),
+                    Ctl::Af(f) => Ctl::Eg(negated(f)),
+                    Ctl::Ag(f) => Ctl::Ef(negated(f)),
+                    Ctl::Au(f, g) => {
+                        let blocked = Ctl::And(negated(f), negated(g));
+                        let blocked = Ctl::Eu(negated(g), Box::new(blocked));
+                        if self.satisfies(&state, &blocked)? {
+                            blocked
+                        } else {
+                            Ctl::Eg(negated(g))
+                        }
+                    }
+                    _ => return Ok((vec![state], None)),
+                };
+                self.explain(&formula, state)
+            }
+            _ => Ok((vec![state], None)),
+        }
+    }
+
+    /// Explain `E[f U g]` by a shortest path through `f` to a (fair) state satisfying `g`.
+    unsafe fn explain_until(
+        &self,
+        f: &Bdd,
+        g: &Ctl,
+        state: Vec<bool>,
+    ) -> Result<(Vec<Vec<bool>>, Option<usize>)> {
+        let target = self.eval(g)?.and(&self.fair)?;
+        if self.state(&state)?.leq(&target) {
+            return self.explain(g, state);
+        }
+        let mut path = self.path(&state, f, &target)?.ok_or_else(no_witness)?;
+        let last = path.pop().ok_or_else(no_witness)?;
+        let mut states = vec![state];
+        states.extend(path);
+        self.extend(states, g, last)
+    }
+
+    /// Append the explanation of the `formula` from the `last` state to the `states`.
+    unsafe fn extend(
+        &self,
+        mut states: Vec<Vec<bool>>,
+        formula: &Ctl,
+        last: Vec<bool>,
+    ) -> Result<(Vec<Vec<bool>>, Option<usize>)> {
+        let (rest, loop_start) = self.explain(formula, last)?;
+        let offset = states.len();
+        states.extend(rest);
+        Ok((states, loop_start.map(|start| start + offset)))
+    }
+
+    /// Explain `EG f` by a loop which stays in `EG f` and visits every fairness constraint.
+    /// If the loop cannot be closed, the search is restarted from the last state.
+    unsafe fn explain_globally(
+        &self,
+        f: &Ctl,
+        state: Vec<bool>,
+    ) -> Result<(Vec<Vec<bool>>, Option<usize>)> {
+        let globally = self.eg(&self.eval(f)?)?;
+        let mut targets = Vec::new();
+        for constraint in &self.fairness {
+            targets.push(globally.and(constraint)?);
+        }
+        if targets.is_empty() {
+            targets.push(globally.clone());
+        }
+        let mut states = vec![state];
+        loop {
+            let start = states.len() - 1;
+            for target in &targets {
+                let path = self.path(&states[states.len() - 1], &globally, target)?;
+                states.extend(path.ok_or_else(no_witness)?);
+            }
+            let first = self.state(&states[start])?;
+            if let Some(mut path) = self.path(&states[states.len() - 1], &globally, &first)? {
+                path.pop();
+                states.extend(path);
+                return Ok((states, Some(start)));
+            }
+        }
+    }
+}
@@ -64,6 +64,7 @@ pub const CUDD_RESIDUE_TC: c_int = 2;
 
 /// Types of variable reordering algorithms.
 #[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Cudd_ReorderingType {
     CUDD_REORDER_SAME,
     CUDD_REORDER_NONE,
@@ -115,6 +116,7 @@ pub enum Cudd_HookType {
 
 // Type of an error code.
 #[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Cudd_ErrorType {
     CUDD_NO_ERROR,
     CUDD_MEMORY_OUT,
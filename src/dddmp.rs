@@ -1,6 +1,53 @@
 use libc::{c_char, c_int, FILE};
 use {DdManager, DdNode};
 
+mod cnf;
+mod error;
+mod format;
+mod header;
+mod stream;
+
+#[cfg(feature = "serde")]
+mod serialization;
+
+pub use self::cnf::{CnfInfo, CnfVariable, CnfWriter};
+pub use self::error::DddmpError;
+pub use self::format::{DddmpEdge, DddmpFile, DddmpNode};
+pub use self::header::{read_header, read_header_file, DddmpHeader};
+pub use self::stream::{DddmpMode, DddmpReader, DddmpRoot, DddmpWriter};
+
+#[cfg(feature = "serde")]
+pub use self::serialization::{DddmpDiagram, DddmpSeed};
+
+/// If `expr` holds, returns `message` (together with the source location) as a
+/// `DddmpError::Format` from the enclosing function. The error is converted using `From`,
+/// like the `?` operator does.
+#[macro_export]
+macro_rules! Dddmp_CheckAndReturn {
+    ($expr:expr, $message:expr) => {
+        if $expr {
+            let message = format!("{} (file {}, line {})", $message, file!(), line!());
+            return Err(::std::convert::From::from(
+                $crate::dddmp::DddmpError::Format(message),
+            ));
+        }
+    };
+}
+
+/// If `expr` holds, breaks out of the `loop` with the given `label`, which then evaluates to
+/// `message` (together with the source location) as an `Err(DddmpError::Format)`. This is
+/// the counterpart of the `goto` used by the C macro: the clean-up code should follow the
+/// labelled loop.
+#[macro_export]
+macro_rules! Dddmp_CheckAndGotoLabel {
+    ($expr:expr, $message:expr, $label:lifetime) => {
+        if $expr {
+            let message = format!("{} (file {}, line {})", $message, file!(), line!());
+            break $label Err($crate::dddmp::DddmpError::Format(message));
+        }
+    };
+}
+
 /// Version of DDDMP format.
 pub const DDDMP_VERSION: &str = "DDDMP-2.0";
 
@@ -20,6 +67,7 @@ pub const DDDMP_MODE_BINARY: c_int = 'B' as c_int;
 pub const DDDMP_MODE_DEFAULT: c_int = 'D' as c_int;
 
 #[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Dddmp_DecompCnfStoreType {
     DDDMP_CNF_MODE_NODE,
     DDDMP_CNF_MODE_MAXTERM,
@@ -27,6 +75,7 @@ pub enum Dddmp_DecompCnfStoreType {
 }
 
 #[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Dddmp_DecompCnfLoadType {
     DDDMP_CNF_MODE_NO_CONJ,
     DDDMP_CNF_MODE_NO_QUANT,
@@ -34,6 +83,7 @@ pub enum Dddmp_DecompCnfLoadType {
 }
 
 #[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Dddmp_DecompType {
     DDDMP_BDD,
     DDDMP_ADD,
@@ -42,6 +92,7 @@ pub enum Dddmp_DecompType {
 }
 
 #[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Dddmp_VarInfoType {
     DDDMP_VARIDS,
     DDDMP_VARPERMIDS,
@@ -51,6 +102,7 @@ pub enum Dddmp_VarInfoType {
 }
 
 #[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Dddmp_VarMatchType {
     DDDMP_VAR_MATCHIDS,
     DDDMP_VAR_MATCHPERMIDS,
@@ -60,6 +112,7 @@ pub enum Dddmp_VarMatchType {
 }
 
 #[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Dddmp_RootMatchType {
     DDDMP_ROOT_MATCHNAMES,
     DDDMP_ROOT_MATCHLIST,
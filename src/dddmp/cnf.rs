@@ -0,0 +1,239 @@
+use cfile::CFile;
+use cudd::Cudd_ReadSize;
+use dddmp::stream::{c_strings, check_ids, check_length};
+use dddmp::{DddmpError, Dddmp_DecompCnfStoreType, Dddmp_cuddBddArrayStoreCnf, DDDMP_SUCCESS};
+use libc::c_int;
+use registry::NamedVars;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Write};
+use std::path::Path;
+use std::ptr::null_mut;
+use {DdManager, DdNode};
+
+/// Stores arrays of BDDs as CNF formulas in the DIMACS format
+/// (see `Dddmp_cuddBddArrayStoreCnf`). Use `DddmpReader::read_cnf` to load them back.
+///
+/// By default, the BDD variable with index `i` is the CNF variable `i + 1` and the
+/// auxiliary variables (introduced for the BDD nodes) follow the BDD variables.
+#[derive(Clone, Debug)]
+pub struct CnfWriter {
+    mode: Dddmp_DecompCnfStoreType,
+    header: bool,
+    var_names: Option<Vec<String>>,
+    aux_ids: Option<Vec<c_int>>,
+    cnf_ids: Option<Vec<c_int>>,
+    first_aux_id: Option<c_int>,
+    edge_threshold: c_int,
+    path_length_threshold: c_int,
+}
+
+/// The result of storing BDDs using a `CnfWriter`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CnfInfo {
+    /// The number of written clauses.
+    pub clause_count: usize,
+    /// The number of auxiliary variables introduced for the BDD nodes.
+    pub new_var_count: usize,
+    /// The CNF variable of every BDD variable, indexed by variable index.
+    pub cnf_ids: Vec<c_int>,
+    /// The first auxiliary variable. The auxiliary variables are numbered consecutively.
+    pub first_aux_id: c_int,
+}
+
+/// The meaning of a variable of a written CNF formula.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CnfVariable {
+    /// The BDD variable with the given index.
+    Bdd(usize),
+    /// The auxiliary variable with the given number (starting at `0`).
+    Auxiliary(usize),
+}
+
+impl CnfInfo {
+    /// The meaning of the given CNF variable, or `None` if the formula does not use it.
+    pub fn variable(&self, cnf_id: c_int) -> Option<CnfVariable> {
+        if let Some(index) = self.cnf_ids.iter().position(|id| *id == cnf_id) {
+            return Some(CnfVariable::Bdd(index));
+        }
+        let aux = cnf_id.checked_sub(self.first_aux_id)?;
+        if aux >= 0 && (aux as usize) < self.new_var_count {
+            Some(CnfVariable::Auxiliary(aux as usize))
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for CnfWriter {
+    fn default() -> Self {
+        CnfWriter::new()
+    }
+}
+
+impl CnfWriter {
+    /// A writer using `DDDMP_CNF_MODE_NODE` which writes the DDDMP header (as DIMACS comments).
+    pub fn new() -> CnfWriter {
+        CnfWriter {
+            mode: Dddmp_DecompCnfStoreType::DDDMP_CNF_MODE_NODE,
+            header: true,
+            var_names: None,
+            aux_ids: None,
+            cnf_ids: None,
+            first_aux_id: None,
+            edge_threshold: -1,
+            path_length_threshold: -1,
+        }
+    }
+
+    /// Set the encoding of the BDDs: one auxiliary variable per node (`NODE`), one clause
+    /// per path to zero (`MAXTERM`) or a combination of both (`BEST`).
+    pub fn mode(mut self, mode: Dddmp_DecompCnfStoreType) -> CnfWriter {
+        self.mode = mode;
+        self
+    }
+
+    /// Whether to write the DDDMP header. Without it, the output only contains the
+    /// `p cnf` line and the clauses.
+    pub fn header(mut self, header: bool) -> CnfWriter {
+        self.header = header;
+        self
+    }
+
+    /// Set the names of the variables (stored in the header), indexed by variable index.
+    /// By default, the names registered with the manager are stored (see `NamedVars`).
+    pub fn var_names<S: AsRef<str>>(mut self, names: &[S]) -> CnfWriter {
+        self.var_names = Some(names.iter().map(|s| s.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Set the auxiliary ids of the variables (stored in the header), indexed by
+    /// variable index.
+    pub fn aux_ids(mut self, ids: &[c_int]) -> CnfWriter {
+        self.aux_ids = Some(ids.to_vec());
+        self
+    }
+
+    /// Set the CNF variables (positive numbers) of the BDD variables, indexed by
+    /// variable index.
+    pub fn cnf_ids(mut self, ids: &[c_int]) -> CnfWriter {
+        self.cnf_ids = Some(ids.to_vec());
+        self
+    }
+
+    /// Set the first auxiliary variable. It must be greater than all CNF variables of
+    /// the BDD variables.
+    pub fn first_aux_id(mut self, id: c_int) -> CnfWriter {
+        self.first_aux_id = Some(id);
+        self
+    }
+
+    /// Set the maximum number of incoming edges of a node which is encoded using paths
+    /// in the `BEST` mode (`-1` by default).
+    pub fn edge_threshold(mut self, threshold: c_int) -> CnfWriter {
+        self.edge_threshold = threshold;
+        self
+    }
+
+    /// Set the maximum length of a path which is encoded as a single clause in the
+    /// `BEST` mode (`-1` by default).
+    pub fn path_length_threshold(mut self, threshold: c_int) -> CnfWriter {
+        self.path_length_threshold = threshold;
+        self
+    }
+
+    /// Store the conjunction of the CNF encodings of the given BDDs into the `output`.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager and the `roots` must be valid BDDs
+    /// of this manager.
+    pub unsafe fn write<W: Write>(
+        &self,
+        manager: *mut DdManager,
+        output: W,
+        roots: &[*mut DdNode],
+    ) -> Result<CnfInfo, DddmpError> {
+        let variables = Cudd_ReadSize(manager) as usize;
+        let mut var_names = match self.var_names {
+            Some(ref names) => Some(check_length(names, variables, "variable names")?),
+            None => match manager.var_name_list() {
+                Some(names) => Some(c_strings(&names)?),
+                None => None,
+            },
+        };
+        let mut bdd_ids: Vec<c_int> = (0..variables as c_int).collect();
+        let mut aux_ids = check_ids(self.aux_ids.as_deref(), variables, "auxiliary ids", false)?
+            .unwrap_or_else(|| bdd_ids.clone());
+        let mut cnf_ids = check_ids(self.cnf_ids.as_deref(), variables, "CNF ids", false)?
+            .unwrap_or_else(|| (1..=variables as c_int).collect());
+        if cnf_ids.iter().any(|id| *id <= 0) {
+            let message = "CNF ids must be positive.".to_string();
+            return Err(DddmpError::InvalidInput(message));
+        }
+        let max_cnf_id = cnf_ids.iter().cloned().max().unwrap_or(0);
+        let first_aux_id = match self.first_aux_id {
+            Some(id) => id,
+            None => max_cnf_id.checked_add(1).ok_or_else(|| {
+                let message = format!("No auxiliary variables can follow CNF id {}.", max_cnf_id);
+                DddmpError::InvalidInput(message)
+            })?,
+        };
+        if first_aux_id <= max_cnf_id {
+            let message = format!(
+                "The first auxiliary variable must be greater than {}.",
+                max_cnf_id
+            );
+            return Err(DddmpError::InvalidInput(message));
+        }
+
+        let mut clause_count: c_int = 0;
+        let mut new_var_count: c_int = 0;
+        let file = CFile::writer(output)?;
+        let status = Dddmp_cuddBddArrayStoreCnf(
+            manager,
+            roots.as_ptr() as *mut *mut DdNode,
+            roots.len() as c_int,
+            self.mode,
+            !self.header as c_int,
+            var_names
+                .as_mut()
+                .map(|n| n.as_mut_ptr())
+                .unwrap_or(null_mut()),
+            bdd_ids.as_mut_ptr(),
+            aux_ids.as_mut_ptr(),
+            cnf_ids.as_mut_ptr(),
+            first_aux_id,
+            self.edge_threshold,
+            self.path_length_threshold,
+            null_mut(),
+            file.as_ptr(),
+            &mut clause_count,
+            &mut new_var_count,
+        );
+        file.close()?;
+        if status != DDDMP_SUCCESS {
+            let message = "Cannot store the CNF formula.";
+            return Err(DddmpError::Io(Error::new(ErrorKind::Other, message)));
+        }
+        Ok(CnfInfo {
+            clause_count: clause_count.max(0) as usize,
+            new_var_count: new_var_count.max(0) as usize,
+            cnf_ids,
+            first_aux_id,
+        })
+    }
+
+    /// Store the CNF encoding of the given BDDs into a file at the given `path`.
+    ///
+    /// # Safety
+    ///
+    /// Same as `CnfWriter::write`.
+    pub unsafe fn write_file<P: AsRef<Path>>(
+        &self,
+        manager: *mut DdManager,
+        path: P,
+        roots: &[*mut DdNode],
+    ) -> Result<CnfInfo, DddmpError> {
+        self.write(manager, File::create(path)?, roots)
+    }
+}
@@ -0,0 +1,50 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Reasons why storing or loading a DDDMP file failed.
+#[derive(Debug)]
+pub enum DddmpError {
+    /// Reading from or writing into the underlying stream failed.
+    Io(io::Error),
+    /// The version in the header does not match `DDDMP_VERSION`.
+    Version(String),
+    /// The variables of the file cannot be matched with the variables of the manager.
+    VarMatch(String),
+    /// The file is malformed or contains a different kind of decision diagram.
+    Format(String),
+    /// The arguments are invalid (e.g. too few variable names or a name with a null byte).
+    InvalidInput(String),
+}
+
+impl fmt::Display for DddmpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DddmpError::Io(error) => write!(f, "DDDMP I/O error: {}", error),
+            DddmpError::Version(version) => write!(
+                f,
+                "Unsupported DDDMP version `{}` (expected `{}`).",
+                version,
+                super::DDDMP_VERSION
+            ),
+            DddmpError::VarMatch(message) => write!(f, "DDDMP variable mismatch: {}", message),
+            DddmpError::Format(message) => write!(f, "Invalid DDDMP file: {}", message),
+            DddmpError::InvalidInput(message) => write!(f, "Invalid DDDMP argument: {}", message),
+        }
+    }
+}
+
+impl Error for DddmpError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DddmpError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DddmpError {
+    fn from(error: io::Error) -> Self {
+        DddmpError::Io(error)
+    }
+}
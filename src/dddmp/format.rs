@@ -0,0 +1,680 @@
+use dddmp::{
+    DddmpError, DddmpHeader, DddmpMode, Dddmp_DecompType, Dddmp_VarInfoType, DDDMP_VERSION,
+};
+use libc::c_int;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+// Encodings of the variable and of the children of a node in the binary format.
+const DDDMP_TERMINAL: u8 = 0;
+const DDDMP_ABSOLUTE_ID: u8 = 1;
+const DDDMP_RELATIVE_ID: u8 = 2;
+const DDDMP_RELATIVE_1: u8 = 3;
+
+/// An edge of a decision diagram stored in a DDDMP file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DddmpEdge {
+    /// The id of the target node. Node ids start at `1`.
+    pub id: usize,
+    /// True if the edge is complemented (only used by BDDs).
+    pub complemented: bool,
+}
+
+/// A node of a decision diagram stored in a DDDMP file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DddmpNode {
+    /// A constant node. BDDs only contain the constant `1`.
+    Terminal(f64),
+    /// An internal node. The `var` is the position of the node variable in the support
+    /// arrays of the header (`support_names`, `ids`, `perm_ids`, `aux_ids`).
+    Internal {
+        var: usize,
+        then_child: DddmpEdge,
+        else_child: DddmpEdge,
+    },
+}
+
+/// The contents of a DDDMP file (BDDs or ADDs in the text or binary format) parsed without
+/// a `DdManager`, so that stored diagrams can be inspected, compared or converted between
+/// the text and binary formats (like `Dddmp_Text2Bin` and `Dddmp_Bin2Text`).
+///
+/// The nodes are stored in the order of the file: the node with id `i` is `nodes[i - 1]`
+/// and the children of every node precede the node itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DddmpFile {
+    /// The header of the file. The node and root counts are given by `nodes` and `roots`.
+    pub header: DddmpHeader,
+    /// The variable information stored with each node in the text format.
+    pub var_info: Dddmp_VarInfoType,
+    /// The node table.
+    pub nodes: Vec<DddmpNode>,
+    /// The stored roots.
+    pub roots: Vec<DddmpEdge>,
+}
+
+impl DddmpFile {
+    /// Parse a DDDMP file from the `input`.
+    pub fn read<R: Read>(mut input: R) -> Result<DddmpFile, DddmpError> {
+        let mut bytes = Vec::new();
+        input.read_to_end(&mut bytes)?;
+        DddmpFile::parse(&bytes)
+    }
+
+    /// Parse the DDDMP file at the given `path`.
+    pub fn read_file<P: AsRef<Path>>(path: P) -> Result<DddmpFile, DddmpError> {
+        DddmpFile::read(File::open(path)?)
+    }
+
+    /// Parse a DDDMP file from its contents.
+    pub fn parse(bytes: &[u8]) -> Result<DddmpFile, DddmpError> {
+        let mut parser = Parser { bytes, position: 0 };
+        let (header, var_info, node_count, root_ids) = parser.header()?;
+        let nodes = match header.mode {
+            Some(DddmpMode::Text) => parser.text_nodes(&header, node_count)?,
+            _ => parser.binary_nodes(&header, node_count)?,
+        };
+        match parser.line() {
+            Some(ref line) if line == ".end" => (),
+            _ => return Err(format_error("Missing `.end`.")),
+        }
+
+        let roots = root_ids
+            .iter()
+            .map(|id| {
+                let edge = DddmpEdge {
+                    id: id.unsigned_abs() as usize,
+                    complemented: *id < 0,
+                };
+                if edge.id == 0 || edge.id > nodes.len() {
+                    return Err(format_error(format!("Invalid root id {}.", id)));
+                }
+                Ok(edge)
+            })
+            .collect::<Result<Vec<DddmpEdge>, DddmpError>>()?;
+        Ok(DddmpFile {
+            header,
+            var_info,
+            nodes,
+            roots,
+        })
+    }
+
+    /// Write the file into the `output` using the given `mode`.
+    pub fn write<W: Write>(&self, mut output: W, mode: DddmpMode) -> Result<(), DddmpError> {
+        output.write_all(&self.to_bytes(mode)?)?;
+        output.flush()?;
+        Ok(())
+    }
+
+    /// Write the file at the given `path` using the given `mode`.
+    pub fn write_file<P: AsRef<Path>>(&self, path: P, mode: DddmpMode) -> Result<(), DddmpError> {
+        self.write(File::create(path)?, mode)
+    }
+
+    /// Encode the file using the given `mode`.
+    pub fn to_bytes(&self, mode: DddmpMode) -> Result<Vec<u8>, DddmpError> {
+        let header = &self.header;
+        let support_count = header.support_count;
+        check_support(&header.support_names, support_count, "support names")?;
+        check_support(&header.ids, support_count, "ids")?;
+        check_support(&header.perm_ids, support_count, "permutation ids")?;
+        check_support(&header.aux_ids, support_count, "auxiliary ids")?;
+        self.check_nodes()?;
+
+        let mut result = Vec::new();
+        writeln!(result, ".ver {}", DDDMP_VERSION)?;
+        match header.dd_type {
+            Dddmp_DecompType::DDDMP_BDD => (),
+            Dddmp_DecompType::DDDMP_ADD => writeln!(result, ".add")?,
+            other => return Err(invalid_input(format!("Cannot write {:?}.", other))),
+        }
+        let mode_char = if mode == DddmpMode::Text { 'A' } else { 'B' };
+        writeln!(result, ".mode {}", mode_char)?;
+        writeln!(result, ".varinfo {}", self.var_info as c_int)?;
+        if let Some(ref name) = header.dd_name {
+            writeln!(result, ".dd {}", name)?;
+        }
+        writeln!(result, ".nnodes {}", self.nodes.len())?;
+        writeln!(result, ".nvars {}", header.var_count)?;
+        writeln!(result, ".nsuppvars {}", support_count)?;
+        write_list(&mut result, ".suppvarnames", &header.support_names)?;
+        write_list(&mut result, ".orderedvarnames", &header.ordered_names)?;
+        write_list(&mut result, ".ids", &header.ids)?;
+        write_list(&mut result, ".permids", &header.perm_ids)?;
+        write_list(&mut result, ".auxids", &header.aux_ids)?;
+        writeln!(result, ".nroots {}", self.roots.len())?;
+        let root_ids: Vec<i64> = self.roots.iter().map(|root| signed_id(*root)).collect();
+        write_list(&mut result, ".rootids", &Some(root_ids))?;
+        write_list(&mut result, ".rootnames", &header.root_names)?;
+        writeln!(result, ".nodes")?;
+        match mode {
+            DddmpMode::Text => self.write_text_nodes(&mut result)?,
+            DddmpMode::Binary => self.write_binary_nodes(&mut result)?,
+        }
+        writeln!(result, ".end")?;
+        Ok(result)
+    }
+
+    /// The node with the given id, if there is such a node.
+    pub fn node(&self, id: usize) -> Option<&DddmpNode> {
+        id.checked_sub(1).and_then(|index| self.nodes.get(index))
+    }
+
+    /// Evaluate the root at the given position for the `assignment` of variables, indexed
+    /// by the variable ids of the file (or by the support positions if the file does not
+    /// contain ids). Returns `None` if the root does not exist or some variable is not
+    /// assigned.
+    pub fn eval(&self, root: usize, assignment: &[bool]) -> Option<f64> {
+        let mut edge = *self.roots.get(root)?;
+        let mut complemented = false;
+        loop {
+            complemented ^= edge.complemented;
+            match *self.node(edge.id)? {
+                DddmpNode::Terminal(value) if complemented => {
+                    return Some(if value == 0.0 { 1.0 } else { 0.0 });
+                }
+                DddmpNode::Terminal(value) => return Some(value),
+                DddmpNode::Internal {
+                    var,
+                    then_child,
+                    else_child,
+                } => {
+                    let id = match self.header.ids {
+                        Some(ref ids) => *ids.get(var)? as usize,
+                        None => var,
+                    };
+                    let next = if *assignment.get(id)? {
+                        then_child
+                    } else {
+                        else_child
+                    };
+                    if next.id >= edge.id {
+                        return None;
+                    }
+                    edge = next;
+                }
+            }
+        }
+    }
+
+    /// Check that the children of every node precede the node and that the roots exist.
+    fn check_nodes(&self) -> Result<(), DddmpError> {
+        for (index, node) in self.nodes.iter().enumerate() {
+            if let DddmpNode::Internal {
+                then_child,
+                else_child,
+                ..
+            } = *node
+            {
+                edge(signed_id(then_child), index + 1).map_err(|e| invalid_input(e.to_string()))?;
+                edge(signed_id(else_child), index + 1).map_err(|e| invalid_input(e.to_string()))?;
+            }
+        }
+        if self.roots.iter().any(|root| self.node(root.id).is_none()) {
+            return Err(invalid_input("Some root does not exist."));
+        }
+        Ok(())
+    }
+
+    fn write_text_nodes(&self, result: &mut Vec<u8>) -> Result<(), DddmpError> {
+        let header = &self.header;
+        for (index, node) in self.nodes.iter().enumerate() {
+            let id = index + 1;
+            match *node {
+                DddmpNode::Terminal(value) => writeln!(result, "{} T {} 0 0", id, value)?,
+                DddmpNode::Internal {
+                    var,
+                    then_child,
+                    else_child,
+                } => {
+                    let info = match self.var_info {
+                        Dddmp_VarInfoType::DDDMP_VARIDS => support_entry(&header.ids, var)?,
+                        Dddmp_VarInfoType::DDDMP_VARPERMIDS => {
+                            support_entry(&header.perm_ids, var)?
+                        }
+                        Dddmp_VarInfoType::DDDMP_VARAUXIDS => support_entry(&header.aux_ids, var)?,
+                        Dddmp_VarInfoType::DDDMP_VARNAMES => {
+                            support_entry(&header.support_names, var)?
+                        }
+                        Dddmp_VarInfoType::DDDMP_VARDEFAULT => None,
+                    };
+                    write!(result, "{} ", id)?;
+                    if let Some(info) = info {
+                        write!(result, "{} ", info)?;
+                    }
+                    writeln!(
+                        result,
+                        "{} {} {}",
+                        var,
+                        signed_id(then_child),
+                        signed_id(else_child)
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_binary_nodes(&self, result: &mut Vec<u8>) -> Result<(), DddmpError> {
+        if self.header.dd_type != Dddmp_DecompType::DDDMP_BDD {
+            return Err(invalid_input("The binary format only supports BDDs."));
+        }
+        let support_count = self.header.support_count;
+        for (index, node) in self.nodes.iter().enumerate() {
+            let id = index + 1;
+            let (var, then_child, else_child) = match *node {
+                DddmpNode::Terminal(value) if value == 1.0 && id == 1 => {
+                    write_byte(result, DDDMP_TERMINAL << 5);
+                    continue;
+                }
+                DddmpNode::Terminal(_) => {
+                    let message = "The binary format only supports the constant 1 with id 1.";
+                    return Err(invalid_input(message));
+                }
+                DddmpNode::Internal {
+                    var,
+                    then_child,
+                    else_child,
+                } => (var, then_child, else_child),
+            };
+            if then_child.complemented {
+                return Err(invalid_input("Then edges cannot be complemented."));
+            }
+            let var_of = |child: usize| match self.node(child) {
+                Some(&DddmpNode::Internal { var, .. }) => var,
+                _ => support_count,
+            };
+            let min = var_of(then_child.id).min(var_of(else_child.id));
+            let (v, var_value) = if var + 1 == min {
+                (DDDMP_RELATIVE_1, None)
+            } else if min > var && min - var < var {
+                (DDDMP_RELATIVE_ID, Some(min - var))
+            } else {
+                (DDDMP_ABSOLUTE_ID, Some(var))
+            };
+            let (t, then_value) = encode_child(id, then_child.id);
+            let (e, else_value) = encode_child(id, else_child.id);
+            let code = (v << 5) | (t << 3) | ((else_child.complemented as u8) << 2) | e;
+            write_byte(result, code);
+            for value in var_value.iter().chain(&then_value).chain(&else_value) {
+                write_int(result, *value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the parts of a DDDMP file.
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// The number of bytes which have not been read yet.
+    fn remaining(&self) -> usize {
+        self.bytes.len().saturating_sub(self.position)
+    }
+
+    /// The next line (without surrounding whitespace), or `None` at the end of the file.
+    fn line(&mut self) -> Option<String> {
+        if self.position >= self.bytes.len() {
+            return None;
+        }
+        let rest = &self.bytes[self.position..];
+        let length = rest.iter().position(|b| *b == b'\n').unwrap_or(rest.len());
+        self.position += (length + 1).min(rest.len());
+        Some(String::from_utf8_lossy(&rest[..length]).trim().to_string())
+    }
+
+    fn header(&mut self) -> Result<(DddmpHeader, Dddmp_VarInfoType, usize, Vec<i64>), DddmpError> {
+        let mut header = DddmpHeader {
+            dd_type: Dddmp_DecompType::DDDMP_BDD,
+            dd_name: None,
+            mode: None,
+            var_count: 0,
+            support_count: 0,
+            support_names: None,
+            ordered_names: None,
+            ids: None,
+            perm_ids: None,
+            aux_ids: None,
+            root_count: 0,
+            root_names: None,
+        };
+        let mut version = None;
+        let mut var_info = Dddmp_VarInfoType::DDDMP_VARDEFAULT;
+        let mut node_count = None;
+        let mut root_ids = None;
+        loop {
+            let line = self
+                .line()
+                .ok_or_else(|| format_error("Missing `.nodes`."))?;
+            let mut tokens = line.splitn(2, char::is_whitespace);
+            let field = tokens.next().unwrap_or("");
+            let value = tokens.next().unwrap_or("").trim();
+            match field {
+                ".nodes" => break,
+                ".ver" => version = Some(value.to_string()),
+                ".add" => header.dd_type = Dddmp_DecompType::DDDMP_ADD,
+                ".mode" => {
+                    header.mode = match value {
+                        "A" => Some(DddmpMode::Text),
+                        "B" => Some(DddmpMode::Binary),
+                        _ => return Err(format_error(format!("Invalid mode `{}`.", value))),
+                    }
+                }
+                ".varinfo" => {
+                    var_info = match parse::<c_int>(field, value)? {
+                        0 => Dddmp_VarInfoType::DDDMP_VARIDS,
+                        1 => Dddmp_VarInfoType::DDDMP_VARPERMIDS,
+                        2 => Dddmp_VarInfoType::DDDMP_VARAUXIDS,
+                        3 => Dddmp_VarInfoType::DDDMP_VARNAMES,
+                        _ => Dddmp_VarInfoType::DDDMP_VARDEFAULT,
+                    }
+                }
+                ".dd" => header.dd_name = Some(value.to_string()),
+                ".nnodes" => node_count = Some(parse(field, value)?),
+                ".nvars" => header.var_count = parse(field, value)?,
+                ".nsuppvars" => header.support_count = parse(field, value)?,
+                ".suppvarnames" | ".varnames" => header.support_names = Some(words(value)),
+                ".orderedvarnames" => header.ordered_names = Some(words(value)),
+                ".ids" => header.ids = Some(parse_list(field, value)?),
+                ".permids" => header.perm_ids = Some(parse_list(field, value)?),
+                ".auxids" => header.aux_ids = Some(parse_list(field, value)?),
+                ".nroots" => header.root_count = parse(field, value)?,
+                ".rootids" => root_ids = Some(parse_list(field, value)?),
+                ".rootnames" => header.root_names = Some(words(value)),
+                _ => (),
+            }
+        }
+
+        match version {
+            Some(ref version) if version == DDDMP_VERSION => (),
+            Some(version) => return Err(DddmpError::Version(version)),
+            None => return Err(format_error("Missing `.ver` field.")),
+        }
+        if header.mode.is_none() {
+            return Err(format_error("Missing `.mode` field."));
+        }
+        let node_count = node_count.ok_or_else(|| format_error("Missing `.nnodes` field."))?;
+        let root_ids: Vec<i64> =
+            root_ids.ok_or_else(|| format_error("Missing `.rootids` field."))?;
+        if root_ids.len() != header.root_count {
+            return Err(format_error(
+                "The number of root ids does not match `.nroots`.",
+            ));
+        }
+        let support_count = header.support_count;
+        check_support(&header.support_names, support_count, "support names")
+            .and(check_support(&header.ids, support_count, "ids"))
+            .and(check_support(
+                &header.perm_ids,
+                support_count,
+                "permutation ids",
+            ))
+            .and(check_support(
+                &header.aux_ids,
+                support_count,
+                "auxiliary ids",
+            ))
+            .map_err(|e| format_error(e.to_string()))?;
+        Ok((header, var_info, node_count, root_ids))
+    }
+
+    fn text_nodes(
+        &mut self,
+        header: &DddmpHeader,
+        count: usize,
+    ) -> Result<Vec<DddmpNode>, DddmpError> {
+        // Every node takes up at least one byte, which bounds the untrusted count.
+        let mut nodes = Vec::with_capacity(count.min(self.remaining()));
+        while nodes.len() < count {
+            let line = self.line().ok_or_else(|| format_error("Missing nodes."))?;
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let id = nodes.len() + 1;
+            if tokens.len() < 4 || tokens[0] != id.to_string() {
+                return Err(format_error(format!("Invalid node `{}`.", line)));
+            }
+            if tokens[1] == "T" {
+                nodes.push(DddmpNode::Terminal(parse("T", tokens[2])?));
+                continue;
+            }
+            // The optional variable information precedes the variable, then and else fields.
+            let fields = &tokens[tokens.len() - 3..];
+            let var: usize = parse("var", fields[0])?;
+            let then_child = edge(parse("then", fields[1])?, id)?;
+            let else_child = edge(parse("else", fields[2])?, id)?;
+            if var >= header.support_count {
+                return Err(format_error(format!("Invalid variable in node {}.", id)));
+            }
+            nodes.push(DddmpNode::Internal {
+                var,
+                then_child,
+                else_child,
+            });
+        }
+        Ok(nodes)
+    }
+
+    fn binary_nodes(
+        &mut self,
+        header: &DddmpHeader,
+        count: usize,
+    ) -> Result<Vec<DddmpNode>, DddmpError> {
+        let support_count = header.support_count;
+        let capacity = count.min(self.remaining());
+        let mut nodes = Vec::with_capacity(capacity);
+        // The variables of the nodes, the constant is below all variables.
+        let mut vars = Vec::with_capacity(capacity);
+        while nodes.len() < count {
+            let id = nodes.len() + 1;
+            let code = self.byte()?;
+            let v = (code >> 5) & 3;
+            if v == DDDMP_TERMINAL {
+                nodes.push(DddmpNode::Terminal(1.0));
+                vars.push(support_count);
+                continue;
+            }
+            let var_value = match v {
+                DDDMP_ABSOLUTE_ID | DDDMP_RELATIVE_ID => self.int()?,
+                _ => 1,
+            };
+            let then_id = self.child((code >> 3) & 3, id)?;
+            let else_id = self.child(code & 3, id)?;
+            let else_sign = if (code >> 2) & 1 == 1 { -1 } else { 1 };
+            let then_child = edge(then_id as i64, id)?;
+            let else_child = edge(else_sign * else_id as i64, id)?;
+            let var = if v == DDDMP_ABSOLUTE_ID {
+                Some(var_value)
+            } else {
+                vars[then_child.id - 1]
+                    .min(vars[else_child.id - 1])
+                    .checked_sub(var_value)
+            };
+            let var = match var {
+                Some(var) if var < support_count => var,
+                _ => return Err(format_error(format!("Invalid variable in node {}.", id))),
+            };
+            nodes.push(DddmpNode::Internal {
+                var,
+                then_child,
+                else_child,
+            });
+            vars.push(var);
+        }
+        // Skip the line break which may separate the nodes from `.end`.
+        while self.bytes.get(self.position) == Some(&b'\n') {
+            self.position += 1;
+        }
+        Ok(nodes)
+    }
+
+    /// Read the id of a child of the node with the given `id` in the given encoding.
+    fn child(&mut self, encoding: u8, id: usize) -> Result<usize, DddmpError> {
+        Ok(match encoding {
+            DDDMP_TERMINAL => 1,
+            DDDMP_RELATIVE_1 => id - 1,
+            DDDMP_RELATIVE_ID => id.saturating_sub(self.int()?),
+            _ => self.int()?,
+        })
+    }
+
+    /// Read one byte of the binary format, in which the bytes `0x00`, `\n`, `\r` and `0x1a`
+    /// are escaped by a preceding `0x00`.
+    fn byte(&mut self) -> Result<u8, DddmpError> {
+        let mut next = || {
+            let byte = self.bytes.get(self.position).cloned();
+            self.position += 1;
+            byte.ok_or_else(|| format_error("Unexpected end of binary data."))
+        };
+        match next()? {
+            0x00 => match next()? {
+                0x00 => Ok(0x00),
+                0x01 => Ok(b'\n'),
+                0x02 => Ok(b'\r'),
+                0x03 => Ok(0x1a),
+                other => Err(format_error(format!("Invalid escape {:#x}.", other))),
+            },
+            byte => Ok(byte),
+        }
+    }
+
+    /// Read an integer of the binary format: groups of 7 bits starting with the most
+    /// significant one, where the lowest bit of every byte marks that more bytes follow.
+    fn int(&mut self) -> Result<usize, DddmpError> {
+        let mut value = 0usize;
+        for _ in 0..4 {
+            let byte = self.byte()?;
+            value = (value << 7) | (byte >> 1) as usize;
+            if byte & 1 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(format_error("Invalid integer in binary data."))
+    }
+}
+
+/// Choose the encoding of the child `child` of the node `id` in the binary format.
+fn encode_child(id: usize, child: usize) -> (u8, Option<usize>) {
+    if child == 1 {
+        (DDDMP_TERMINAL, None)
+    } else if id - child == 1 {
+        (DDDMP_RELATIVE_1, None)
+    } else if id - child < child {
+        (DDDMP_RELATIVE_ID, Some(id - child))
+    } else {
+        (DDDMP_ABSOLUTE_ID, Some(child))
+    }
+}
+
+fn write_byte(result: &mut Vec<u8>, byte: u8) {
+    match byte {
+        0x00 => result.extend_from_slice(&[0x00, 0x00]),
+        b'\n' => result.extend_from_slice(&[0x00, 0x01]),
+        b'\r' => result.extend_from_slice(&[0x00, 0x02]),
+        0x1a => result.extend_from_slice(&[0x00, 0x03]),
+        byte => result.push(byte),
+    }
+}
+
+fn write_int(result: &mut Vec<u8>, value: usize) -> Result<(), DddmpError> {
+    if value >= 1 << 28 {
+        return Err(invalid_input(format!("Value {} is too large.", value)));
+    }
+    let groups: Vec<u8> = (0..4)
+        .map(|i| (((value >> (7 * i)) & 0x7f) << 1) as u8)
+        .collect();
+    let highest = (1..4).rev().find(|i| groups[*i] != 0).unwrap_or(0);
+    for i in (1..=highest).rev() {
+        write_byte(result, groups[i] | 1);
+    }
+    write_byte(result, groups[0]);
+    Ok(())
+}
+
+fn write_list<T: ToString>(
+    result: &mut Vec<u8>,
+    field: &str,
+    values: &Option<Vec<T>>,
+) -> Result<(), DddmpError> {
+    if let Some(ref values) = *values {
+        write!(result, "{}", field)?;
+        for value in values {
+            write!(result, " {}", value.to_string())?;
+        }
+        writeln!(result)?;
+    }
+    Ok(())
+}
+
+fn signed_id(edge: DddmpEdge) -> i64 {
+    if edge.complemented {
+        -(edge.id as i64)
+    } else {
+        edge.id as i64
+    }
+}
+
+/// An edge from the node `id` to the node with the (possibly negative) id `child`.
+fn edge(child: i64, id: usize) -> Result<DddmpEdge, DddmpError> {
+    let target = child.unsigned_abs() as usize;
+    if target == 0 || target >= id {
+        return Err(format_error(format!(
+            "Invalid child {} of node {}.",
+            child, id
+        )));
+    }
+    Ok(DddmpEdge {
+        id: target,
+        complemented: child < 0,
+    })
+}
+
+fn support_entry<T: ToString>(
+    values: &Option<Vec<T>>,
+    var: usize,
+) -> Result<Option<String>, DddmpError> {
+    match values.as_ref().and_then(|v| v.get(var)) {
+        Some(value) => Ok(Some(value.to_string())),
+        None => Err(invalid_input(
+            "The variable information is not available in the header.",
+        )),
+    }
+}
+
+fn check_support<T>(values: &Option<Vec<T>>, count: usize, what: &str) -> Result<(), DddmpError> {
+    match *values {
+        Some(ref values) if values.len() != count => Err(invalid_input(format!(
+            "Expected {} {}, but {} were given.",
+            count,
+            what,
+            values.len()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn parse<T: FromStr>(field: &str, value: &str) -> Result<T, DddmpError> {
+    value
+        .parse()
+        .map_err(|_| format_error(format!("Invalid value `{}` of `{}`.", value, field)))
+}
+
+fn parse_list<T: FromStr>(field: &str, value: &str) -> Result<Vec<T>, DddmpError> {
+    value.split_whitespace().map(|v| parse(field, v)).collect()
+}
+
+fn words(value: &str) -> Vec<String> {
+    value.split_whitespace().map(|s| s.to_string()).collect()
+}
+
+fn format_error<S: Into<String>>(message: S) -> DddmpError {
+    DddmpError::Format(message.into())
+}
+
+fn invalid_input<S: Into<String>>(message: S) -> DddmpError {
+    DddmpError::InvalidInput(message.into())
+}
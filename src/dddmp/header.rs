@@ -0,0 +1,206 @@
+use cfile::CFile;
+use dddmp::{
+    DddmpError, DddmpMode, Dddmp_DecompType, Dddmp_cuddHeaderLoad, Dddmp_cuddHeaderLoadCnf,
+    DDDMP_SUCCESS, DDDMP_VERSION,
+};
+use libc::{c_char, c_int, c_void};
+use std::ffi::CStr;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::ptr::null_mut;
+
+/// The header of a DDDMP file, obtained using `Dddmp_cuddHeaderLoad` (or
+/// `Dddmp_cuddHeaderLoadCnf` for files in the CNF format) without loading the diagrams.
+///
+/// The arrays describing the support (`support_names`, `ids`, `perm_ids` and `aux_ids`)
+/// contain one entry for each variable in the support of the stored diagrams. They are
+/// `None` if the corresponding information is not present in the file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DddmpHeader {
+    /// The kind of the stored diagrams (`DDDMP_BDD`, `DDDMP_ADD` or `DDDMP_CNF`).
+    pub dd_type: Dddmp_DecompType,
+    /// The name of the stored diagrams (the `.dd` field).
+    pub dd_name: Option<String>,
+    /// The encoding of the nodes (the `.mode` field). Not present in CNF files.
+    pub mode: Option<DddmpMode>,
+    /// The number of variables of the manager at the time of storing.
+    pub var_count: usize,
+    /// The number of variables in the support of the stored diagrams.
+    pub support_count: usize,
+    /// The names of the variables in the support.
+    pub support_names: Option<Vec<String>>,
+    /// The names of all variables, in the order of the manager at the time of storing.
+    pub ordered_names: Option<Vec<String>>,
+    /// The ids (variable indices) of the variables in the support.
+    pub ids: Option<Vec<c_int>>,
+    /// The permutation ids (levels) of the variables in the support.
+    pub perm_ids: Option<Vec<c_int>>,
+    /// The auxiliary ids of the variables in the support.
+    pub aux_ids: Option<Vec<c_int>>,
+    /// The number of stored roots.
+    pub root_count: usize,
+    /// The names of the stored roots (the `.rootnames` field).
+    pub root_names: Option<Vec<String>>,
+}
+
+/// Read the header of a DDDMP file from the `input`.
+pub fn read_header<R: Read>(mut input: R) -> Result<DddmpHeader, DddmpError> {
+    let mut bytes = Vec::new();
+    input.read_to_end(&mut bytes)?;
+    DddmpHeader::load(&bytes)
+}
+
+/// Read the header of the DDDMP file at the given `path`.
+pub fn read_header_file<P: AsRef<Path>>(path: P) -> Result<DddmpHeader, DddmpError> {
+    read_header(File::open(path)?)
+}
+
+impl DddmpHeader {
+    /// Check the version of the file and load its header.
+    pub(crate) fn load(bytes: &[u8]) -> Result<DddmpHeader, DddmpError> {
+        match header_field(bytes, ".ver") {
+            Some(ref version) if version == DDDMP_VERSION => (),
+            Some(version) => return Err(DddmpError::Version(version)),
+            None => return Err(DddmpError::Format("Missing `.ver` field.".to_string())),
+        }
+
+        let is_cnf = is_cnf(bytes);
+        let mut dd_type = Dddmp_DecompType::DDDMP_CNF;
+        let mut var_count: c_int = 0;
+        let mut support_count: c_int = 0;
+        let mut support_names: *mut *mut c_char = null_mut();
+        let mut ordered_names: *mut *mut c_char = null_mut();
+        let mut ids: *mut c_int = null_mut();
+        let mut perm_ids: *mut c_int = null_mut();
+        let mut aux_ids: *mut c_int = null_mut();
+        let mut root_count: c_int = 0;
+        let file = CFile::reader(bytes)?;
+        let status = unsafe {
+            if is_cnf {
+                Dddmp_cuddHeaderLoadCnf(
+                    &mut var_count,
+                    &mut support_count,
+                    &mut support_names,
+                    &mut ordered_names,
+                    &mut ids,
+                    &mut perm_ids,
+                    &mut aux_ids,
+                    &mut root_count,
+                    null_mut(),
+                    file.as_ptr(),
+                )
+            } else {
+                // The composition ids reported by DDDMP are the permutation ids of the file.
+                Dddmp_cuddHeaderLoad(
+                    &mut dd_type,
+                    &mut var_count,
+                    &mut support_count,
+                    &mut support_names,
+                    &mut ordered_names,
+                    &mut ids,
+                    &mut perm_ids,
+                    &mut aux_ids,
+                    &mut root_count,
+                    null_mut(),
+                    file.as_ptr(),
+                )
+            }
+        };
+        file.close()?;
+
+        // Take ownership of the arrays before checking the status, so that they are freed.
+        let support_count = support_count.max(0) as usize;
+        let var_count = var_count.max(0) as usize;
+        let header = unsafe {
+            DddmpHeader {
+                dd_type,
+                dd_name: header_field(bytes, ".dd"),
+                mode: match header_field(bytes, ".mode").as_deref() {
+                    Some("A") => Some(DddmpMode::Text),
+                    Some("B") => Some(DddmpMode::Binary),
+                    _ => None,
+                },
+                var_count,
+                support_count,
+                support_names: take_strings(support_names, support_count),
+                ordered_names: take_strings(ordered_names, var_count),
+                ids: take_ints(ids, support_count),
+                perm_ids: take_ints(perm_ids, support_count),
+                aux_ids: take_ints(aux_ids, support_count),
+                root_count: root_count.max(0) as usize,
+                root_names: header_field(bytes, ".rootnames")
+                    .map(|names| names.split_whitespace().map(|s| s.to_string()).collect()),
+            }
+        };
+        if status != DDDMP_SUCCESS {
+            return Err(DddmpError::Format("Cannot read the header.".to_string()));
+        }
+        Ok(header)
+    }
+}
+
+/// Copy and free an array of C strings allocated by DDDMP.
+unsafe fn take_strings(array: *mut *mut c_char, length: usize) -> Option<Vec<String>> {
+    if array.is_null() {
+        return None;
+    }
+    let strings = std::slice::from_raw_parts(array, length)
+        .iter()
+        .map(|string| {
+            let result = if string.is_null() {
+                String::new()
+            } else {
+                CStr::from_ptr(*string).to_string_lossy().into_owned()
+            };
+            libc::free(*string as *mut c_void);
+            result
+        })
+        .collect();
+    libc::free(array as *mut c_void);
+    Some(strings)
+}
+
+/// Copy and free an integer array allocated by DDDMP.
+unsafe fn take_ints(array: *mut c_int, length: usize) -> Option<Vec<c_int>> {
+    if array.is_null() {
+        return None;
+    }
+    let ints = std::slice::from_raw_parts(array, length).to_vec();
+    libc::free(array as *mut c_void);
+    Some(ints)
+}
+
+/// True if the file uses the CNF format, whose header lines are DIMACS comments (`c .ver ...`).
+fn is_cnf(bytes: &[u8]) -> bool {
+    header_lines(bytes)
+        .find(|line| !line.is_empty())
+        .map(|line| line.starts_with("c "))
+        .unwrap_or(false)
+}
+
+fn header_lines<'a>(bytes: &'a [u8]) -> impl Iterator<Item = String> + 'a {
+    bytes
+        .split(|b| *b == b'\n')
+        .map(|line| String::from_utf8_lossy(line).trim().to_string())
+}
+
+/// Read the value of a field from the (always textual) header of a DDDMP file.
+pub(crate) fn header_field(bytes: &[u8], field: &str) -> Option<String> {
+    for line in header_lines(bytes) {
+        if line.starts_with("p cnf") {
+            break;
+        }
+        // Strip the comment marker of the CNF format.
+        let line = line.strip_prefix("c ").unwrap_or(&line);
+        let mut tokens = line.trim().splitn(2, char::is_whitespace);
+        match tokens.next() {
+            Some(".nodes") => break,
+            Some(name) if name == field => {
+                return Some(tokens.next().unwrap_or("").trim().to_string());
+            }
+            _ => (),
+        }
+    }
+    None
+}
@@ -0,0 +1,276 @@
+use cudd::{Cudd_ReadSize, Cudd_RecursiveDeref, Cudd_Ref};
+use dddmp::{DddmpError, DddmpHeader, DddmpMode, DddmpReader, DddmpWriter};
+use libc::c_int;
+use registry::VarNames;
+use serde::de::{DeserializeSeed, Deserializer, Error, SeqAccess, Visitor};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use {DdManager, DdNode};
+
+/// A referenced BDD or ADD which can be serialised using `serde`. The diagram is encoded
+/// as the bytes of a DDDMP file produced by `Dddmp_cuddBddStore` or `Dddmp_cuddAddStore`,
+/// so that non-self-describing formats can be used as well. Use a `DddmpSeed` to
+/// deserialise it.
+///
+/// The variable names and auxiliary ids stored in the DDDMP header, as well as the encoding,
+/// are given by the `DddmpWriter` attached to the diagram.
+pub struct DddmpDiagram {
+    manager: *mut DdManager,
+    node: *mut DdNode,
+    is_add: bool,
+    writer: DddmpWriter,
+}
+
+impl DddmpDiagram {
+    /// Create a serialisable handle of a BDD. The node is referenced by the handle.
+    ///
+    /// # Safety
+    ///
+    /// The `node` must be a valid BDD of the `manager` and the manager must outlive
+    /// the handle (and all its clones).
+    pub unsafe fn bdd(manager: *mut DdManager, node: *mut DdNode) -> DddmpDiagram {
+        Cudd_Ref(node);
+        DddmpDiagram::referenced(manager, node, false)
+    }
+
+    /// Create a serialisable handle of an ADD. The node is referenced by the handle.
+    ///
+    /// # Safety
+    ///
+    /// The `node` must be a valid ADD of the `manager` and the manager must outlive
+    /// the handle (and all its clones).
+    pub unsafe fn add(manager: *mut DdManager, node: *mut DdNode) -> DddmpDiagram {
+        Cudd_Ref(node);
+        DddmpDiagram::referenced(manager, node, true)
+    }
+
+    unsafe fn referenced(manager: *mut DdManager, node: *mut DdNode, is_add: bool) -> Self {
+        DddmpDiagram {
+            manager,
+            node,
+            is_add,
+            writer: DddmpWriter::new(),
+        }
+    }
+
+    /// Use the given `writer` settings (encoding, variable names, auxiliary ids)
+    /// when serialising the diagram.
+    pub fn writer(mut self, writer: DddmpWriter) -> DddmpDiagram {
+        self.writer = writer;
+        self
+    }
+
+    /// The manager of the diagram.
+    pub fn manager(&self) -> *mut DdManager {
+        self.manager
+    }
+
+    /// The root node of the diagram. The node stays referenced as long as the handle exists.
+    pub fn node(&self) -> *mut DdNode {
+        self.node
+    }
+
+    /// True if the diagram is an ADD.
+    pub fn is_add(&self) -> bool {
+        self.is_add
+    }
+
+    /// Encode the diagram into the DDDMP format.
+    pub fn to_dddmp(&self) -> Result<Vec<u8>, DddmpError> {
+        let mut buffer = Vec::new();
+        unsafe {
+            if self.is_add {
+                self.writer
+                    .write_add(self.manager, &mut buffer, &[self.node])?;
+            } else {
+                self.writer.write(self.manager, &mut buffer, &[self.node])?;
+            }
+        }
+        Ok(buffer)
+    }
+}
+
+impl Clone for DddmpDiagram {
+    fn clone(&self) -> Self {
+        unsafe { Cudd_Ref(self.node) };
+        DddmpDiagram {
+            manager: self.manager,
+            node: self.node,
+            is_add: self.is_add,
+            writer: self.writer.clone(),
+        }
+    }
+}
+
+impl Drop for DddmpDiagram {
+    fn drop(&mut self) {
+        unsafe { Cudd_RecursiveDeref(self.manager, self.node) };
+    }
+}
+
+impl fmt::Debug for DddmpDiagram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DddmpDiagram")
+            .field("node", &self.node)
+            .field("is_add", &self.is_add)
+            .finish()
+    }
+}
+
+impl Serialize for DddmpDiagram {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let bytes = self.to_dddmp().map_err(serde::ser::Error::custom)?;
+        serializer.serialize_bytes(&bytes)
+    }
+}
+
+/// Deserialises a `DddmpDiagram` into the given manager. Both text and binary encodings
+/// can be loaded, and self-describing formats may also give the file as a string.
+///
+/// The variables are matched according to the attached `DddmpReader`. The loaded
+/// diagram uses a writer with the same encoding and the variable names and auxiliary ids
+/// of the header, so that serialising it again keeps them.
+pub struct DddmpSeed {
+    manager: *mut DdManager,
+    is_add: bool,
+    reader: DddmpReader,
+}
+
+impl DddmpSeed {
+    /// A seed which loads a BDD into the `manager`.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be valid and must outlive the deserialised handles.
+    pub unsafe fn bdd(manager: *mut DdManager) -> DddmpSeed {
+        DddmpSeed {
+            manager,
+            is_add: false,
+            reader: DddmpReader::new(),
+        }
+    }
+
+    /// A seed which loads an ADD into the `manager`.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be valid and must outlive the deserialised handles.
+    pub unsafe fn add(manager: *mut DdManager) -> DddmpSeed {
+        DddmpSeed {
+            manager,
+            is_add: true,
+            reader: DddmpReader::new(),
+        }
+    }
+
+    /// Use the given `reader` settings (variable matching) when loading the diagram.
+    pub fn reader(mut self, reader: DddmpReader) -> DddmpSeed {
+        self.reader = reader;
+        self
+    }
+
+    fn load<E: Error>(self, bytes: &[u8]) -> Result<DddmpDiagram, E> {
+        let mut roots = unsafe {
+            if self.is_add {
+                self.reader.read_add(self.manager, bytes)
+            } else {
+                self.reader.read(self.manager, bytes)
+            }
+        }
+        .map_err(E::custom)?;
+        if roots.len() != 1 {
+            for root in &roots {
+                unsafe { Cudd_RecursiveDeref(self.manager, root.node) };
+            }
+            let message = format!("Expected one DDDMP root, found {}.", roots.len());
+            return Err(E::custom(message));
+        }
+        let node = roots.remove(0).node;
+        let mode = if is_binary(bytes) {
+            DddmpMode::Binary
+        } else {
+            DddmpMode::Text
+        };
+        let diagram = unsafe { DddmpDiagram::referenced(self.manager, node, self.is_add) };
+        let header = DddmpHeader::load(bytes).map_err(E::custom)?;
+        Ok(diagram.writer(unsafe { self.header_writer(&header, mode) }))
+    }
+
+    /// A writer which stores the variable names and auxiliary ids of the `header`.
+    unsafe fn header_writer(&self, header: &DddmpHeader, mode: DddmpMode) -> DddmpWriter {
+        let variables = Cudd_ReadSize(self.manager) as usize;
+        let indices = self.reader.support_indices(self.manager, header);
+        // The positions in the support and the indices of the matched variables.
+        let matched = || {
+            indices
+                .iter()
+                .enumerate()
+                .filter_map(|(i, index)| match *index {
+                    Some(index) if index < variables => Some((i, index)),
+                    _ => None,
+                })
+        };
+        let mut writer = DddmpWriter::new().mode(mode);
+        if let Some(ref support_names) = header.support_names {
+            let mut names = VarNames::new();
+            for (i, index) in matched() {
+                if let Some(name) = support_names.get(i) {
+                    // Names which clash with other variables are left out.
+                    let _ = names.insert(index as c_int, name);
+                }
+            }
+            writer = writer.var_names(&names.to_vec(variables));
+        }
+        if let Some(ref support_ids) = header.aux_ids {
+            let mut aux_ids = vec![0; variables];
+            for (i, index) in matched() {
+                if let Some(id) = support_ids.get(i) {
+                    aux_ids[index] = *id;
+                }
+            }
+            writer = writer.aux_ids(&aux_ids);
+        }
+        writer
+    }
+}
+
+/// Check the `.mode` field of the DDDMP header.
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes
+        .split(|b| *b == b'\n')
+        .take_while(|line| !line.starts_with(b".nodes"))
+        .any(|line| line.starts_with(b".mode B"))
+}
+
+impl<'de> DeserializeSeed<'de> for DddmpSeed {
+    type Value = DddmpDiagram;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_byte_buf(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DddmpSeed {
+    type Value = DddmpDiagram;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a DDDMP string or byte array")
+    }
+
+    fn visit_str<E: Error>(self, value: &str) -> Result<Self::Value, E> {
+        self.load(value.as_bytes())
+    }
+
+    fn visit_bytes<E: Error>(self, value: &[u8]) -> Result<Self::Value, E> {
+        self.load(value)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        // The length hint comes from the input, so it is only trusted up to a small bound.
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        self.load(&bytes)
+    }
+}
@@ -0,0 +1,711 @@
+use cfile::CFile;
+use cudd::Cudd_ReadSize;
+use dddmp::header::header_field;
+use dddmp::*;
+use libc::{c_char, c_int, c_void};
+use names::CStringArray;
+use registry::NamedVars;
+use std::ffi::CString;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::path::Path;
+use std::ptr::{null_mut, NonNull};
+use {DdManager, DdNode};
+
+/// The encoding of the nodes in a DDDMP file. The header is always stored as text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DddmpMode {
+    /// Corresponds to `DDDMP_MODE_TEXT`.
+    Text,
+    /// Corresponds to `DDDMP_MODE_BINARY`.
+    Binary,
+}
+
+impl DddmpMode {
+    /// The `mode` argument expected by the DDDMP functions.
+    pub fn as_c_int(self) -> c_int {
+        match self {
+            DddmpMode::Text => DDDMP_MODE_TEXT,
+            DddmpMode::Binary => DDDMP_MODE_BINARY,
+        }
+    }
+}
+
+/// A root loaded by a `DddmpReader`.
+#[derive(Clone, Debug)]
+pub struct DddmpRoot {
+    /// The name of the root, if the file contains root names.
+    pub name: Option<String>,
+    /// The loaded node. The node is referenced and should be eventually released
+    /// using `Cudd_RecursiveDeref`.
+    pub node: *mut DdNode,
+}
+
+/// Stores arrays of BDDs or ADDs in the DDDMP format (see `Dddmp_cuddBddArrayStore`).
+#[derive(Clone, Debug)]
+pub struct DddmpWriter {
+    mode: DddmpMode,
+    dd_name: Option<String>,
+    var_names: Option<Vec<String>>,
+    aux_ids: Option<Vec<c_int>>,
+    root_names: Option<Vec<String>>,
+    var_info: Dddmp_VarInfoType,
+}
+
+impl Default for DddmpWriter {
+    fn default() -> Self {
+        DddmpWriter::new()
+    }
+}
+
+impl DddmpWriter {
+    /// A writer using the text mode and no optional information.
+    pub fn new() -> DddmpWriter {
+        DddmpWriter {
+            mode: DddmpMode::Text,
+            dd_name: None,
+            var_names: None,
+            aux_ids: None,
+            root_names: None,
+            var_info: Dddmp_VarInfoType::DDDMP_VARDEFAULT,
+        }
+    }
+
+    /// Set the encoding of the nodes.
+    pub fn mode(mut self, mode: DddmpMode) -> DddmpWriter {
+        self.mode = mode;
+        self
+    }
+
+    /// The encoding of the nodes.
+    pub fn get_mode(&self) -> DddmpMode {
+        self.mode
+    }
+
+    /// Set the name of the stored decision diagram (the `.dd` header field).
+    pub fn dd_name(mut self, name: &str) -> DddmpWriter {
+        self.dd_name = Some(name.to_string());
+        self
+    }
+
+    /// Set the names of the variables, indexed by variable index. By default, the names
+    /// registered with the manager are stored (see `NamedVars`), if there are any.
+    pub fn var_names<S: AsRef<str>>(mut self, names: &[S]) -> DddmpWriter {
+        self.var_names = Some(names.iter().map(|s| s.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Set the auxiliary ids of the variables, indexed by variable index.
+    pub fn aux_ids(mut self, ids: &[c_int]) -> DddmpWriter {
+        self.aux_ids = Some(ids.to_vec());
+        self
+    }
+
+    /// Set the names of the stored roots.
+    pub fn root_names<S: AsRef<str>>(mut self, names: &[S]) -> DddmpWriter {
+        self.root_names = Some(names.iter().map(|s| s.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Set the variable information stored with each node in text mode.
+    pub fn var_info(mut self, info: Dddmp_VarInfoType) -> DddmpWriter {
+        self.var_info = info;
+        self
+    }
+
+    /// Store the given BDDs into the `output`.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager and the `roots` must be valid BDDs
+    /// of this manager.
+    pub unsafe fn write<W: Write>(
+        &self,
+        manager: *mut DdManager,
+        output: W,
+        roots: &[*mut DdNode],
+    ) -> Result<(), DddmpError> {
+        self.store(manager, output, roots, Dddmp_cuddBddArrayStore)
+    }
+
+    /// Store the given ADDs into the `output`.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager and the `roots` must be valid ADDs
+    /// of this manager.
+    pub unsafe fn write_add<W: Write>(
+        &self,
+        manager: *mut DdManager,
+        output: W,
+        roots: &[*mut DdNode],
+    ) -> Result<(), DddmpError> {
+        self.store(manager, output, roots, Dddmp_cuddAddArrayStore)
+    }
+
+    /// Store the given BDDs into a file at the given `path`.
+    ///
+    /// # Safety
+    ///
+    /// Same as `DddmpWriter::write`.
+    pub unsafe fn write_file<P: AsRef<Path>>(
+        &self,
+        manager: *mut DdManager,
+        path: P,
+        roots: &[*mut DdNode],
+    ) -> Result<(), DddmpError> {
+        self.write(manager, File::create(path)?, roots)
+    }
+
+    unsafe fn store<W: Write>(
+        &self,
+        manager: *mut DdManager,
+        output: W,
+        roots: &[*mut DdNode],
+        store: ArrayStoreFunction,
+    ) -> Result<(), DddmpError> {
+        let variables = Cudd_ReadSize(manager) as usize;
+        let dd_name = match self.dd_name {
+            Some(ref name) => Some(CString::new(name.as_str()).map_err(|_| {
+                DddmpError::InvalidInput(format!("Name `{}` contains a null byte.", name))
+            })?),
+            None => None,
+        };
+        let mut var_names = match self.var_names {
+            Some(ref names) => Some(check_length(names, variables, "variable names")?),
+            None => match manager.var_name_list() {
+                Some(names) => Some(c_strings(&names)?),
+                None => None,
+            },
+        };
+        let mut root_names = match self.root_names {
+            Some(ref names) if names.len() != roots.len() => {
+                let message = format!(
+                    "Expected {} root names, but {} were given.",
+                    roots.len(),
+                    names.len()
+                );
+                return Err(DddmpError::InvalidInput(message));
+            }
+            Some(ref names) => Some(c_strings(names)?),
+            None => None,
+        };
+        let mut aux_ids = check_ids(self.aux_ids.as_deref(), variables, "auxiliary ids", false)?;
+        let file = CFile::writer(output)?;
+        let status = store(
+            manager,
+            dd_name
+                .as_ref()
+                .map(|n| n.as_ptr() as *mut c_char)
+                .unwrap_or(null_mut()),
+            roots.len() as c_int,
+            roots.as_ptr() as *mut *mut DdNode,
+            root_names
+                .as_mut()
+                .map(|n| n.as_mut_ptr())
+                .unwrap_or(null_mut()),
+            var_names
+                .as_mut()
+                .map(|n| n.as_mut_ptr())
+                .unwrap_or(null_mut()),
+            aux_ids
+                .as_mut()
+                .map(|ids| ids.as_mut_ptr())
+                .unwrap_or(null_mut()),
+            self.mode.as_c_int(),
+            self.var_info,
+            null_mut(),
+            file.as_ptr(),
+        );
+        file.close()?;
+        if status != DDDMP_SUCCESS {
+            let message = "Cannot store the decision diagrams.";
+            return Err(DddmpError::Io(Error::new(ErrorKind::Other, message)));
+        }
+        Ok(())
+    }
+}
+
+type ArrayStoreFunction = unsafe extern "C" fn(
+    *mut DdManager,
+    *mut c_char,
+    c_int,
+    *mut *mut DdNode,
+    *mut *mut c_char,
+    *mut *mut c_char,
+    *mut c_int,
+    c_int,
+    Dddmp_VarInfoType,
+    *mut c_char,
+    *mut libc::FILE,
+) -> c_int;
+
+/// Loads arrays of BDDs or ADDs stored in the DDDMP format (see `Dddmp_cuddBddArrayLoad`).
+///
+/// By default, variables are matched by their ids and all roots are loaded in the
+/// order in which they appear in the file.
+#[derive(Clone, Debug)]
+pub struct DddmpReader {
+    var_match: Dddmp_VarMatchType,
+    var_names: Option<Vec<String>>,
+    aux_ids: Option<Vec<c_int>>,
+    compose_ids: Option<Vec<c_int>>,
+    root_names: Option<Vec<String>>,
+}
+
+impl Default for DddmpReader {
+    fn default() -> Self {
+        DddmpReader::new()
+    }
+}
+
+impl DddmpReader {
+    /// A reader which matches variables by their ids.
+    pub fn new() -> DddmpReader {
+        DddmpReader {
+            var_match: Dddmp_VarMatchType::DDDMP_VAR_MATCHIDS,
+            var_names: None,
+            aux_ids: None,
+            compose_ids: None,
+            root_names: None,
+        }
+    }
+
+    /// Match the variables in the file with the variables of the manager using names
+    /// (indexed by the variable index in the manager).
+    pub fn match_names<S: AsRef<str>>(mut self, names: &[S]) -> DddmpReader {
+        self.var_match = Dddmp_VarMatchType::DDDMP_VAR_MATCHNAMES;
+        self.var_names = Some(names.iter().map(|s| s.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Match the variables in the file with the variables of the manager using auxiliary
+    /// ids (indexed by the variable index in the manager).
+    pub fn match_aux_ids(mut self, ids: &[c_int]) -> DddmpReader {
+        self.var_match = Dddmp_VarMatchType::DDDMP_VAR_MATCHAUXIDS;
+        self.aux_ids = Some(ids.to_vec());
+        self
+    }
+
+    /// Match the variables in the file with the variables of the manager using their
+    /// permutation ids (i.e. the levels at the time of storing).
+    pub fn match_perm_ids(mut self) -> DddmpReader {
+        self.var_match = Dddmp_VarMatchType::DDDMP_VAR_MATCHPERMIDS;
+        self
+    }
+
+    /// Map the variable ids in the file to the given variable indices of the manager
+    /// (indexed by the variable id in the file).
+    pub fn compose_ids(mut self, ids: &[c_int]) -> DddmpReader {
+        self.var_match = Dddmp_VarMatchType::DDDMP_VAR_COMPOSEIDS;
+        self.compose_ids = Some(ids.to_vec());
+        self
+    }
+
+    /// Only load the roots with the given names, in the given order.
+    pub fn root_names<S: AsRef<str>>(mut self, names: &[S]) -> DddmpReader {
+        self.root_names = Some(names.iter().map(|s| s.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Load BDDs from the `input`.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager.
+    pub unsafe fn read<R: Read>(
+        &self,
+        manager: *mut DdManager,
+        input: R,
+    ) -> Result<Vec<DddmpRoot>, DddmpError> {
+        self.load(manager, input, Loaded::Bdd)
+    }
+
+    /// Load ADDs from the `input`.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager.
+    pub unsafe fn read_add<R: Read>(
+        &self,
+        manager: *mut DdManager,
+        input: R,
+    ) -> Result<Vec<DddmpRoot>, DddmpError> {
+        self.load(manager, input, Loaded::Add)
+    }
+
+    /// Load BDDs from a CNF (DIMACS) file, such as the ones produced by a `CnfWriter`.
+    /// The `load_type` determines whether the clauses are returned separately or conjoined
+    /// (and whether the auxiliary variables are quantified).
+    ///
+    /// Files without the DDDMP header (plain DIMACS) are accepted as well. In that case,
+    /// the CNF variable `i` corresponds to the BDD variable with index `i - 1`.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager.
+    pub unsafe fn read_cnf<R: Read>(
+        &self,
+        manager: *mut DdManager,
+        input: R,
+        load_type: Dddmp_DecompCnfLoadType,
+    ) -> Result<Vec<DddmpRoot>, DddmpError> {
+        self.load(manager, input, Loaded::Cnf(load_type))
+    }
+
+    /// Load BDDs from a file at the given `path`.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager.
+    pub unsafe fn read_file<P: AsRef<Path>>(
+        &self,
+        manager: *mut DdManager,
+        path: P,
+    ) -> Result<Vec<DddmpRoot>, DddmpError> {
+        self.read(manager, File::open(path)?)
+    }
+
+    /// Check that a file with the given `header` can be loaded using the variable matching
+    /// mode and the root names of this reader.
+    pub fn check_header(&self, header: &DddmpHeader) -> Result<(), DddmpError> {
+        let unavailable = |what: &str| {
+            DddmpError::VarMatch(format!("The file does not contain variable {}.", what))
+        };
+        let no_match = |what: &str, missing: String| {
+            DddmpError::VarMatch(format!("Variable {} {} are not known.", what, missing))
+        };
+        match self.var_match {
+            Dddmp_VarMatchType::DDDMP_VAR_MATCHNAMES => {
+                let names = header
+                    .support_names
+                    .as_ref()
+                    .ok_or_else(|| unavailable("names"))?;
+                let known = self.var_names.as_deref().unwrap_or(&[]);
+                if let Some(missing) = missing(names, known) {
+                    return Err(no_match("names", missing));
+                }
+            }
+            Dddmp_VarMatchType::DDDMP_VAR_MATCHAUXIDS => {
+                let ids = header
+                    .aux_ids
+                    .as_ref()
+                    .ok_or_else(|| unavailable("auxiliary ids"))?;
+                let known = self.aux_ids.as_deref().unwrap_or(&[]);
+                if let Some(missing) = missing(ids, known) {
+                    return Err(no_match("auxiliary ids", missing));
+                }
+            }
+            Dddmp_VarMatchType::DDDMP_VAR_MATCHPERMIDS => {
+                header
+                    .perm_ids
+                    .as_ref()
+                    .ok_or_else(|| unavailable("permutation ids"))?;
+            }
+            Dddmp_VarMatchType::DDDMP_VAR_COMPOSEIDS => {
+                let ids = header.ids.as_ref().ok_or_else(|| unavailable("ids"))?;
+                let composed = self.compose_ids.as_ref().map(|n| n.len()).unwrap_or(0);
+                let unknown: Vec<c_int> = ids
+                    .iter()
+                    .cloned()
+                    .filter(|id| *id < 0 || *id as usize >= composed)
+                    .collect();
+                if let Some(missing) = missing(&unknown, &[]) {
+                    return Err(no_match("ids", missing));
+                }
+            }
+            Dddmp_VarMatchType::DDDMP_VAR_MATCHIDS => (),
+        }
+
+        if let Some(ref requested) = self.root_names {
+            let stored = header.root_names.as_deref().unwrap_or(&[]);
+            if let Some(missing) = missing(requested, stored) {
+                let message = format!("Roots {} are not stored in the file.", missing);
+                return Err(DddmpError::InvalidInput(message));
+            }
+        }
+        Ok(())
+    }
+
+    /// The indices in the `manager` of the support variables of a file with the given
+    /// `header`, as matched by this reader (`None` if a variable cannot be matched).
+    #[cfg(feature = "serde")]
+    pub(super) unsafe fn support_indices(
+        &self,
+        manager: *mut DdManager,
+        header: &DddmpHeader,
+    ) -> Vec<Option<usize>> {
+        let position = |values: Option<&[c_int]>, value: c_int| {
+            values.and_then(|values| values.iter().position(|v| *v == value))
+        };
+        (0..header.support_count)
+            .map(|i| match self.var_match {
+                Dddmp_VarMatchType::DDDMP_VAR_MATCHIDS => header
+                    .ids
+                    .as_ref()
+                    .and_then(|ids| ids.get(i))
+                    .map(|id| *id as usize),
+                Dddmp_VarMatchType::DDDMP_VAR_MATCHNAMES => {
+                    let name = header.support_names.as_ref().and_then(|n| n.get(i));
+                    let names = self.var_names.as_deref();
+                    name.and_then(|name| names.and_then(|n| n.iter().position(|v| v == name)))
+                }
+                Dddmp_VarMatchType::DDDMP_VAR_MATCHAUXIDS => header
+                    .aux_ids
+                    .as_ref()
+                    .and_then(|ids| ids.get(i))
+                    .and_then(|id| position(self.aux_ids.as_deref(), *id)),
+                Dddmp_VarMatchType::DDDMP_VAR_MATCHPERMIDS => header
+                    .perm_ids
+                    .as_ref()
+                    .and_then(|ids| ids.get(i))
+                    .filter(|level| **level >= 0 && **level < Cudd_ReadSize(manager))
+                    .map(|level| ::cudd::Cudd_ReadInvPerm(manager, *level) as usize),
+                Dddmp_VarMatchType::DDDMP_VAR_COMPOSEIDS => header
+                    .ids
+                    .as_ref()
+                    .and_then(|ids| ids.get(i))
+                    .and_then(|id| {
+                        let composed = self.compose_ids.as_deref().unwrap_or(&[]);
+                        composed.get(*id as usize)
+                    })
+                    .map(|index| *index as usize),
+            })
+            .collect()
+    }
+
+    unsafe fn load<R: Read>(
+        &self,
+        manager: *mut DdManager,
+        mut input: R,
+        kind: Loaded,
+    ) -> Result<Vec<DddmpRoot>, DddmpError> {
+        let variables = Cudd_ReadSize(manager) as usize;
+        let mut var_names = match self.var_names {
+            Some(ref names) => Some(check_length(names, variables, "variable names")?),
+            None if self.var_match == Dddmp_VarMatchType::DDDMP_VAR_MATCHNAMES => {
+                let message = "Variable names are required to match variables by names.";
+                return Err(DddmpError::InvalidInput(message.to_string()));
+            }
+            None => None,
+        };
+        let mut root_names = match self.root_names {
+            Some(ref names) => Some(c_strings(names)?),
+            None => None,
+        };
+        // DDDMP reads the ids of all variables of the manager without checking the length.
+        let mut aux_ids = check_ids(
+            self.aux_ids.as_deref(),
+            variables,
+            "auxiliary ids",
+            self.var_match == Dddmp_VarMatchType::DDDMP_VAR_MATCHAUXIDS,
+        )?;
+        let mut compose_ids = check_ids(
+            self.compose_ids.as_deref(),
+            variables,
+            "composition ids",
+            self.var_match == Dddmp_VarMatchType::DDDMP_VAR_COMPOSEIDS,
+        )?;
+        if let Some(index) = compose_ids.iter().flatten().find(|index| **index < 0) {
+            let message = format!("Invalid variable index {} in composition ids.", index);
+            return Err(DddmpError::InvalidInput(message));
+        }
+        let root_match = if root_names.is_some() {
+            Dddmp_RootMatchType::DDDMP_ROOT_MATCHNAMES
+        } else {
+            Dddmp_RootMatchType::DDDMP_ROOT_MATCHLIST
+        };
+
+        // The whole input is needed to validate the header before loading.
+        let mut bytes = Vec::new();
+        input.read_to_end(&mut bytes)?;
+        let header = match kind {
+            // Plain DIMACS files do not have a header.
+            Loaded::Cnf(_) if header_field(&bytes, ".ver").is_none() => None,
+            _ => Some(DddmpHeader::load(&bytes)?),
+        };
+        if let Some(ref header) = header {
+            self.check_header(header)?;
+            let expected = match kind {
+                Loaded::Bdd => Dddmp_DecompType::DDDMP_BDD,
+                Loaded::Add => Dddmp_DecompType::DDDMP_ADD,
+                Loaded::Cnf(_) => Dddmp_DecompType::DDDMP_CNF,
+            };
+            if header.dd_type != expected {
+                let message = format!("Expected {:?}, but found {:?}.", expected, header.dd_type);
+                return Err(DddmpError::Format(message));
+            }
+        }
+
+        let root_names_ptr = root_names
+            .as_mut()
+            .map(|n| n.as_mut_ptr())
+            .unwrap_or(null_mut());
+        let var_names_ptr = var_names
+            .as_mut()
+            .map(|n| n.as_mut_ptr())
+            .unwrap_or(null_mut());
+        let aux_ids_ptr = aux_ids
+            .as_mut()
+            .map(|ids| ids.as_mut_ptr())
+            .unwrap_or(null_mut());
+        let compose_ids_ptr = compose_ids
+            .as_mut()
+            .map(|ids| ids.as_mut_ptr())
+            .unwrap_or(null_mut());
+        let file = CFile::reader(&bytes[..])?;
+        let mut roots: *mut *mut DdNode = null_mut();
+        let count = match kind {
+            Loaded::Bdd | Loaded::Add => {
+                let load: ArrayLoadFunction = if kind == Loaded::Add {
+                    Dddmp_cuddAddArrayLoad
+                } else {
+                    Dddmp_cuddBddArrayLoad
+                };
+                load(
+                    manager,
+                    root_match,
+                    root_names_ptr,
+                    self.var_match,
+                    var_names_ptr,
+                    aux_ids_ptr,
+                    compose_ids_ptr,
+                    DDDMP_MODE_DEFAULT,
+                    null_mut(),
+                    file.as_ptr(),
+                    &mut roots,
+                )
+            }
+            Loaded::Cnf(load_type) => {
+                let mut count: c_int = 0;
+                let status = Dddmp_cuddBddArrayLoadCnf(
+                    manager,
+                    root_match,
+                    root_names_ptr,
+                    self.var_match,
+                    var_names_ptr,
+                    aux_ids_ptr,
+                    compose_ids_ptr,
+                    load_type as c_int,
+                    null_mut(),
+                    file.as_ptr(),
+                    &mut roots,
+                    &mut count,
+                );
+                if status == DDDMP_SUCCESS {
+                    count
+                } else {
+                    0
+                }
+            }
+        };
+        file.close()?;
+        let roots = match NonNull::new(roots) {
+            Some(roots) if count > 0 => roots,
+            _ => {
+                let message = "Cannot load the decision diagrams.".to_string();
+                return Err(DddmpError::Format(message));
+            }
+        };
+        let nodes = std::slice::from_raw_parts(roots.as_ptr(), count as usize).to_vec();
+        libc::free(roots.as_ptr() as *mut c_void);
+
+        let names = match self.root_names {
+            Some(ref names) => Some(names.clone()),
+            None => header.and_then(|h| h.root_names),
+        };
+        Ok(nodes
+            .into_iter()
+            .enumerate()
+            .map(|(i, node)| DddmpRoot {
+                name: names.as_ref().and_then(|n| n.get(i).cloned()),
+                node,
+            })
+            .collect())
+    }
+}
+
+/// The kind of diagrams loaded by a `DddmpReader`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Loaded {
+    Bdd,
+    Add,
+    Cnf(Dddmp_DecompCnfLoadType),
+}
+
+type ArrayLoadFunction = unsafe extern "C" fn(
+    *mut DdManager,
+    Dddmp_RootMatchType,
+    *mut *mut c_char,
+    Dddmp_VarMatchType,
+    *mut *mut c_char,
+    *mut c_int,
+    *mut c_int,
+    c_int,
+    *mut c_char,
+    *mut libc::FILE,
+    *mut *mut *mut DdNode,
+) -> c_int;
+
+/// Convert names into C strings, checking that there is a name for every variable.
+pub(super) fn check_length(
+    names: &[String],
+    expected: usize,
+    what: &str,
+) -> Result<CStringArray, DddmpError> {
+    if names.len() < expected {
+        let message = format!(
+            "Expected {} {}, but only {} were given.",
+            expected,
+            what,
+            names.len()
+        );
+        return Err(DddmpError::InvalidInput(message));
+    }
+    c_strings(names)
+}
+
+/// Check that the `ids` cover all `variables` of the manager. If `required`, the ids must
+/// be given.
+pub(super) fn check_ids(
+    ids: Option<&[c_int]>,
+    variables: usize,
+    what: &str,
+    required: bool,
+) -> Result<Option<Vec<c_int>>, DddmpError> {
+    match ids {
+        Some(ids) if ids.len() < variables => {
+            let message = format!(
+                "Expected {} {}, but only {} were given.",
+                variables,
+                what,
+                ids.len()
+            );
+            Err(DddmpError::InvalidInput(message))
+        }
+        Some(ids) => Ok(Some(ids.to_vec())),
+        None if required => {
+            let message = format!("The {} are required by the variable matching mode.", what);
+            Err(DddmpError::InvalidInput(message))
+        }
+        None => Ok(None),
+    }
+}
+
+pub(super) fn c_strings(names: &[String]) -> Result<CStringArray, DddmpError> {
+    CStringArray::new(names).map_err(|e| DddmpError::InvalidInput(e.to_string()))
+}
+
+/// List the `items` which are not in `known`, or `None` if there are none.
+fn missing<T: PartialEq + ToString>(items: &[T], known: &[T]) -> Option<String> {
+    let missing: Vec<String> = items
+        .iter()
+        .filter(|item| !known.contains(item))
+        .map(|item| format!("`{}`", item.to_string()))
+        .collect();
+    if missing.is_empty() {
+        None
+    } else {
+        Some(missing.join(", "))
+    }
+}
@@ -0,0 +1,466 @@
+use cudd::*;
+use libc::{c_int, c_uint};
+use std::collections::VecDeque;
+use std::io::{Error, ErrorKind, Read, Result};
+use {DdManager, DdNode};
+
+/// A CNF formula in the DIMACS format. Variables are numbered from `1` and a literal is
+/// a variable (positive literal) or a negated variable (negative literal).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DimacsCnf {
+    /// The number of variables declared by the `p cnf` line.
+    pub var_count: usize,
+    /// The clauses of the formula.
+    pub clauses: Vec<Vec<c_int>>,
+}
+
+/// The order in which `DimacsCompiler` conjoins the clauses of a formula.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClauseSchedule {
+    /// Conjoin the clauses one by one, in the order of the file.
+    Linear,
+    /// Conjoin pairs of clauses, then pairs of the results, and so on (a balanced tree).
+    Balanced,
+    /// For every quantified variable (in the order given to `DimacsCompiler::quantify`),
+    /// conjoin all the parts which contain it, so that it can be quantified away. The
+    /// remaining parts are conjoined as in `Balanced`.
+    BucketElimination,
+    /// Starting with the first clause, always conjoin the part which adds the fewest
+    /// new variables to the support of the result.
+    MinWidth,
+}
+
+/// The state of the compilation reported to the progress callback after every conjunction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DimacsProgress {
+    /// The number of conjunctions computed so far.
+    pub conjunctions: usize,
+    /// The number of parts (clauses or intermediate results) which remain to be conjoined.
+    pub remaining: usize,
+    /// The number of nodes of the last computed conjunction.
+    pub size: usize,
+    /// The number of live nodes of the manager.
+    pub live_nodes: usize,
+}
+
+/// Compiles CNF formulas into BDDs. The DIMACS variable `v` corresponds to the BDD
+/// variable with index `v - 1`.
+///
+/// The auxiliary variables given to `quantify` are existentially quantified as soon as
+/// no remaining clause contains them (using `Cudd_bddAndAbstract`). The number of
+/// satisfying assignments of the formula over the remaining variables is then the
+/// number of minterms of the result (see `Cudd_CountMinterm` and `Cudd_ApaCountMinterm`).
+#[derive(Clone, Debug)]
+pub struct DimacsCompiler {
+    schedule: ClauseSchedule,
+    quantify: Vec<usize>,
+    node_limit: Option<c_uint>,
+}
+
+/// A clause or an intermediate result together with its support (DIMACS variables).
+struct Part {
+    node: *mut DdNode,
+    support: Vec<usize>,
+}
+
+fn invalid_data<S: Into<String>>(message: S) -> Error {
+    Error::new(ErrorKind::InvalidData, message.into())
+}
+
+impl DimacsCnf {
+    /// Parse a formula in the DIMACS format. Clauses can span several lines and the
+    /// formula can be terminated by a `%` line.
+    pub fn parse(dimacs: &str) -> Result<DimacsCnf> {
+        let mut var_count = None;
+        let mut clauses = Vec::new();
+        let mut clause = Vec::new();
+        for line in dimacs.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('c') {
+                continue;
+            }
+            if line.starts_with('%') {
+                break;
+            }
+            if line.starts_with('p') {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                // The variables are BDD variables, so their count has to fit into a `c_int`.
+                let count = match fields[..] {
+                    ["p", "cnf", vars, _] if var_count.is_none() => vars
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|count| *count <= c_int::MAX as usize),
+                    _ => None,
+                };
+                match count {
+                    Some(count) => var_count = Some(count),
+                    None => return Err(invalid_data(format!("Invalid problem line `{}`.", line))),
+                }
+                continue;
+            }
+            let var_count = match var_count {
+                Some(count) => count,
+                None => return Err(invalid_data("Missing `p cnf` line.")),
+            };
+            for token in line.split_whitespace() {
+                let literal: c_int = token
+                    .parse()
+                    .map_err(|_| invalid_data(format!("Invalid literal `{}`.", token)))?;
+                if literal == 0 {
+                    clauses.push(std::mem::take(&mut clause));
+                } else if literal.unsigned_abs() as usize > var_count {
+                    return Err(invalid_data(format!("Invalid literal `{}`.", literal)));
+                } else {
+                    clause.push(literal);
+                }
+            }
+        }
+        // The last clause does not have to be terminated.
+        if !clause.is_empty() {
+            clauses.push(clause);
+        }
+        match var_count {
+            Some(var_count) => Ok(DimacsCnf { var_count, clauses }),
+            None => Err(invalid_data("Missing `p cnf` line.")),
+        }
+    }
+
+    /// Read a formula in the DIMACS format from the `input`.
+    pub fn read<R: Read>(mut input: R) -> Result<DimacsCnf> {
+        let mut dimacs = String::new();
+        input.read_to_string(&mut dimacs)?;
+        DimacsCnf::parse(&dimacs)
+    }
+}
+
+/// Read a formula in the DIMACS format from the `input` and compile it into a BDD using
+/// the default `DimacsCompiler`. The result is referenced.
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager.
+pub unsafe fn from_dimacs<R: Read>(manager: *mut DdManager, input: R) -> Result<*mut DdNode> {
+    DimacsCompiler::new().compile(manager, &DimacsCnf::read(input)?)
+}
+
+impl Default for DimacsCompiler {
+    fn default() -> Self {
+        DimacsCompiler::new()
+    }
+}
+
+impl DimacsCompiler {
+    /// A compiler using bucket elimination, without quantified variables and node limit.
+    pub fn new() -> DimacsCompiler {
+        DimacsCompiler {
+            schedule: ClauseSchedule::BucketElimination,
+            quantify: Vec::new(),
+            node_limit: None,
+        }
+    }
+
+    /// Set the order in which the clauses are conjoined.
+    pub fn schedule(mut self, schedule: ClauseSchedule) -> DimacsCompiler {
+        self.schedule = schedule;
+        self
+    }
+
+    /// Set the (DIMACS) variables which are existentially quantified from the result.
+    pub fn quantify(mut self, vars: &[usize]) -> DimacsCompiler {
+        self.quantify = vars.to_vec();
+        self
+    }
+
+    /// Limit the number of new nodes created by every conjunction (see
+    /// `Cudd_bddAndLimit`). The compilation fails if the limit is exceeded.
+    pub fn node_limit(mut self, limit: c_uint) -> DimacsCompiler {
+        self.node_limit = Some(limit);
+        self
+    }
+
+    /// Compile the formula into a (referenced) BDD.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager.
+    pub unsafe fn compile(&self, manager: *mut DdManager, cnf: &DimacsCnf) -> Result<*mut DdNode> {
+        self.compile_with_progress(manager, cnf, |_| ())
+    }
+
+    /// Compile the formula into a (referenced) BDD, calling `progress` after every
+    /// conjunction.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager.
+    pub unsafe fn compile_with_progress<F: FnMut(&DimacsProgress)>(
+        &self,
+        manager: *mut DdManager,
+        cnf: &DimacsCnf,
+        progress: F,
+    ) -> Result<*mut DdNode> {
+        let mut quantified = vec![false; cnf.var_count + 1];
+        for var in &self.quantify {
+            if *var == 0 || *var > cnf.var_count {
+                let message = format!("Variable {} is not in the formula.", var);
+                return Err(Error::new(ErrorKind::InvalidInput, message));
+            }
+            quantified[*var] = true;
+        }
+        if cnf.var_count > 0 {
+            // Create all the variables, so that the indices match the DIMACS variables.
+            check_node(manager, Cudd_bddIthVar(manager, cnf.var_count as c_int - 1))?;
+        }
+
+        let mut compilation = Compilation {
+            manager,
+            node_limit: self.node_limit,
+            quantified,
+            occurrences: vec![0; cnf.var_count + 1],
+            conjunctions: 0,
+            progress,
+        };
+        let mut parts = VecDeque::new();
+        let result = compilation.run(self, cnf, &mut parts);
+        for part in parts {
+            Cudd_RecursiveDeref(manager, part.node);
+        }
+        result
+    }
+}
+
+struct Compilation<F: FnMut(&DimacsProgress)> {
+    manager: *mut DdManager,
+    node_limit: Option<c_uint>,
+    quantified: Vec<bool>,
+    /// The number of parts which contain each variable.
+    occurrences: Vec<usize>,
+    conjunctions: usize,
+    progress: F,
+}
+
+impl<F: FnMut(&DimacsProgress)> Compilation<F> {
+    /// Conjoin all the clauses. The `parts` which are not consumed must be dereferenced
+    /// by the caller.
+    unsafe fn run(
+        &mut self,
+        compiler: &DimacsCompiler,
+        cnf: &DimacsCnf,
+        parts: &mut VecDeque<Part>,
+    ) -> Result<*mut DdNode> {
+        for clause in &cnf.clauses {
+            let part = self.clause(clause)?;
+            for var in &part.support {
+                self.occurrences[*var] += 1;
+            }
+            parts.push_back(part);
+        }
+        // Variables which occur in a single clause can be quantified right away.
+        for part in parts.iter_mut() {
+            let local: Vec<usize> = part
+                .support
+                .iter()
+                .cloned()
+                .filter(|var| self.quantified[*var] && self.occurrences[*var] == 1)
+                .collect();
+            if !local.is_empty() {
+                let cube = self.cube(&local)?;
+                let node = Cudd_bddExistAbstract(self.manager, part.node, cube);
+                Cudd_RecursiveDeref(self.manager, cube);
+                let node = check_node(self.manager, node)?;
+                Cudd_Ref(node);
+                Cudd_RecursiveDeref(self.manager, part.node);
+                part.node = node;
+                part.support.retain(|var| !local.contains(var));
+            }
+        }
+
+        match compiler.schedule {
+            ClauseSchedule::Linear => {
+                while parts.len() > 1 {
+                    let first = parts.pop_front().unwrap();
+                    let second = parts.pop_front().unwrap();
+                    let part = self.conjoin(first, second, parts.len())?;
+                    parts.push_front(part);
+                }
+            }
+            ClauseSchedule::Balanced => self.balanced(parts)?,
+            ClauseSchedule::BucketElimination => {
+                for var in &compiler.quantify {
+                    let mut bucket = None;
+                    let mut i = 0;
+                    while i < parts.len() {
+                        if !parts[i].support.contains(var) {
+                            i += 1;
+                            continue;
+                        }
+                        let part = parts.remove(i).unwrap();
+                        bucket = Some(match bucket {
+                            Some(bucket) => self.conjoin(bucket, part, parts.len())?,
+                            None => part,
+                        });
+                    }
+                    if let Some(bucket) = bucket {
+                        parts.push_back(bucket);
+                    }
+                }
+                self.balanced(parts)?;
+            }
+            ClauseSchedule::MinWidth => {
+                let mut in_result = vec![false; self.quantified.len()];
+                while parts.len() > 1 {
+                    let first = parts.pop_front().unwrap();
+                    for var in &first.support {
+                        in_result[*var] = true;
+                    }
+                    let new_vars =
+                        |part: &Part| part.support.iter().filter(|var| !in_result[**var]).count();
+                    let next = (0..parts.len())
+                        .min_by_key(|i| new_vars(&parts[*i]))
+                        .unwrap();
+                    for var in &first.support {
+                        in_result[*var] = false;
+                    }
+                    let second = parts.remove(next).unwrap();
+                    let part = self.conjoin(first, second, parts.len())?;
+                    parts.push_front(part);
+                }
+            }
+        }
+
+        match parts.pop_front() {
+            Some(part) => Ok(part.node),
+            None => {
+                let one = Cudd_ReadOne(self.manager);
+                Cudd_Ref(one);
+                Ok(one)
+            }
+        }
+    }
+
+    /// Conjoin the parts in pairs until a single part remains.
+    unsafe fn balanced(&mut self, parts: &mut VecDeque<Part>) -> Result<()> {
+        while parts.len() > 1 {
+            let first = parts.pop_front().unwrap();
+            let second = parts.pop_front().unwrap();
+            let part = self.conjoin(first, second, parts.len())?;
+            parts.push_back(part);
+        }
+        Ok(())
+    }
+
+    /// Build the (referenced) BDD of a clause.
+    unsafe fn clause(&mut self, clause: &[c_int]) -> Result<Part> {
+        let mut node = Cudd_ReadLogicZero(self.manager);
+        Cudd_Ref(node);
+        let mut support = Vec::new();
+        for literal in clause {
+            let var = literal.unsigned_abs() as usize;
+            let mut bdd_var =
+                match check_node(self.manager, Cudd_bddIthVar(self.manager, var as c_int - 1)) {
+                    Ok(bdd_var) => bdd_var,
+                    Err(error) => {
+                        Cudd_RecursiveDeref(self.manager, node);
+                        return Err(error);
+                    }
+                };
+            if *literal < 0 {
+                bdd_var = Cudd_Not(bdd_var);
+            }
+            let result = Cudd_bddOr(self.manager, node, bdd_var);
+            if !result.is_null() {
+                Cudd_Ref(result);
+            }
+            Cudd_RecursiveDeref(self.manager, node);
+            node = check_node(self.manager, result)?;
+            if !support.contains(&var) {
+                support.push(var);
+            }
+        }
+        support.sort_unstable();
+        Ok(Part { node, support })
+    }
+
+    /// Conjoin two parts, quantifying the variables which do not occur in any other part.
+    /// Both parts are dereferenced.
+    unsafe fn conjoin(&mut self, first: Part, second: Part, remaining: usize) -> Result<Part> {
+        let mut support = first.support.clone();
+        support.extend(second.support.iter().cloned());
+        support.sort_unstable();
+        support.dedup();
+        let mut local = Vec::new();
+        for var in &support {
+            let count =
+                first.support.contains(var) as usize + second.support.contains(var) as usize;
+            self.occurrences[*var] -= count;
+            if self.quantified[*var] && self.occurrences[*var] == 0 {
+                local.push(*var);
+            } else {
+                self.occurrences[*var] += 1;
+            }
+        }
+        support.retain(|var| !local.contains(var));
+
+        let node = if local.is_empty() {
+            match self.node_limit {
+                Some(limit) => Cudd_bddAndLimit(self.manager, first.node, second.node, limit),
+                None => Cudd_bddAnd(self.manager, first.node, second.node),
+            }
+        } else {
+            let cube = match self.cube(&local) {
+                Ok(cube) => cube,
+                Err(error) => {
+                    Cudd_RecursiveDeref(self.manager, first.node);
+                    Cudd_RecursiveDeref(self.manager, second.node);
+                    return Err(error);
+                }
+            };
+            let node = match self.node_limit {
+                Some(limit) => {
+                    Cudd_bddAndAbstractLimit(self.manager, first.node, second.node, cube, limit)
+                }
+                None => Cudd_bddAndAbstract(self.manager, first.node, second.node, cube),
+            };
+            Cudd_RecursiveDeref(self.manager, cube);
+            node
+        };
+        if !node.is_null() {
+            Cudd_Ref(node);
+        }
+        Cudd_RecursiveDeref(self.manager, first.node);
+        Cudd_RecursiveDeref(self.manager, second.node);
+        let node = check_node(self.manager, node)?;
+
+        self.conjunctions += 1;
+        (self.progress)(&DimacsProgress {
+            conjunctions: self.conjunctions,
+            remaining,
+            size: Cudd_DagSize(node).max(0) as usize,
+            live_nodes: Cudd_ReadNodeCount(self.manager).max(0) as usize,
+        });
+        Ok(Part { node, support })
+    }
+
+    /// The (referenced) cube of the given DIMACS variables.
+    unsafe fn cube(&self, vars: &[usize]) -> Result<*mut DdNode> {
+        let mut indices: Vec<c_int> = vars.iter().map(|var| *var as c_int - 1).collect();
+        let cube = Cudd_IndicesToCube(self.manager, indices.as_mut_ptr(), indices.len() as c_int);
+        let cube = check_node(self.manager, cube)?;
+        Cudd_Ref(cube);
+        Ok(cube)
+    }
+}
+
+/// Turn a NULL result of CUDD into an error (distinguishing an exceeded node limit).
+unsafe fn check_node(manager: *mut DdManager, node: *mut DdNode) -> Result<*mut DdNode> {
+    if !node.is_null() {
+        return Ok(node);
+    }
+    let message = match Cudd_ReadErrorCode(manager) {
+        Cudd_ErrorType::CUDD_TOO_MANY_NODES => "The node limit was exceeded.",
+        Cudd_ErrorType::CUDD_TIMEOUT_EXPIRED => "The time limit was exceeded.",
+        _ => "CUDD ran out of memory.",
+    };
+    Cudd_ClearErrorCode(manager);
+    Err(Error::new(ErrorKind::Other, message))
+}
@@ -0,0 +1,201 @@
+use capture::capture;
+use cudd::{Cudd_DumpDot, Cudd_ReadSize, Cudd_ReadZddSize, Cudd_zddDumpDot};
+use libc::{c_char, c_int};
+use names::CStringArray;
+use registry::NamedVars;
+use std::io::{Error, ErrorKind, Result};
+use std::ptr::null;
+use {DdManager, DdNode};
+
+/// Options of the DOT export performed by `to_dot` and `zdd_to_dot`.
+///
+/// Without any options, the output is exactly what `Cudd_DumpDot` produces: nodes are ranked
+/// by the current variable order (one rank per level) and complemented edges are dotted.
+#[derive(Clone, Debug, Default)]
+pub struct DotOptions {
+    /// Names of the variables, indexed by variable index. If given, there must be a name for
+    /// every variable of the manager. Otherwise, the names registered with the manager are
+    /// used for BDDs (see `NamedVars`), or variable indices if there are none.
+    pub input_names: Option<Vec<String>>,
+    /// Names of the exported functions (one for each root). Otherwise, roots are numbered.
+    pub output_names: Option<Vec<String>>,
+    /// If set, complemented edges are drawn using this Graphviz colour (e.g. `"red"`).
+    pub complement_color: Option<String>,
+    /// If set, the nodes of every level are enclosed in a Graphviz cluster.
+    pub cluster_levels: bool,
+}
+
+impl DotOptions {
+    /// Create default options.
+    pub fn new() -> DotOptions {
+        DotOptions::default()
+    }
+
+    /// Use the given variable names (indexed by variable index).
+    pub fn input_names<S: AsRef<str>>(mut self, names: &[S]) -> DotOptions {
+        self.input_names = Some(names.iter().map(|s| s.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Use the given names of the exported functions.
+    pub fn output_names<S: AsRef<str>>(mut self, names: &[S]) -> DotOptions {
+        self.output_names = Some(names.iter().map(|s| s.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Draw complemented edges using the given colour.
+    pub fn complement_color(mut self, color: &str) -> DotOptions {
+        self.complement_color = Some(color.to_string());
+        self
+    }
+
+    /// Enclose the nodes of every level in a cluster.
+    pub fn cluster_levels(mut self, cluster: bool) -> DotOptions {
+        self.cluster_levels = cluster;
+        self
+    }
+}
+
+/// Export the given BDDs or ADDs to a Graphviz DOT string using `Cudd_DumpDot`.
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager and the `roots` must be valid nodes
+/// of this manager.
+pub unsafe fn to_dot(
+    manager: *mut DdManager,
+    roots: &[*mut DdNode],
+    options: &DotOptions,
+) -> Result<String> {
+    let variables = Cudd_ReadSize(manager);
+    let named;
+    let options = match (options.input_names.is_none(), manager.var_name_list()) {
+        (true, Some(names)) => {
+            named = DotOptions {
+                input_names: Some(names),
+                ..options.clone()
+            };
+            &named
+        }
+        _ => options,
+    };
+    dump(roots, variables, options, |n, f, inames, onames, fp| {
+        Cudd_DumpDot(manager, n, f, inames, onames, fp)
+    })
+}
+
+/// Export the given ZDDs to a Graphviz DOT string using `Cudd_zddDumpDot`.
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager and the `roots` must be valid ZDD nodes
+/// of this manager.
+pub unsafe fn zdd_to_dot(
+    manager: *mut DdManager,
+    roots: &[*mut DdNode],
+    options: &DotOptions,
+) -> Result<String> {
+    let variables = Cudd_ReadZddSize(manager);
+    dump(roots, variables, options, |n, f, inames, onames, fp| {
+        Cudd_zddDumpDot(manager, n, f, inames, onames, fp)
+    })
+}
+
+unsafe fn dump<F>(
+    roots: &[*mut DdNode],
+    variables: c_int,
+    options: &DotOptions,
+    action: F,
+) -> Result<String>
+where
+    F: FnOnce(
+        c_int,
+        *mut *mut DdNode,
+        *const *const c_char,
+        *const *const c_char,
+        *mut libc::FILE,
+    ) -> c_int,
+{
+    let inames = match options.input_names {
+        Some(ref names) if names.len() < variables as usize => {
+            let message = format!(
+                "Expected {} variable names, but only {} were given.",
+                variables,
+                names.len()
+            );
+            return Err(Error::new(ErrorKind::InvalidInput, message));
+        }
+        Some(ref names) => Some(CStringArray::new(&escape_all(names))?),
+        None => None,
+    };
+    let onames = match options.output_names {
+        Some(ref names) if names.len() != roots.len() => {
+            let message = format!(
+                "Expected {} output names, but {} were given.",
+                roots.len(),
+                names.len()
+            );
+            return Err(Error::new(ErrorKind::InvalidInput, message));
+        }
+        Some(ref names) => Some(CStringArray::new(&escape_all(names))?),
+        None => None,
+    };
+    let inames_ptr = inames.as_ref().map(|a| a.as_ptr()).unwrap_or(null());
+    let onames_ptr = onames.as_ref().map(|a| a.as_ptr()).unwrap_or(null());
+    let dot = capture(|fp| {
+        action(
+            roots.len() as c_int,
+            roots.as_ptr() as *mut *mut DdNode,
+            inames_ptr,
+            onames_ptr,
+            fp,
+        )
+    })?;
+    Ok(restyle(&dot, options))
+}
+
+/// Escape backslashes and double quotes, since CUDD writes the names into quoted DOT
+/// identifiers. Backslashes are escaped first, so that the escaped quotes are kept intact.
+fn escape(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_all(names: &[String]) -> Vec<String> {
+    names.iter().map(|name| escape(name)).collect()
+}
+
+/// Apply the styling `options` to the output of `Cudd_DumpDot`.
+fn restyle(dot: &str, options: &DotOptions) -> String {
+    if options.complement_color.is_none() && !options.cluster_levels {
+        return dot.to_string();
+    }
+    let mut result = String::with_capacity(dot.len());
+    let mut level = 0;
+    for line in dot.lines() {
+        if options.cluster_levels && line.starts_with("{ rank = same; \" ") {
+            // Rank of one variable level (the constant nodes start with `"CONST NODES"`).
+            result.push_str(&format!(
+                "subgraph \"cluster_{}\" {{ style = dashed; color = grey; {}",
+                level,
+                &line[2..]
+            ));
+            level += 1;
+        } else if let Some(ref color) = options.complement_color {
+            match line.strip_suffix("[style = dotted];") {
+                Some(edge) => {
+                    let color = escape(color);
+                    result.push_str(&format!("{}[style = dotted, color = \"{}\"];", edge, color))
+                }
+                None => result.push_str(line),
+            }
+        } else {
+            result.push_str(line);
+        }
+        result.push('\n');
+        if options.cluster_levels && line.starts_with("digraph") {
+            // Needed to keep the ranks of nodes consistent across clusters.
+            result.push_str("newrank = true;\n");
+        }
+    }
+    result
+}
@@ -1,6 +1,12 @@
+use cudd::Cudd_EpdCountMinterm;
 use libc::{c_int, c_void};
+use std::cmp::Ordering;
+use std::ffi::CStr;
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use std::ops::{Add, Div, Mul, Sub};
 use std::os::raw::{c_char, c_double};
-use EpDouble;
+use {DdManager, DdNode, EpDouble};
 
 extern "C" {
     pub fn EpdAlloc() -> *mut EpDouble;
@@ -57,3 +63,253 @@ extern "C" {
     pub fn IsNanDouble(value: c_double) -> c_int;
     pub fn IsNanOrInfDouble(value: c_double) -> c_int;
 }
+
+/// An extended double precision number stored by value, with the same layout as the C
+/// struct behind `EpDouble` (a `double` mantissa and an `int` binary exponent).
+///
+/// Use `Epd::as_ptr` and `Epd::as_mut_ptr` to pass it to the `Epd*` functions above.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Epd {
+    value: c_double,
+    exponent: c_int,
+}
+
+impl Epd {
+    /// Positive zero.
+    pub fn zero() -> Epd {
+        Epd::with(|epd| unsafe { EpdMakeZero(epd, 0) })
+    }
+
+    /// Not a number.
+    pub fn nan() -> Epd {
+        Epd::with(|epd| unsafe { EpdMakeNan(epd) })
+    }
+
+    /// Positive or negative infinity.
+    pub fn infinity(negative: bool) -> Epd {
+        Epd::with(|epd| unsafe { EpdMakeInf(epd, negative as c_int) })
+    }
+
+    /// The number `2^n` (see `EpdPow2`).
+    pub fn pow2(n: c_int) -> Epd {
+        Epd::with(|epd| unsafe { EpdPow2(n, epd) })
+    }
+
+    /// The number of minterms of the BDD `node` over `nvars` variables (see
+    /// `Cudd_EpdCountMinterm`), which cannot overflow unlike `Cudd_CountMinterm`.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` and the `node` must be valid.
+    pub unsafe fn count_minterm(
+        manager: *mut DdManager,
+        node: *mut DdNode,
+        nvars: c_int,
+    ) -> Result<Epd> {
+        let mut epd = Epd::zero();
+        if Cudd_EpdCountMinterm(manager, node, nvars, epd.as_mut_ptr()) != 0 {
+            return Err(Error::new(ErrorKind::Other, "CUDD ran out of memory."));
+        }
+        Ok(epd)
+    }
+
+    /// A pointer to the number, usable as a C `EpDouble`.
+    pub fn as_ptr(&self) -> *const EpDouble {
+        self as *const Epd as *const EpDouble
+    }
+
+    /// A mutable pointer to the number, usable as a C `EpDouble`.
+    pub fn as_mut_ptr(&mut self) -> *mut EpDouble {
+        self as *mut Epd as *mut EpDouble
+    }
+
+    /// The mantissa of the number (in `[1, 2)` for normalised finite non-zero numbers).
+    pub fn mantissa(&self) -> f64 {
+        self.value
+    }
+
+    /// The binary exponent of the number.
+    pub fn exponent(&self) -> i32 {
+        self.exponent
+    }
+
+    /// The mantissa (in `[1, 10)`) and the decimal exponent of the number (see
+    /// `EpdGetValueAndDecimalExponent`).
+    pub fn decimal(&self) -> (f64, i32) {
+        let mut value = 0.0;
+        let mut exponent = 0;
+        unsafe { EpdGetValueAndDecimalExponent(self.as_ptr(), &mut value, &mut exponent) };
+        (value, exponent)
+    }
+
+    /// The closest `f64` value, which can be infinite or zero if the number is out of range.
+    pub fn to_f64(&self) -> f64 {
+        // Apply the exponent in two steps, so that intermediate powers do not overflow.
+        let half = self.exponent / 2;
+        self.value * 2f64.powi(half) * 2f64.powi(self.exponent - half)
+    }
+
+    /// True if the number is zero.
+    pub fn is_zero(&self) -> bool {
+        unsafe { EpdIsZero(self.as_ptr()) != 0 }
+    }
+
+    /// True if the number is infinite.
+    pub fn is_inf(&self) -> bool {
+        unsafe { EpdIsInf(self.as_ptr()) != 0 }
+    }
+
+    /// True if the number is not a number.
+    pub fn is_nan(&self) -> bool {
+        unsafe { EpdIsNan(self.as_ptr()) != 0 }
+    }
+
+    /// True if the number is infinite or not a number.
+    pub fn is_nan_or_inf(&self) -> bool {
+        unsafe { EpdIsNanOrInf(self.as_ptr()) != 0 }
+    }
+
+    fn negated(self) -> Epd {
+        Epd {
+            value: -self.value,
+            exponent: self.exponent,
+        }
+    }
+
+    fn with<R, F: FnOnce(*mut EpDouble) -> R>(init: F) -> Epd {
+        let mut epd = Epd {
+            value: 0.0,
+            exponent: 0,
+        };
+        init(epd.as_mut_ptr());
+        epd
+    }
+}
+
+impl Default for Epd {
+    fn default() -> Epd {
+        Epd::zero()
+    }
+}
+
+impl From<f64> for Epd {
+    fn from(value: f64) -> Epd {
+        if value == 0.0 {
+            // Zero has no normalised mantissa, so it is created explicitly.
+            Epd::with(|epd| unsafe { EpdMakeZero(epd, value.is_sign_negative() as c_int) })
+        } else {
+            Epd::with(|epd| unsafe { EpdConvert(value, epd) })
+        }
+    }
+}
+
+impl PartialEq for Epd {
+    fn eq(&self, other: &Epd) -> bool {
+        unsafe {
+            EpdCmp(
+                self.as_ptr() as *const c_void,
+                other.as_ptr() as *const c_void,
+            ) == 0
+        }
+    }
+}
+
+impl PartialOrd for Epd {
+    /// `EpdCmp` only tests equality, so the numbers are ordered by the sign of their
+    /// difference.
+    fn partial_cmp(&self, other: &Epd) -> Option<Ordering> {
+        if self.is_nan() || other.is_nan() {
+            None
+        } else if self == other {
+            Some(Ordering::Equal)
+        } else {
+            let difference = *self - *other;
+            if difference.is_nan() || difference.is_zero() {
+                // Infinities with the same sign.
+                Some(Ordering::Equal)
+            } else if difference.value < 0.0 {
+                Some(Ordering::Less)
+            } else {
+                Some(Ordering::Greater)
+            }
+        }
+    }
+}
+
+// `EpdNormalize` expects a non-zero mantissa, so results which involve zeros, including
+// the exact cancellation of `a + (-a)` and `a - a`, are computed without calling CUDD.
+fn add_zero(a: Epd, b: Epd) -> Option<Epd> {
+    if a.is_zero() {
+        Some(b)
+    } else if b.is_zero() {
+        Some(a)
+    } else if cancels(a, b.negated()) {
+        Some(Epd::zero())
+    } else {
+        None
+    }
+}
+
+fn sub_zero(a: Epd, b: Epd) -> Option<Epd> {
+    if b.is_zero() {
+        Some(a)
+    } else if cancels(a, b) {
+        Some(Epd::zero())
+    } else {
+        None
+    }
+}
+
+// Normalised mantissas are in `[1, 2)`, so the difference of two finite numbers is only
+// zero if they are identical.
+fn cancels(a: Epd, b: Epd) -> bool {
+    !a.is_nan_or_inf() && a.value == b.value && a.exponent == b.exponent
+}
+
+fn mul_zero(a: Epd, b: Epd) -> Option<Epd> {
+    if (a.is_zero() || b.is_zero()) && !a.is_nan_or_inf() && !b.is_nan_or_inf() {
+        Some(Epd::zero())
+    } else {
+        None
+    }
+}
+
+fn div_zero(a: Epd, b: Epd) -> Option<Epd> {
+    if a.is_zero() && !b.is_zero() && !b.is_nan() {
+        Some(Epd::zero())
+    } else {
+        None
+    }
+}
+
+macro_rules! epd_operator {
+    ($trait:ident, $method:ident, $function:ident, $zero:ident) => {
+        impl $trait for Epd {
+            type Output = Epd;
+
+            fn $method(self, other: Epd) -> Epd {
+                $zero(self, other).unwrap_or_else(|| {
+                    Epd::with(|result| unsafe { $function(self.as_ptr(), other.as_ptr(), result) })
+                })
+            }
+        }
+    };
+}
+
+epd_operator!(Add, add, EpdAdd3, add_zero);
+epd_operator!(Sub, sub, EpdSubtract3, sub_zero);
+epd_operator!(Mul, mul, EpdMultiply3, mul_zero);
+epd_operator!(Div, div, EpdDivide3, div_zero);
+
+impl fmt::Display for Epd {
+    /// Formats the number in the scientific notation using `EpdGetString`, e.g. `1.267651e+30`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut buffer = [0 as c_char; 64];
+        let string = unsafe {
+            EpdGetString(self.as_ptr(), buffer.as_mut_ptr());
+            CStr::from_ptr(buffer.as_ptr())
+        };
+        f.write_str(&string.to_string_lossy())
+    }
+}
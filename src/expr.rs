@@ -0,0 +1,540 @@
+use cudd::*;
+use libc::{c_int, c_void};
+use names::CStringArray;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use std::str::FromStr;
+use {DdManager, DdNode};
+
+/// A Boolean expression over named variables.
+///
+/// The textual syntax uses (from the lowest to the highest precedence) `<->`, `->`
+/// (right associative), `|`, `^`, `&` and the negation `!` (or `~`). The constants are
+/// `0`/`false` and `1`/`true`. Quantifiers (`exists x, y. f` and `forall x. f`) extend
+/// as far to the right as possible. Variable names start with a letter or `_` and contain
+/// letters, digits, `_`, `'`, `[` and `]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    /// A constant (`0` or `1`).
+    Const(bool),
+    /// A variable.
+    Var(String),
+    /// A negation (`!f`).
+    Not(Box<Expr>),
+    /// A conjunction (`f & g`).
+    And(Box<Expr>, Box<Expr>),
+    /// A disjunction (`f | g`).
+    Or(Box<Expr>, Box<Expr>),
+    /// An exclusive or (`f ^ g`).
+    Xor(Box<Expr>, Box<Expr>),
+    /// An implication (`f -> g`).
+    Implies(Box<Expr>, Box<Expr>),
+    /// An equivalence (`f <-> g`).
+    Iff(Box<Expr>, Box<Expr>),
+    /// An existential quantification (`exists x, y. f`).
+    Exists(Vec<String>, Box<Expr>),
+    /// A universal quantification (`forall x, y. f`).
+    Forall(Vec<String>, Box<Expr>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Const(bool),
+    Not,
+    And,
+    Or,
+    Xor,
+    Implies,
+    Iff,
+    Exists,
+    Forall,
+    Comma,
+    Dot,
+    Open,
+    Close,
+}
+
+fn invalid_input<S: Into<String>>(message: S) -> Error {
+    Error::new(ErrorKind::InvalidInput, message.into())
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_'[]".contains(c)
+}
+
+/// Split the expression into tokens, each with its (byte) position.
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        let token = match c {
+            _ if c.is_whitespace() => continue,
+            '!' | '~' => Token::Not,
+            '&' => Token::And,
+            '|' => Token::Or,
+            '^' => Token::Xor,
+            ',' => Token::Comma,
+            '.' => Token::Dot,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '-' if text[position..].starts_with("->") => {
+                chars.next();
+                Token::Implies
+            }
+            '<' if text[position..].starts_with("<->") => {
+                chars.next();
+                chars.next();
+                Token::Iff
+            }
+            '0' | '1' if !text[position + 1..].starts_with(is_name_char) => Token::Const(c == '1'),
+            _ if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = position + c.len_utf8();
+                while let Some(&(next, c)) = chars.peek() {
+                    if !is_name_char(c) {
+                        break;
+                    }
+                    end = next + c.len_utf8();
+                    chars.next();
+                }
+                match &text[position..end] {
+                    "true" => Token::Const(true),
+                    "false" => Token::Const(false),
+                    "exists" => Token::Exists,
+                    "forall" => Token::Forall,
+                    name => Token::Ident(name.to_string()),
+                }
+            }
+            _ => {
+                let message = format!("Unexpected `{}` at position {}.", c, position);
+                return Err(invalid_input(message));
+            }
+        };
+        tokens.push((position, token));
+    }
+    Ok(tokens)
+}
+
+/// A recursive descent parser over the tokens of an expression.
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    length: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn error(&self) -> Error {
+        match self.tokens.get(self.position) {
+            Some((position, _)) => {
+                invalid_input(format!("Unexpected token at position {}.", position))
+            }
+            None => invalid_input(format!(
+                "Unexpected end of expression at position {}.",
+                self.length
+            )),
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> Result<()> {
+        if self.peek() == Some(&token) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    fn iff(&mut self) -> Result<Expr> {
+        let mut left = self.implies()?;
+        while self.peek() == Some(&Token::Iff) {
+            self.position += 1;
+            left = Expr::Iff(Box::new(left), Box::new(self.implies()?));
+        }
+        Ok(left)
+    }
+
+    fn implies(&mut self) -> Result<Expr> {
+        let left = self.binary(0)?;
+        if self.peek() == Some(&Token::Implies) {
+            self.position += 1;
+            return Ok(Expr::Implies(Box::new(left), Box::new(self.implies()?)));
+        }
+        Ok(left)
+    }
+
+    /// Left associative operators: `|` (level 0), `^` (level 1) and `&` (level 2).
+    fn binary(&mut self, level: usize) -> Result<Expr> {
+        let operators = [Token::Or, Token::Xor, Token::And];
+        if level == operators.len() {
+            return self.unary();
+        }
+        let mut left = self.binary(level + 1)?;
+        while self.peek() == Some(&operators[level]) {
+            self.position += 1;
+            let right = Box::new(self.binary(level + 1)?);
+            left = match level {
+                0 => Expr::Or(Box::new(left), right),
+                1 => Expr::Xor(Box::new(left), right),
+                _ => Expr::And(Box::new(left), right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        let token = self.peek().cloned().ok_or_else(|| self.error())?;
+        let expr = match token {
+            Token::Not => {
+                self.position += 1;
+                return Ok(Expr::Not(Box::new(self.unary()?)));
+            }
+            Token::Exists | Token::Forall => {
+                self.position += 1;
+                let mut vars = Vec::new();
+                loop {
+                    match self.peek().cloned() {
+                        Some(Token::Ident(name)) => vars.push(name),
+                        _ => return Err(self.error()),
+                    }
+                    self.position += 1;
+                    if self.peek() != Some(&Token::Comma) {
+                        break;
+                    }
+                    self.position += 1;
+                }
+                self.expect(Token::Dot)?;
+                let body = Box::new(self.iff()?);
+                return Ok(if token == Token::Exists {
+                    Expr::Exists(vars, body)
+                } else {
+                    Expr::Forall(vars, body)
+                });
+            }
+            Token::Open => {
+                self.position += 1;
+                let expr = self.iff()?;
+                self.expect(Token::Close)?;
+                return Ok(expr);
+            }
+            Token::Ident(name) => Expr::Var(name),
+            Token::Const(value) => Expr::Const(value),
+            _ => return Err(self.error()),
+        };
+        self.position += 1;
+        Ok(expr)
+    }
+}
+
+impl Expr {
+    /// Parse an expression.
+    pub fn parse(text: &str) -> Result<Expr> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+            length: text.len(),
+        };
+        let expr = parser.iff()?;
+        if parser.position < parser.tokens.len() {
+            return Err(parser.error());
+        }
+        Ok(expr)
+    }
+
+    /// The binding strength of the top-level operator (higher binds tighter).
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Exists(..) | Expr::Forall(..) => 0,
+            Expr::Iff(..) => 1,
+            Expr::Implies(..) => 2,
+            Expr::Or(..) => 3,
+            Expr::Xor(..) => 4,
+            Expr::And(..) => 5,
+            Expr::Not(..) => 6,
+            Expr::Const(..) | Expr::Var(..) => 7,
+        }
+    }
+
+    /// Build the (referenced) BDD of the expression. The variable with name `names[i]`
+    /// is the BDD variable with index `i`.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager.
+    pub unsafe fn to_bdd<S: AsRef<str>>(
+        &self,
+        manager: *mut DdManager,
+        names: &[S],
+    ) -> Result<*mut DdNode> {
+        let mut indices = HashMap::new();
+        for (index, name) in names.iter().enumerate() {
+            if indices.insert(name.as_ref(), index as c_int).is_some() {
+                let message = format!("Name `{}` is used more than once.", name.as_ref());
+                return Err(invalid_input(message));
+            }
+        }
+        self.build(manager, &indices)
+    }
+
+    unsafe fn build(
+        &self,
+        manager: *mut DdManager,
+        indices: &HashMap<&str, c_int>,
+    ) -> Result<*mut DdNode> {
+        let index = |name: &String| match indices.get(name.as_str()) {
+            Some(index) => Ok(*index),
+            None => Err(invalid_input(format!("Unknown variable `{}`.", name))),
+        };
+        let (left, right) = match self {
+            Expr::Const(value) => {
+                let node = if *value {
+                    Cudd_ReadOne(manager)
+                } else {
+                    Cudd_ReadLogicZero(manager)
+                };
+                Cudd_Ref(node);
+                return Ok(node);
+            }
+            Expr::Var(name) => {
+                let node = check_node(Cudd_bddIthVar(manager, index(name)?))?;
+                Cudd_Ref(node);
+                return Ok(node);
+            }
+            Expr::Not(inner) => return Ok(Cudd_Not(inner.build(manager, indices)?)),
+            Expr::Exists(vars, inner) | Expr::Forall(vars, inner) => {
+                let mut cube_indices = vars.iter().map(index).collect::<Result<Vec<c_int>>>()?;
+                let inner = inner.build(manager, indices)?;
+                let cube = Cudd_IndicesToCube(
+                    manager,
+                    cube_indices.as_mut_ptr(),
+                    cube_indices.len() as c_int,
+                );
+                if cube.is_null() {
+                    Cudd_RecursiveDeref(manager, inner);
+                    return Err(out_of_memory());
+                }
+                Cudd_Ref(cube);
+                let result = if let Expr::Exists(..) = self {
+                    Cudd_bddExistAbstract(manager, inner, cube)
+                } else {
+                    Cudd_bddUnivAbstract(manager, inner, cube)
+                };
+                return finish(manager, result, &[inner, cube]);
+            }
+            Expr::And(left, right)
+            | Expr::Or(left, right)
+            | Expr::Xor(left, right)
+            | Expr::Implies(left, right)
+            | Expr::Iff(left, right) => (left, right),
+        };
+        let left = left.build(manager, indices)?;
+        let right = match right.build(manager, indices) {
+            Ok(right) => right,
+            Err(error) => {
+                Cudd_RecursiveDeref(manager, left);
+                return Err(error);
+            }
+        };
+        let result = match self {
+            Expr::And(..) => Cudd_bddAnd(manager, left, right),
+            Expr::Or(..) => Cudd_bddOr(manager, left, right),
+            Expr::Xor(..) => Cudd_bddXor(manager, left, right),
+            Expr::Implies(..) => Cudd_bddOr(manager, Cudd_Not(left), right),
+            _ => Cudd_bddXnor(manager, left, right),
+        };
+        finish(manager, result, &[left, right])
+    }
+}
+
+/// Reference the `result` (if it is not NULL) and dereference the `operands`.
+unsafe fn finish(
+    manager: *mut DdManager,
+    result: *mut DdNode,
+    operands: &[*mut DdNode],
+) -> Result<*mut DdNode> {
+    if !result.is_null() {
+        Cudd_Ref(result);
+    }
+    for operand in operands {
+        Cudd_RecursiveDeref(manager, *operand);
+    }
+    check_node(result)
+}
+
+fn out_of_memory() -> Error {
+    Error::new(ErrorKind::Other, "CUDD ran out of memory.")
+}
+
+fn check_node(node: *mut DdNode) -> Result<*mut DdNode> {
+    if node.is_null() {
+        Err(out_of_memory())
+    } else {
+        Ok(node)
+    }
+}
+
+impl FromStr for Expr {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Expr> {
+        Expr::parse(text)
+    }
+}
+
+impl fmt::Display for Expr {
+    /// Print the expression in the syntax accepted by `Expr::parse`, with as few
+    /// parentheses as possible.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operand = |f: &mut fmt::Formatter, expr: &Expr, min: u8| {
+            if expr.precedence() < min {
+                write!(f, "({})", expr)
+            } else {
+                write!(f, "{}", expr)
+            }
+        };
+        let (left, right, operator) = match self {
+            Expr::Const(value) => return write!(f, "{}", if *value { "1" } else { "0" }),
+            Expr::Var(name) => return write!(f, "{}", name),
+            Expr::Not(inner) => {
+                write!(f, "!")?;
+                return operand(f, inner, 6);
+            }
+            Expr::Exists(vars, inner) | Expr::Forall(vars, inner) => {
+                let quantifier = if let Expr::Exists(..) = self {
+                    "exists"
+                } else {
+                    "forall"
+                };
+                return write!(f, "{} {}. {}", quantifier, vars.join(", "), inner);
+            }
+            Expr::And(left, right) => (left, right, "&"),
+            Expr::Or(left, right) => (left, right, "|"),
+            Expr::Xor(left, right) => (left, right, "^"),
+            Expr::Implies(left, right) => (left, right, "->"),
+            Expr::Iff(left, right) => (left, right, "<->"),
+        };
+        // Left associative operators need parentheses on the right (and `->` on the left).
+        let precedence = self.precedence();
+        let (left_min, right_min) = match self {
+            Expr::Implies(..) => (precedence + 1, precedence),
+            _ => (precedence, precedence + 1),
+        };
+        operand(f, left, left_min)?;
+        write!(f, " {} ", operator)?;
+        operand(f, right, right_min)
+    }
+}
+
+/// Parse the expression and build its (referenced) BDD (see `Expr::to_bdd`).
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager.
+pub unsafe fn parse_bdd<S: AsRef<str>>(
+    manager: *mut DdManager,
+    expr: &str,
+    names: &[S],
+) -> Result<*mut DdNode> {
+    Expr::parse(expr)?.to_bdd(manager, names)
+}
+
+/// Print a BDD in a factored form using `Cudd_FactoredFormString`. The `names` are
+/// indexed by variable index and must cover all variables of the manager.
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager and `f` must be a valid BDD of this manager.
+pub unsafe fn factored_form<S: AsRef<str>>(
+    manager: *mut DdManager,
+    f: *mut DdNode,
+    names: &[S],
+) -> Result<String> {
+    let variables = Cudd_ReadSize(manager) as usize;
+    if names.len() < variables {
+        let message = format!(
+            "Expected {} names, but only {} were given.",
+            variables,
+            names.len()
+        );
+        return Err(invalid_input(message));
+    }
+    let names = CStringArray::new(names)?;
+    let string = Cudd_FactoredFormString(manager, f, names.as_ptr());
+    if string.is_null() {
+        return Err(out_of_memory());
+    }
+    let result = CStr::from_ptr(string).to_string_lossy().into_owned();
+    libc::free(string as *mut c_void);
+    Ok(result)
+}
+
+/// Print a BDD as an irredundant sum of products (computed using `Cudd_zddIsop`), for
+/// example `a & !b | c`. The `names` are indexed by variable index.
+///
+/// The cover is computed in the ZDD variables of the manager, which are created using
+/// `Cudd_zddVarsFromBddVars(manager, 2)` if needed.
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager and `f` must be a valid BDD of this manager.
+pub unsafe fn isop_cover<S: AsRef<str>>(
+    manager: *mut DdManager,
+    f: *mut DdNode,
+    names: &[S],
+) -> Result<String> {
+    if f == Cudd_ReadOne(manager) || f == Cudd_ReadLogicZero(manager) {
+        return Ok(if f == Cudd_ReadOne(manager) { "1" } else { "0" }.to_string());
+    }
+    let variables = Cudd_ReadSize(manager);
+    if Cudd_ReadZddSize(manager) != 2 * variables && Cudd_zddVarsFromBddVars(manager, 2) == 0 {
+        return Err(Error::new(
+            ErrorKind::Other,
+            "Cannot create the ZDD variables.",
+        ));
+    }
+    let mut cover = std::ptr::null_mut();
+    let bdd = Cudd_zddIsop(manager, f, f, &mut cover);
+    if bdd.is_null() {
+        return Err(out_of_memory());
+    }
+    Cudd_Ref(bdd);
+    Cudd_Ref(cover);
+    Cudd_RecursiveDeref(manager, bdd);
+
+    // ZDD variable `2 * i` is the positive and `2 * i + 1` the negative literal of `i`.
+    let mut cubes = Vec::new();
+    let mut missing = None;
+    Cudd_zddForeachPath(manager, cover, |path| {
+        let path = std::slice::from_raw_parts(path, 2 * variables as usize);
+        let mut literals = Vec::new();
+        for (zdd_var, value) in path.iter().enumerate() {
+            if *value != 1 {
+                continue;
+            }
+            let var = zdd_var / 2;
+            let name = match names.get(var) {
+                Some(name) => name.as_ref(),
+                None => {
+                    missing = Some(var);
+                    continue;
+                }
+            };
+            if zdd_var % 2 == 0 {
+                literals.push(name.to_string());
+            } else {
+                literals.push(format!("!{}", name));
+            }
+        }
+        cubes.push(literals.join(" & "));
+    });
+    Cudd_RecursiveDerefZdd(manager, cover);
+    if let Some(var) = missing {
+        return Err(invalid_input(format!("Missing name of variable {}.", var)));
+    }
+    Ok(cubes.join(" | "))
+}
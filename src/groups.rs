@@ -0,0 +1,483 @@
+use cudd::{
+    Cudd_FreeTree, Cudd_FreeZddTree, Cudd_MakeTreeNode, Cudd_MakeZddTreeNode, Cudd_ReadInvPerm,
+    Cudd_ReadSize, Cudd_ReadTree, Cudd_ReadZddTree, Cudd_SetTree,
+};
+use libc::{c_int, c_uint};
+use mtr::{Mtr_FreeTree, Mtr_InitGroupTree, Mtr_MakeGroup, MTR_DEFAULT, MTR_FIXED, MTR_SOFT};
+use registry::{NamedVars, VarNames};
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::ops::Range;
+use std::path::Path;
+use {DdManager, MtrHalfWord, MtrNode};
+
+/// A group of variables which are kept adjacent during reordering, together with
+/// nested subgroups.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Group {
+    /// The index of the first variable of the group.
+    pub low: c_uint,
+    /// The number of variables in the group.
+    pub size: c_uint,
+    /// The `MTR_*` flags of the group (e.g. `MTR_FIXED`).
+    pub flags: c_uint,
+    /// The nested groups, ordered by their first variable.
+    pub children: Vec<Group>,
+}
+
+/// A tree of variable groups, which can be attached to a manager to constrain the
+/// reordering of BDD variables (`Cudd_MakeTreeNode`) or ZDD variables
+/// (`Cudd_MakeZddTreeNode`).
+///
+/// Groups are declared using ranges of variable indices, for example
+/// `tree.group(0..4).fixed()` or `tree.group(4..8).group(4..6)` for nested groups.
+///
+/// The tree attached to a manager is owned by the manager: it is freed by `Cudd_Quit`
+/// or when another tree is attached. A `GroupTree` is only a description of the groups,
+/// so it can be attached to several managers and read back using `GroupTree::read`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GroupTree {
+    /// The top-level groups, ordered by their first variable.
+    pub groups: Vec<Group>,
+}
+
+fn invalid_input<S: Into<String>>(message: S) -> Error {
+    Error::new(ErrorKind::InvalidInput, message.into())
+}
+
+fn invalid_data<S: Into<String>>(message: S) -> Error {
+    Error::new(ErrorKind::InvalidData, message.into())
+}
+
+/// Add a new group to `groups`, keeping them ordered by their first variable.
+fn add_group(groups: &mut Vec<Group>, range: Range<c_uint>) -> &mut Group {
+    let group = Group::new(range);
+    let position = groups
+        .iter()
+        .position(|other| other.low > group.low)
+        .unwrap_or(groups.len());
+    groups.insert(position, group);
+    &mut groups[position]
+}
+
+/// Check that the `groups` are non-empty, disjoint and contained in the `parent` range.
+fn check_groups(groups: &[Group], parent: Range<c_uint>) -> Result<()> {
+    let mut end = parent.start;
+    for group in groups {
+        let range = group.range();
+        if group.size == 0 || range.start < end || range.end > parent.end {
+            let message = format!(
+                "Group {:?} is empty, overlaps another group or exceeds its parent {:?}.",
+                range, parent
+            );
+            return Err(invalid_input(message));
+        }
+        check_groups(&group.children, range.clone())?;
+        end = range.end;
+    }
+    Ok(())
+}
+
+impl Group {
+    /// A group of the variables with indices in the given range.
+    pub fn new(range: Range<c_uint>) -> Group {
+        Group {
+            low: range.start,
+            size: range.end.saturating_sub(range.start),
+            flags: MTR_DEFAULT,
+            children: Vec::new(),
+        }
+    }
+
+    /// The range of variable indices of the group.
+    pub fn range(&self) -> Range<c_uint> {
+        self.low..self.low + self.size
+    }
+
+    /// True if the order of the variables in the group cannot change.
+    pub fn is_fixed(&self) -> bool {
+        self.flags & MTR_FIXED != 0
+    }
+
+    /// Add a nested group and return it.
+    pub fn group(&mut self, range: Range<c_uint>) -> &mut Group {
+        add_group(&mut self.children, range)
+    }
+
+    /// Forbid reordering of the variables in the group (`MTR_FIXED`).
+    pub fn fixed(&mut self) -> &mut Group {
+        self.flags |= MTR_FIXED;
+        self
+    }
+
+    /// Allow the reordering of the group with its siblings to be undone when it does not
+    /// pay off (`MTR_SOFT`).
+    pub fn soft(&mut self) -> &mut Group {
+        self.flags |= MTR_SOFT;
+        self
+    }
+
+    /// Set the `MTR_*` flags of the group.
+    pub fn flags(&mut self, flags: c_uint) -> &mut Group {
+        self.flags = flags;
+        self
+    }
+}
+
+impl GroupTree {
+    /// A tree without groups.
+    pub fn new() -> GroupTree {
+        GroupTree::default()
+    }
+
+    /// Add a top-level group and return it.
+    pub fn group(&mut self, range: Range<c_uint>) -> &mut Group {
+        add_group(&mut self.groups, range)
+    }
+
+    /// Check that all groups are non-empty, that sibling groups do not overlap and that
+    /// nested groups are contained in their parents.
+    pub fn validate(&self) -> Result<()> {
+        check_groups(&self.groups, 0..c_uint::MAX)
+    }
+
+    /// Replace the group tree of the BDD variables of the `manager` with this tree.
+    ///
+    /// The ranges are converted from variable indices to levels using the current
+    /// variable order, so the variables of every group should be adjacent in this order.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager.
+    pub unsafe fn attach(&self, manager: *mut DdManager) -> Result<()> {
+        self.validate()?;
+        Cudd_FreeTree(manager);
+        make_nodes(&self.groups, &mut |group| {
+            Cudd_MakeTreeNode(manager, group.low, group.size, group.flags)
+        })
+    }
+
+    /// Replace the group tree of the ZDD variables of the `manager` with this tree
+    /// (see `GroupTree::attach`).
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager.
+    pub unsafe fn attach_zdd(&self, manager: *mut DdManager) -> Result<()> {
+        self.validate()?;
+        Cudd_FreeZddTree(manager);
+        make_nodes(&self.groups, &mut |group| {
+            Cudd_MakeZddTreeNode(manager, group.low, group.size, group.flags)
+        })
+    }
+
+    /// Replace the group tree of the BDD variables of the `manager` with this tree, built
+    /// as a whole by `GroupTree::to_raw` and installed using `Cudd_SetTree`.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager.
+    pub unsafe fn install(&self, manager: *mut DdManager) -> Result<()> {
+        let size = Cudd_ReadSize(manager) as c_uint;
+        let tree = self.to_raw(size)?;
+        Cudd_SetTree(manager, tree);
+        // `Cudd_SetTree` converts the `low` field of every node from an index to a level,
+        // but the root always covers all levels.
+        if size > 0 {
+            (*tree).index = Cudd_ReadInvPerm(manager, 0) as MtrHalfWord;
+            (*tree).low = 0;
+        }
+        Ok(())
+    }
+
+    /// Build a standalone MTR tree of the groups (using `Mtr_MakeGroup`), whose root covers
+    /// the variable indices `0..size`. The `low` field of every node is a variable index.
+    ///
+    /// The tree must be freed using `Mtr_FreeTree`, unless it is passed to `Cudd_SetTree`.
+    pub fn to_raw(&self, size: c_uint) -> Result<*mut MtrNode> {
+        check_groups(&self.groups, 0..size)?;
+        unsafe {
+            let root = Mtr_InitGroupTree(0, size as c_int);
+            if root.is_null() {
+                return Err(Error::new(ErrorKind::Other, "CUDD ran out of memory."));
+            }
+            let result = make_nodes(&self.groups, &mut |group| {
+                Mtr_MakeGroup(root, group.low, group.size, group.flags)
+            });
+            if let Err(error) = result {
+                Mtr_FreeTree(root);
+                return Err(error);
+            }
+            Ok(root)
+        }
+    }
+
+    /// Parse a group specification (see `GroupTree::write_spec` for the format). Variables
+    /// are given either by a name from `names`, or by their index.
+    pub fn parse_spec(spec: &str, names: Option<&VarNames>) -> Result<GroupTree> {
+        let mut tokens = Vec::new();
+        for line in spec.lines() {
+            let line = line.split('#').next().unwrap_or("");
+            let line = line.replace('{', " { ").replace('}', " } ");
+            tokens.extend(line.split_whitespace().map(|token| token.to_string()));
+        }
+        let mut tokens = tokens.iter().map(|token| token.as_str()).peekable();
+        let groups = parse_groups(&mut tokens, names, false)?;
+        let tree = GroupTree { groups };
+        tree.validate()
+            .map_err(|error| invalid_data(error.to_string()))?;
+        Ok(tree)
+    }
+
+    /// Read a group specification from the `input` (see `GroupTree::parse_spec`).
+    pub fn read_spec<R: Read>(mut input: R, names: Option<&VarNames>) -> Result<GroupTree> {
+        let mut spec = String::new();
+        input.read_to_string(&mut spec)?;
+        GroupTree::parse_spec(&spec, names)
+    }
+
+    /// Write the groups into the `output` as a group specification. Every group is declared
+    /// as `group FIRST LAST [fixed] [soft]`, where `FIRST` and `LAST` are the first and the
+    /// last variable of the group (inclusive), followed by its nested groups in braces:
+    ///
+    /// ```text
+    /// # Comments start with `#`.
+    /// group x0 x3 fixed
+    /// group x4 x7 {
+    ///     group x4 x5 soft
+    /// }
+    /// ```
+    ///
+    /// Variables are written using the `names` (indexed by variable index) if given, or as
+    /// indices otherwise. Flags other than `MTR_FIXED` and `MTR_SOFT` are not written. Names
+    /// which could not be parsed back (empty names, names containing whitespace, `{`, `}` or
+    /// `#`, numbers and the keywords `group`, `fixed` and `soft`) are rejected before anything
+    /// is written.
+    pub fn write_spec<W: Write, S: AsRef<str>>(
+        &self,
+        output: W,
+        names: Option<&[S]>,
+    ) -> Result<()> {
+        let names: Vec<Option<&str>> = names
+            .unwrap_or(&[])
+            .iter()
+            .map(|name| Some(name.as_ref()))
+            .collect();
+        self.write_named(output, &names)
+    }
+
+    /// Write the groups, using the index of variables without a name.
+    fn write_named<W: Write>(&self, mut output: W, names: &[Option<&str>]) -> Result<()> {
+        check_names(&self.groups, names)?;
+        write_groups(&mut output, &self.groups, names, 0)?;
+        output.flush()
+    }
+
+    /// The group tree of the BDD variables of the `manager`, if it has one.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager.
+    pub unsafe fn read(manager: *mut DdManager) -> Option<GroupTree> {
+        let tree = Cudd_ReadTree(manager);
+        if tree.is_null() {
+            None
+        } else {
+            Some(GroupTree::from_raw(tree))
+        }
+    }
+
+    /// The group tree of the ZDD variables of the `manager`, if it has one.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager.
+    pub unsafe fn read_zdd(manager: *mut DdManager) -> Option<GroupTree> {
+        let tree = Cudd_ReadZddTree(manager);
+        if tree.is_null() {
+            None
+        } else {
+            Some(GroupTree::from_raw(tree))
+        }
+    }
+
+    /// Convert the groups below the given `root` into a `GroupTree`. The groups are
+    /// identified by the index of their first variable (the `index` field of the node).
+    ///
+    /// # Safety
+    ///
+    /// The `root` must be a valid tree node.
+    pub unsafe fn from_raw(root: *const MtrNode) -> GroupTree {
+        GroupTree {
+            groups: read_children(root),
+        }
+    }
+}
+
+/// Create the tree nodes of the `groups`, parents before their children.
+unsafe fn make_nodes<F>(groups: &[Group], make_node: &mut F) -> Result<()>
+where
+    F: FnMut(&Group) -> *mut MtrNode,
+{
+    for group in groups {
+        if make_node(group).is_null() {
+            let message = format!("Cannot create the group {:?}.", group.range());
+            return Err(Error::new(ErrorKind::Other, message));
+        }
+        make_nodes(&group.children, make_node)?;
+    }
+    Ok(())
+}
+
+unsafe fn read_children(node: *const MtrNode) -> Vec<Group> {
+    let mut groups = Vec::new();
+    let mut child = (*node).child() as *const MtrNode;
+    while !child.is_null() {
+        groups.push(Group {
+            low: (*child).index() as c_uint,
+            size: (*child).size() as c_uint,
+            flags: (*child).flags() as c_uint,
+            children: read_children(child),
+        });
+        child = (*child).younger();
+    }
+    groups.sort_by_key(|group| group.low);
+    groups
+}
+
+fn parse_groups<'a, I: Iterator<Item = &'a str>>(
+    tokens: &mut std::iter::Peekable<I>,
+    names: Option<&VarNames>,
+    nested: bool,
+) -> Result<Vec<Group>> {
+    let mut groups = Vec::new();
+    loop {
+        match tokens.next() {
+            Some("group") => (),
+            Some("}") if nested => break,
+            None if !nested => break,
+            None => return Err(invalid_data("Missing `}` at the end of the specification.")),
+            Some(token) => return Err(invalid_data(format!("Unexpected `{}`.", token))),
+        }
+        let first = parse_var(tokens.next(), names)?;
+        let last = parse_var(tokens.next(), names)?;
+        if last < first {
+            let message = format!("Group {}..={} is empty.", first, last);
+            return Err(invalid_data(message));
+        }
+        let mut group = Group::new(first..last.saturating_add(1));
+        loop {
+            match tokens.peek() {
+                Some(&"fixed") => group.fixed(),
+                Some(&"soft") => group.soft(),
+                _ => break,
+            };
+            tokens.next();
+        }
+        if tokens.peek() == Some(&"{") {
+            tokens.next();
+            group.children = parse_groups(tokens, names, true)?;
+        }
+        groups.push(group);
+    }
+    groups.sort_by_key(|group| group.low);
+    Ok(groups)
+}
+
+fn parse_var(token: Option<&str>, names: Option<&VarNames>) -> Result<c_uint> {
+    let token = token.ok_or_else(|| invalid_data("Unexpected end of the specification."))?;
+    match names.and_then(|names| names.index(token)) {
+        Some(index) => Ok(index as c_uint),
+        None => token
+            .parse::<c_uint>()
+            .map_err(|_| invalid_data(format!("Unknown variable `{}`.", token))),
+    }
+}
+
+fn check_names(groups: &[Group], names: &[Option<&str>]) -> Result<()> {
+    for group in groups {
+        let range = group.range();
+        for index in &[range.start, range.end - 1] {
+            if let Some(name) = names.get(*index as usize).cloned().flatten() {
+                if name.is_empty()
+                    || name.parse::<c_uint>().is_ok()
+                    || ["group", "fixed", "soft"].contains(&name)
+                    || name
+                        .chars()
+                        .any(|c| c.is_whitespace() || c == '{' || c == '}' || c == '#')
+                {
+                    let message = format!("Variable name `{}` cannot be written.", name);
+                    return Err(invalid_input(message));
+                }
+            }
+        }
+        check_names(&group.children, names)?;
+    }
+    Ok(())
+}
+
+fn write_groups<W: Write>(
+    output: &mut W,
+    groups: &[Group],
+    names: &[Option<&str>],
+    depth: usize,
+) -> Result<()> {
+    let var = |index: c_uint| match names.get(index as usize).cloned().flatten() {
+        Some(name) => name.to_string(),
+        None => index.to_string(),
+    };
+    let indent = "    ".repeat(depth);
+    for group in groups {
+        let range = group.range();
+        write!(
+            output,
+            "{}group {} {}",
+            indent,
+            var(range.start),
+            var(range.end - 1)
+        )?;
+        if group.is_fixed() {
+            write!(output, " fixed")?;
+        }
+        if group.flags & MTR_SOFT != 0 {
+            write!(output, " soft")?;
+        }
+        if group.children.is_empty() {
+            writeln!(output)?;
+        } else {
+            writeln!(output, " {{")?;
+            write_groups(output, &group.children, names, depth + 1)?;
+            writeln!(output, "{}}}", indent)?;
+        }
+    }
+    Ok(())
+}
+
+/// Save the group tree of the BDD variables of the `manager` into the file at the given
+/// `path` (see `GroupTree::write_spec`), using the names registered with the manager
+/// (see `NamedVars`). Unnamed variables are written as indices, so that `load_groups`
+/// can read them back. A manager without a group tree produces an empty file.
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager.
+pub unsafe fn save_groups<P: AsRef<Path>>(manager: *mut DdManager, path: P) -> Result<()> {
+    let tree = GroupTree::read(manager).unwrap_or_default();
+    let registered = manager.registered_var_names();
+    let names: Vec<Option<&str>> = (0..Cudd_ReadSize(manager))
+        .map(|index| registered.and_then(|names| names.name(index)))
+        .collect();
+    tree.write_named(File::create(path)?, &names)
+}
+
+/// Load the group specification in the file at the given `path` and install it as the group
+/// tree of the BDD variables of the `manager` (see `GroupTree::install`). Variables are
+/// identified using the names registered with the manager (see `NamedVars`) or by index.
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager.
+pub unsafe fn load_groups<P: AsRef<Path>>(manager: *mut DdManager, path: P) -> Result<()> {
+    let tree = GroupTree::read_spec(File::open(path)?, manager.registered_var_names())?;
+    tree.install(manager)
+}
@@ -34,7 +34,18 @@
 // Allow non-idiomatic names in the whole crate.
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
+// `io::Error::other` is not available in the Rust version used by the CI.
+#![allow(unknown_lints)]
+#![allow(clippy::io_other_error)]
+#[cfg(all(test, feature = "serde"))]
+extern crate bincode;
 extern crate libc;
+#[cfg(feature = "num-bigint")]
+extern crate num_bigint;
+#[cfg(feature = "rand_core")]
+extern crate rand_core;
+#[cfg(feature = "serde")]
+extern crate serde;
 
 #[cfg(test)]
 mod test;
@@ -48,11 +59,75 @@ pub mod mtr;
 /// Contains the declarations present in `epd.h` (extended double precision numbers).
 pub mod epd;
 
+/// Owned arbitrary precision integers (`DdApaNumber`), used for exact minterm counts.
+///
+/// With the `num-bigint` feature enabled, the numbers can be converted into `BigUint`.
+pub mod apa;
+
 /// Declarations from `dddmp.h` (serialisation of decision diagrams).
 ///
-/// Currently, the error checking macros are not implemented.
+/// The error checking macros are available as `Dddmp_CheckAndReturn!` and
+/// `Dddmp_CheckAndGotoLabel!`, which report failures as a `DddmpError`. The safe readers and writers (`DddmpReader`, `DddmpWriter`)
+/// validate the DDDMP header before loading and report failures as a `DddmpError`.
+/// The header and the node table of a DDDMP file can be also read and written without
+/// a manager (see `read_header` and `DddmpFile`). BDDs can be exported to DIMACS using
+/// a `CnfWriter` and loaded back using `DddmpReader::read_cnf`.
+///
+/// With the `serde` feature enabled, decision diagrams can be also serialised
+/// through the DDDMP format (see `DddmpDiagram` and `DddmpSeed`).
 pub mod dddmp;
 
+/// Safe wrappers which capture the text printed by CUDD into Rust strings instead of
+/// writing it into a C stream or the standard output of the manager.
+pub mod capture;
+
+/// Adapters which connect Rust readers and writers to C streams (`FILE*`) used by CUDD.
+pub mod cfile;
+
+/// Export of decision diagrams into the Graphviz DOT format.
+pub mod dot;
+
+/// Export of BDDs as BLIF, SMV and prefix netlists.
+pub mod netlist;
+
+/// Import of combinational and sequential circuits (BLIF and AIGER) as BDDs.
+pub mod circuit;
+
+/// Compilation of CNF formulas in the DIMACS format into BDDs.
+pub mod dimacs;
+
+/// Parsing and printing of Boolean expressions over named BDD variables.
+pub mod expr;
+
+/// Trees of variable groups which constrain the reordering of BDD and ZDD variables.
+pub mod groups;
+
+/// Safe access to the variable order, including saving and loading orders to text files.
+pub mod order;
+
+/// Names of BDD variables stored with the manager (`manager.var("x3")`).
+pub mod registry;
+
+/// Probability evaluation and weighted model counting of BDDs.
+pub mod weighted;
+
+/// Symbolic transition systems: images, reachability and counterexample traces.
+pub mod transition;
+
+/// Partitioned transition relations with clustering and early quantification.
+pub mod partition;
+
+/// CTL model checking of transition systems, with fairness constraints and witnesses.
+pub mod ctl;
+
+/// Uniform and weighted random sampling of the satisfying assignments of BDDs.
+///
+/// Requires the `rand_core` feature.
+#[cfg(feature = "rand_core")]
+pub mod sample;
+
+mod names;
+
 use std::marker::{PhantomData, PhantomPinned};
 
 /// An opaque C struct used to represent the decision diagram node.
@@ -101,9 +176,71 @@ pub struct EpDouble {
     _marker: PhantomData<(*mut u8, PhantomPinned)>,
 }
 
-/// An opaque C struct representing a multi-way branch tree node.
+/// The type of the integer fields of `MtrNode` (half of a pointer on 64-bit platforms).
+#[cfg(target_pointer_width = "64")]
+pub type MtrHalfWord = u32;
+
+/// The type of the integer fields of `MtrNode` (half of a pointer on 64-bit platforms).
+#[cfg(not(target_pointer_width = "64"))]
+pub type MtrHalfWord = u16;
+
+/// A C struct representing a multi-way branch tree node.
+///
+/// The layout follows the declaration in `mtrInt.h` of CUDD `3.0.0`. The nodes are always
+/// allocated by CUDD (`Mtr_AllocNode`, `Mtr_MakeGroup`, `Cudd_MakeTreeNode`, ...), so the
+/// fields are only readable through the accessors below. Flags can be modified using
+/// `mtr::MTR_SET` and `mtr::MTR_RESET`.
 #[repr(C)]
 pub struct MtrNode {
-    _data: [u8; 0],
-    _marker: PhantomData<(*mut u8, PhantomPinned)>,
+    flags: MtrHalfWord,
+    low: MtrHalfWord,
+    size: MtrHalfWord,
+    index: MtrHalfWord,
+    parent: *mut MtrNode,
+    child: *mut MtrNode,
+    elder: *mut MtrNode,
+    younger: *mut MtrNode,
+    _marker: PhantomData<PhantomPinned>,
+}
+
+impl MtrNode {
+    /// The `MTR_*` flags of the node.
+    pub fn flags(&self) -> MtrHalfWord {
+        self.flags
+    }
+
+    /// The lowest level (or leaf) covered by the node.
+    pub fn low(&self) -> MtrHalfWord {
+        self.low
+    }
+
+    /// The number of levels (or leaves) covered by the node.
+    pub fn size(&self) -> MtrHalfWord {
+        self.size
+    }
+
+    /// The variable index of the first variable of the group (set by `Cudd_MakeTreeNode`).
+    pub fn index(&self) -> MtrHalfWord {
+        self.index
+    }
+
+    /// The parent of the node, or null for the root.
+    pub fn parent(&self) -> *mut MtrNode {
+        self.parent
+    }
+
+    /// The first (eldest) child of the node, or null.
+    pub fn child(&self) -> *mut MtrNode {
+        self.child
+    }
+
+    /// The previous sibling of the node, or null.
+    pub fn elder(&self) -> *mut MtrNode {
+        self.elder
+    }
+
+    /// The next sibling of the node, or null.
+    pub fn younger(&self) -> *mut MtrNode {
+        self.younger
+    }
 }
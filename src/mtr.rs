@@ -1,5 +1,6 @@
 use libc::{c_int, c_uint, c_void, FILE};
-use MtrNode;
+use std::fmt::{Result, Write};
+use {MtrHalfWord, MtrNode};
 
 /// Default flag value in `Mtr_MakeGroup`.
 pub const MTR_DEFAULT: c_uint = 0;
@@ -11,6 +12,74 @@ pub const MTR_SOFT: c_uint = 2;
 pub const MTR_FIXED: c_uint = 4;
 /// See `Mtr_MakeGroup`.
 pub const MTR_NEWNODE: c_uint = 8;
+/// The largest value of the `low` and `size` fields of an `MtrNode` (`((MtrHalfWord) ~0) >> 1`).
+pub const MTR_MAXHIGH: MtrHalfWord = MtrHalfWord::MAX >> 1;
+
+/// Set the given `flag` of the node (the `MTR_SET` macro).
+///
+/// # Safety
+///
+/// This function should only be called on a valid `MtrNode` pointer.
+#[inline]
+pub unsafe fn MTR_SET(node: *mut MtrNode, flag: c_uint) {
+    (*node).flags |= flag as MtrHalfWord;
+}
+
+/// Clear the given `flag` of the node (the `MTR_RESET` macro).
+///
+/// # Safety
+///
+/// This function should only be called on a valid `MtrNode` pointer.
+#[inline]
+pub unsafe fn MTR_RESET(node: *mut MtrNode, flag: c_uint) {
+    (*node).flags &= !(flag as MtrHalfWord);
+}
+
+/// Test the given `flag` of the node (the `MTR_TEST` macro). Returns a non-zero value
+/// if the flag is set.
+///
+/// # Safety
+///
+/// This function should only be called on a valid `MtrNode` pointer.
+#[inline]
+pub unsafe fn MTR_TEST(node: *const MtrNode, flag: c_uint) -> c_int {
+    ((*node).flags as c_uint & flag != 0) as c_int
+}
+
+/// Write the groups of the tree rooted in `root` into `output`, using the same format as
+/// `Mtr_PrintGroups` (which can only print to the standard output). For example,
+/// `(0(0,3|F)7)` is a group of the levels `0..=7` with a fixed subgroup of the levels `0..=3`.
+///
+/// # Safety
+///
+/// This function should only be called on a valid `MtrNode` pointer.
+pub unsafe fn Mtr_WriteGroups<W: Write>(root: *const MtrNode, output: &mut W) -> Result {
+    let node = &*root;
+    write!(output, "({}", node.low())?;
+    if MTR_TEST(root, MTR_TERMINAL) != 0 || node.child().is_null() {
+        write!(output, ",")?;
+    } else {
+        let mut child = node.child() as *const MtrNode;
+        while !child.is_null() {
+            Mtr_WriteGroups(child, output)?;
+            child = (*child).younger();
+        }
+    }
+    write!(
+        output,
+        "{}",
+        node.low().wrapping_add(node.size()).wrapping_sub(1)
+    )?;
+    if node.flags() as c_uint != MTR_DEFAULT {
+        write!(output, "|")?;
+        for (flag, letter) in &[(MTR_FIXED, 'F'), (MTR_NEWNODE, 'N'), (MTR_SOFT, 'S')] {
+            if MTR_TEST(root, *flag) != 0 {
+                output.write_char(*letter)?;
+            }
+        }
+    }
+    write!(output, ")")
+}
 
 extern "C" {
     pub fn Mtr_AllocNode() -> *mut MtrNode;
@@ -24,15 +93,17 @@ extern "C" {
     pub fn Mtr_CreateLastChild(parent: *mut MtrNode) -> *mut MtrNode;
     pub fn Mtr_MakeNextSibling(first: *mut MtrNode, second: *mut MtrNode) -> c_void;
     pub fn Mtr_PrintTree(node: *const MtrNode) -> c_void;
+    // The `high` parameter of `Mtr_MakeGroup` and `Mtr_FindGroup` in `mtr.h` is in fact
+    // the size of the group.
     pub fn Mtr_InitGroupTree(lower: c_int, size: c_int) -> *mut MtrNode;
     pub fn Mtr_MakeGroup(
         root: *mut MtrNode,
         low: c_uint,
-        high: c_uint,
+        size: c_uint,
         flags: c_uint,
     ) -> *mut MtrNode;
     pub fn Mtr_DissolveGroup(group: *mut MtrNode) -> *mut MtrNode;
-    pub fn Mtr_FindGroup(root: *mut MtrNode, low: c_uint, high: c_uint) -> *mut MtrNode;
+    pub fn Mtr_FindGroup(root: *mut MtrNode, low: c_uint, size: c_uint) -> *mut MtrNode;
     pub fn Mtr_SwapGroups(first: *mut MtrNode, second: *mut MtrNode) -> c_int;
     pub fn Mtr_ReorderGroups(treenode: *mut MtrNode, permutation: *mut c_int) -> c_void;
     pub fn Mtr_PrintGroups(root: *const MtrNode, silent: c_int) -> c_void;
@@ -42,4 +113,5 @@ extern "C" {
         fp: *mut FILE,
     ) -> c_int;
     pub fn Mtr_ReadGroups(fp: *mut FILE, nleaves: c_int) -> *mut MtrNode;
+    pub fn Mtr_ResizeTree(node: *mut MtrNode, size: MtrHalfWord) -> c_int;
 }
@@ -0,0 +1,41 @@
+use libc::c_char;
+use std::ffi::CString;
+use std::io::{Error, ErrorKind, Result};
+
+/// An array of C strings (`char**`) together with the Rust storage backing it, so that
+/// the pointers stay valid for as long as the array is alive.
+pub(crate) struct CStringArray {
+    _strings: Vec<CString>,
+    pointers: Vec<*mut c_char>,
+}
+
+impl CStringArray {
+    /// Convert the given names into C strings. Fails if some name contains a null byte.
+    pub fn new<S: AsRef<str>>(names: &[S]) -> Result<CStringArray> {
+        let strings = names
+            .iter()
+            .map(|name| {
+                CString::new(name.as_ref()).map_err(|_| {
+                    let message = format!("Name `{}` contains a null byte.", name.as_ref());
+                    Error::new(ErrorKind::InvalidInput, message)
+                })
+            })
+            .collect::<Result<Vec<CString>>>()?;
+        let pointers = strings.iter().map(|s| s.as_ptr() as *mut c_char).collect();
+        Ok(CStringArray {
+            _strings: strings,
+            pointers,
+        })
+    }
+
+    /// A pointer usable with the `const char**` parameters of CUDD.
+    pub fn as_ptr(&self) -> *const *const c_char {
+        self.pointers.as_ptr() as *const *const c_char
+    }
+
+    /// A pointer usable with the `char**` parameters of CUDD and DDDMP. The strings must
+    /// not be modified through this pointer (none of the library functions do that).
+    pub fn as_mut_ptr(&mut self) -> *mut *mut c_char {
+        self.pointers.as_mut_ptr()
+    }
+}
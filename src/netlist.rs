@@ -0,0 +1,250 @@
+use cfile::write_with;
+use cudd::{Cudd_DumpBlif, Cudd_DumpBlifBody, Cudd_ReadSize};
+use dddmp::{
+    Dddmp_cuddBddArrayStoreBlif, Dddmp_cuddBddArrayStorePrefix, Dddmp_cuddBddArrayStoreSmv,
+};
+use libc::{c_char, c_int};
+use names::CStringArray;
+use registry::NamedVars;
+use std::collections::HashSet;
+use std::ffi::CString;
+use std::io::{Error, ErrorKind, Result, Write};
+use std::ptr::null_mut;
+use {DdManager, DdNode};
+
+/// The netlist formats supported by `Netlist`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NetlistFormat {
+    /// A BLIF model with one multiplexer per node (`Cudd_DumpBlif`).
+    Blif,
+    /// The `.names` tables of a BLIF model, without the `.model`, `.inputs`, `.outputs`
+    /// and `.end` lines, so that it can be embedded into another model (`Cudd_DumpBlifBody`).
+    BlifBody,
+    /// A BLIF model with one gate per node (`Dddmp_cuddBddArrayStoreBlif`).
+    DddmpBlif,
+    /// An SMV module (`Dddmp_cuddBddArrayStoreSmv`).
+    Smv,
+    /// A netlist in prefix notation (`Dddmp_cuddBddArrayStorePrefix`).
+    Prefix,
+}
+
+/// Export of BDDs as netlists (BLIF, SMV or prefix notation).
+///
+/// Unless specified otherwise, the inputs use the names registered with the manager (see
+/// `NamedVars`) or are named `x0`, `x1`, ... (by variable index),
+/// the outputs `f0`, `f1`, ... and the model `DD`. All names are checked to be valid
+/// identifiers of the target format.
+#[derive(Clone, Debug)]
+pub struct Netlist {
+    format: NetlistFormat,
+    model_name: Option<String>,
+    input_names: Option<Vec<String>>,
+    output_names: Option<Vec<String>>,
+}
+
+/// Reserved words of the SMV language, which cannot be used as names.
+const SMV_KEYWORDS: &[&str] = &[
+    "MODULE",
+    "VAR",
+    "IVAR",
+    "DEFINE",
+    "ASSIGN",
+    "INIT",
+    "TRANS",
+    "INVAR",
+    "SPEC",
+    "CTLSPEC",
+    "LTLSPEC",
+    "FAIRNESS",
+    "JUSTICE",
+    "COMPASSION",
+    "init",
+    "next",
+    "case",
+    "esac",
+    "TRUE",
+    "FALSE",
+    "process",
+    "self",
+    "boolean",
+    "word",
+    "array",
+    "of",
+    "mod",
+    "union",
+    "in",
+    "xor",
+    "xnor",
+];
+
+impl Netlist {
+    /// An export into the given format using the default names.
+    pub fn new(format: NetlistFormat) -> Netlist {
+        Netlist {
+            format,
+            model_name: None,
+            input_names: None,
+            output_names: None,
+        }
+    }
+
+    /// Set the name of the model.
+    pub fn model_name(mut self, name: &str) -> Netlist {
+        self.model_name = Some(name.to_string());
+        self
+    }
+
+    /// Set the names of the inputs, indexed by variable index. There must be a name for
+    /// every variable of the manager.
+    pub fn input_names<S: AsRef<str>>(mut self, names: &[S]) -> Netlist {
+        self.input_names = Some(names.iter().map(|s| s.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Set the names of the outputs (one for each root).
+    pub fn output_names<S: AsRef<str>>(mut self, names: &[S]) -> Netlist {
+        self.output_names = Some(names.iter().map(|s| s.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Write the netlist computing the given BDDs into the `output`.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager and the `roots` must be valid BDDs
+    /// of this manager.
+    pub unsafe fn write<W: Write>(
+        &self,
+        manager: *mut DdManager,
+        output: W,
+        roots: &[*mut DdNode],
+    ) -> Result<()> {
+        let variables = Cudd_ReadSize(manager) as usize;
+        let inputs = match self.input_names {
+            Some(ref names) if names.len() < variables => {
+                let message = format!(
+                    "Expected {} input names, but only {} were given.",
+                    variables,
+                    names.len()
+                );
+                return Err(Error::new(ErrorKind::InvalidInput, message));
+            }
+            Some(ref names) => names.clone(),
+            None => match manager.var_name_list() {
+                Some(names) => names,
+                None => (0..variables).map(|i| format!("x{}", i)).collect(),
+            },
+        };
+        let outputs = match self.output_names {
+            Some(ref names) if names.len() != roots.len() => {
+                let message = format!(
+                    "Expected {} output names, but {} were given.",
+                    roots.len(),
+                    names.len()
+                );
+                return Err(Error::new(ErrorKind::InvalidInput, message));
+            }
+            Some(ref names) => names.clone(),
+            None => (0..roots.len()).map(|i| format!("f{}", i)).collect(),
+        };
+        let model = self.model_name.as_deref().unwrap_or("DD");
+
+        let mut seen = HashSet::new();
+        for name in inputs.iter().chain(&outputs) {
+            self.check_name(name)?;
+            if !seen.insert(name) {
+                let message = format!("Name `{}` is used more than once.", name);
+                return Err(Error::new(ErrorKind::InvalidInput, message));
+            }
+        }
+        self.check_name(model)?;
+
+        let mut inames = CStringArray::new(&inputs)?;
+        let mut onames = CStringArray::new(&outputs)?;
+        let model = CString::new(model).unwrap();
+        let model = model.as_ptr() as *mut c_char;
+        let count = roots.len() as c_int;
+        let roots = roots.as_ptr() as *mut *mut DdNode;
+        let format = self.format;
+        write_with(output, |fp| match format {
+            NetlistFormat::Blif => Cudd_DumpBlif(
+                manager,
+                count,
+                roots,
+                inames.as_ptr(),
+                onames.as_ptr(),
+                model,
+                fp,
+                0,
+            ),
+            NetlistFormat::BlifBody => Cudd_DumpBlifBody(
+                manager,
+                count,
+                roots,
+                inames.as_ptr(),
+                onames.as_ptr(),
+                fp,
+                0,
+            ),
+            NetlistFormat::DddmpBlif => Dddmp_cuddBddArrayStoreBlif(
+                manager,
+                count,
+                roots,
+                inames.as_mut_ptr(),
+                onames.as_mut_ptr(),
+                model,
+                null_mut(),
+                fp,
+            ),
+            NetlistFormat::Smv => Dddmp_cuddBddArrayStoreSmv(
+                manager,
+                count,
+                roots,
+                inames.as_mut_ptr(),
+                onames.as_mut_ptr(),
+                model,
+                null_mut(),
+                fp,
+            ),
+            NetlistFormat::Prefix => Dddmp_cuddBddArrayStorePrefix(
+                manager,
+                count,
+                roots,
+                inames.as_mut_ptr(),
+                onames.as_mut_ptr(),
+                model,
+                null_mut(),
+                fp,
+            ),
+        })
+    }
+
+    /// Check that the `name` is a valid identifier of the target format.
+    fn check_name(&self, name: &str) -> Result<()> {
+        let valid = !name.is_empty()
+            && !name.chars().any(|c| c.is_whitespace() || c.is_control())
+            && match self.format {
+                NetlistFormat::Blif | NetlistFormat::BlifBody | NetlistFormat::DddmpBlif => {
+                    // `#` starts a comment, `\` continues a line and `=` is used by `.subckt`.
+                    !name.starts_with('.') && !name.contains(&['#', '\\', '='][..])
+                }
+                NetlistFormat::Smv => {
+                    let mut chars = name.chars();
+                    let first = chars.next().unwrap_or(' ');
+                    (first.is_ascii_alphabetic() || first == '_')
+                        && chars.all(|c| c.is_ascii_alphanumeric() || "_$#-".contains(c))
+                        && !SMV_KEYWORDS.contains(&name)
+                }
+                NetlistFormat::Prefix => !name.contains(&['(', ')'][..]),
+            };
+        if valid {
+            Ok(())
+        } else {
+            let message = format!(
+                "Name `{}` is not valid in the {:?} format.",
+                name, self.format
+            );
+            Err(Error::new(ErrorKind::InvalidInput, message))
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use cudd::*;
+use libc::c_int;
+use registry::NamedVars;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Result, Write};
+use std::path::Path;
+use DdManager;
+
+/// The index of a variable (as opposed to its level in the current variable order).
+pub type VarIndex = c_int;
+
+/// The indices of the BDD variables ordered by level (see `Cudd_ReadInvPerm`).
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager.
+pub unsafe fn current_order(manager: *mut DdManager) -> Vec<VarIndex> {
+    (0..Cudd_ReadSize(manager))
+        .map(|level| Cudd_ReadInvPerm(manager, level))
+        .collect()
+}
+
+/// The indices of the ZDD variables ordered by level (see `Cudd_ReadInvPermZdd`).
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager.
+pub unsafe fn current_zdd_order(manager: *mut DdManager) -> Vec<VarIndex> {
+    (0..Cudd_ReadZddSize(manager))
+        .map(|level| Cudd_ReadInvPermZdd(manager, level))
+        .collect()
+}
+
+/// Reorder the BDD variables using `Cudd_ShuffleHeap`, so that `order[level]` is the
+/// index of the variable at the given level. The `order` must be a permutation of all
+/// variable indices.
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager.
+pub unsafe fn set_order(manager: *mut DdManager, order: &[VarIndex]) -> Result<()> {
+    let mut order = check_permutation(order, Cudd_ReadSize(manager))?;
+    if Cudd_ShuffleHeap(manager, order.as_mut_ptr()) == 0 {
+        return Err(Error::new(
+            ErrorKind::Other,
+            "Cannot reorder the variables.",
+        ));
+    }
+    Ok(())
+}
+
+/// Reorder the ZDD variables using `Cudd_zddShuffleHeap` (see `set_order`).
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager.
+pub unsafe fn set_zdd_order(manager: *mut DdManager, order: &[VarIndex]) -> Result<()> {
+    let mut order = check_permutation(order, Cudd_ReadZddSize(manager))?;
+    if Cudd_zddShuffleHeap(manager, order.as_mut_ptr()) == 0 {
+        return Err(Error::new(
+            ErrorKind::Other,
+            "Cannot reorder the variables.",
+        ));
+    }
+    Ok(())
+}
+
+fn check_permutation(order: &[VarIndex], size: c_int) -> Result<Vec<c_int>> {
+    let mut seen = vec![false; size.max(0) as usize];
+    if order.len() != seen.len() {
+        let message = format!(
+            "Expected an order of {} variables, but {} were given.",
+            seen.len(),
+            order.len()
+        );
+        return Err(Error::new(ErrorKind::InvalidInput, message));
+    }
+    for index in order {
+        match seen.get_mut(*index as usize) {
+            Some(seen) if *index >= 0 && !*seen => *seen = true,
+            _ => {
+                let message = format!("Variable {} is invalid or used more than once.", index);
+                return Err(Error::new(ErrorKind::InvalidInput, message));
+            }
+        }
+    }
+    Ok(order.to_vec())
+}
+
+/// Write a variable order into the `output`: one variable per line, starting at the top
+/// level. Every line contains the variable index, followed by its name if `names`
+/// (indexed by variable index) are given.
+pub fn write_order<W: Write, S: AsRef<str>>(
+    mut output: W,
+    order: &[VarIndex],
+    names: Option<&[S]>,
+) -> Result<()> {
+    for index in order {
+        match names.and_then(|names| names.get(*index as usize)) {
+            Some(name) => writeln!(output, "{} {}", index, name.as_ref())?,
+            None => writeln!(output, "{}", index)?,
+        }
+    }
+    output.flush()
+}
+
+/// Read a variable order written by `write_order`. Empty lines and comments (starting
+/// with `#`) are ignored. Returns the variable indices with their names (if present).
+pub fn read_order<R: Read>(input: R) -> Result<Vec<(VarIndex, Option<String>)>> {
+    let mut order = Vec::new();
+    for line in BufReader::new(input).lines() {
+        let line = line?;
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut fields = line.splitn(2, char::is_whitespace);
+        let index = fields.next().unwrap_or("");
+        let index = index.parse::<VarIndex>().map_err(|_| {
+            let message = format!("Invalid variable index `{}`.", index);
+            Error::new(ErrorKind::InvalidData, message)
+        })?;
+        let name = fields.next().map(|name| name.trim().to_string());
+        order.push((index, name));
+    }
+    Ok(order)
+}
+
+/// Save the current order of the BDD variables into the file at the given `path`,
+/// together with the names registered with the manager (see `NamedVars`).
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager.
+pub unsafe fn save_order<P: AsRef<Path>>(manager: *mut DdManager, path: P) -> Result<()> {
+    let names = manager.var_name_list();
+    write_order(
+        File::create(path)?,
+        &current_order(manager),
+        names.as_deref(),
+    )
+}
+
+/// Reorder the BDD variables according to the order saved in the file at the given `path`.
+/// Variables whose name is registered with the manager (see `NamedVars`) are identified
+/// by their name, the remaining ones by their index.
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager.
+pub unsafe fn load_order<P: AsRef<Path>>(manager: *mut DdManager, path: P) -> Result<()> {
+    let order: Vec<VarIndex> = read_order(File::open(path)?)?
+        .into_iter()
+        .map(|(index, name)| {
+            name.and_then(|name| manager.var_index(&name))
+                .unwrap_or(index)
+        })
+        .collect();
+    set_order(manager, &order)
+}
@@ -0,0 +1,440 @@
+use circuit::CircuitBdds;
+use cudd::*;
+use libc::{c_int, c_uint};
+use std::collections::HashSet;
+use std::io::{Error, ErrorKind, Result};
+use transition::{indices_cube, latch_pairs, referenced, register_vars, swap_vars};
+use weighted::support;
+use {DdManager, DdNode};
+
+/// The weights of the IWLS95 cost function used to order the partitions (the values used
+/// by VIS): quantified variables, support, introduced next-state variables and levels.
+const WEIGHTS: (f64, f64, f64, f64) = (6.0, 1.0, 1.0, 2.0);
+
+/// Options of the clustering and of the image computation of a `PartitionedRelation`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartitionOptions {
+    /// Consecutive partitions are conjoined into one cluster while the size of the cluster
+    /// (`Cudd_DagSize`) does not exceed this threshold (5000 by default). Zero disables
+    /// clustering.
+    pub cluster_threshold: usize,
+    /// The maximum number of new nodes created by one step of the image computation (see
+    /// `Cudd_bddAndAbstractLimit`). Unlimited by default.
+    pub node_limit: Option<c_uint>,
+    /// If set, a step which exceeds the node limit is over-approximated using
+    /// `Cudd_bddClippingAndAbstract` with the given recursion depth. Otherwise, the image
+    /// computation fails.
+    pub clipping_depth: Option<c_int>,
+}
+
+impl Default for PartitionOptions {
+    fn default() -> Self {
+        PartitionOptions {
+            cluster_threshold: 5000,
+            node_limit: None,
+            clipping_depth: None,
+        }
+    }
+}
+
+impl PartitionOptions {
+    /// Create default options.
+    pub fn new() -> PartitionOptions {
+        PartitionOptions::default()
+    }
+
+    /// Conjoin partitions into clusters of at most the given number of nodes.
+    pub fn cluster_threshold(mut self, threshold: usize) -> PartitionOptions {
+        self.cluster_threshold = threshold;
+        self
+    }
+
+    /// Limit the number of new nodes created by one step of the image computation.
+    pub fn node_limit(mut self, limit: Option<c_uint>) -> PartitionOptions {
+        self.node_limit = limit;
+        self
+    }
+
+    /// Over-approximate the steps which exceed the node limit using clipping.
+    pub fn clipping_depth(mut self, depth: Option<c_int>) -> PartitionOptions {
+        self.clipping_depth = depth;
+        self
+    }
+}
+
+/// The result of `PartitionedRelation::image`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Image {
+    /// The image (referenced), over the present-state variables.
+    pub states: *mut DdNode,
+    /// False if some step exceeded the node limit and the image is only an
+    /// over-approximation computed by `Cudd_bddClippingAndAbstract`.
+    pub exact: bool,
+}
+
+/// A transition relation given as a conjunction of partitions (e.g. one partition
+/// `next_i <-> f_i` for every latch), which is never built as a single BDD.
+///
+/// The partitions are ordered using the IWLS95 heuristic (Ranjan et al., "Efficient BDD
+/// algorithms for FSM synthesis and verification"), conjoined into clusters up to a node
+/// threshold and every present-state and input variable is quantified right after the last
+/// cluster which depends on it. The image is then computed by a chain of
+/// `Cudd_bddAndAbstract` (or `Cudd_bddAndAbstractLimit`) calls, one per cluster.
+///
+/// The variables are registered with the manager as in `TransitionSystem`. The relation
+/// has to be released using `PartitionedRelation::release`.
+pub struct PartitionedRelation {
+    manager: *mut DdManager,
+    present: Vec<c_int>,
+    next: Vec<c_int>,
+    inputs: Vec<c_int>,
+    swap: Vec<c_int>,
+    // The clusters in the order of the schedule, and the variables quantified right after
+    // conjoining each of them.
+    clusters: Vec<*mut DdNode>,
+    cubes: Vec<*mut DdNode>,
+    options: PartitionOptions,
+}
+
+fn invalid_input<S: Into<String>>(message: S) -> Error {
+    Error::new(ErrorKind::InvalidInput, message.into())
+}
+
+/// A partition and its support, split into quantified and next-state variables.
+struct Part {
+    node: *mut DdNode,
+    quantified: HashSet<c_int>,
+    next: HashSet<c_int>,
+}
+
+/// Order the partitions using the IWLS95 cost function: in every step, choose the partition
+/// which allows to quantify the most variables (relative to its support), while introducing
+/// few next-state variables and quantifying variables low in the order.
+unsafe fn schedule(manager: *mut DdManager, mut parts: Vec<Part>) -> Vec<Part> {
+    let (w1, w2, w3, w4) = WEIGHTS;
+    let level = |index: &c_int| Cudd_ReadPerm(manager, *index) as f64;
+    let mut remaining: HashSet<c_int> = parts.iter().flat_map(|p| p.quantified.clone()).collect();
+    let mut absent: HashSet<c_int> = parts.iter().flat_map(|p| p.next.clone()).collect();
+    let mut ordered = Vec::new();
+    while !parts.is_empty() {
+        let max_level = remaining.iter().map(level).fold(0.0, f64::max);
+        let mut best = (0, f64::NEG_INFINITY, Vec::new());
+        for (i, part) in parts.iter().enumerate() {
+            let support: Vec<c_int> = part.quantified.intersection(&remaining).cloned().collect();
+            let quantifiable: Vec<c_int> = support
+                .iter()
+                .filter(|index| {
+                    let mut others = parts.iter().enumerate().filter(|(j, _)| *j != i);
+                    !others.any(|(_, p)| p.quantified.contains(index))
+                })
+                .cloned()
+                .collect();
+            let introduced = part.next.intersection(&absent).count();
+            let ratio = |a: usize, b: usize| if b == 0 { 0.0 } else { a as f64 / b as f64 };
+            let deepest = quantifiable.iter().map(level).fold(0.0, f64::max);
+            let depth = if max_level > 0.0 {
+                deepest / max_level
+            } else {
+                0.0
+            };
+            let cost = w1 * ratio(quantifiable.len(), support.len())
+                + w2 * ratio(support.len(), remaining.len())
+                - w3 * ratio(introduced, absent.len())
+                + w4 * depth;
+            if cost > best.1 {
+                best = (i, cost, quantifiable);
+            }
+        }
+        let part = parts.remove(best.0);
+        for index in best.2 {
+            remaining.remove(&index);
+        }
+        for index in &part.next {
+            absent.remove(index);
+        }
+        ordered.push(part);
+    }
+    ordered
+}
+
+impl PartitionedRelation {
+    /// A relation given by the conjunction of the `partitions`, with the given pairs of
+    /// present and next-state variables (indices) and input variables. The partitions are
+    /// not released by the relation (the clusters are referenced separately).
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager and must stay valid until the relation is
+    /// released. The `partitions` must be BDDs of this manager.
+    pub unsafe fn new(
+        manager: *mut DdManager,
+        pairs: &[(c_int, c_int)],
+        inputs: &[c_int],
+        partitions: &[*mut DdNode],
+        options: &PartitionOptions,
+    ) -> Result<PartitionedRelation> {
+        let swap = register_vars(manager, pairs, inputs)?;
+        let present: Vec<c_int> = pairs.iter().map(|(p, _)| *p).collect();
+        let next: Vec<c_int> = pairs.iter().map(|(_, n)| *n).collect();
+        let quantified: HashSet<c_int> = present.iter().chain(inputs).cloned().collect();
+        let mut parts = Vec::new();
+        for node in partitions {
+            let support: HashSet<c_int> = support(manager, *node)?.into_iter().collect();
+            parts.push(Part {
+                node: *node,
+                quantified: support.intersection(&quantified).cloned().collect(),
+                next: support.difference(&quantified).cloned().collect(),
+            });
+        }
+        let mut relation = PartitionedRelation {
+            manager,
+            present,
+            next,
+            inputs: inputs.to_vec(),
+            swap,
+            clusters: Vec::new(),
+            cubes: Vec::new(),
+            options: options.clone(),
+        };
+        let result = relation
+            .cluster(schedule(manager, parts))
+            .and_then(|_| relation.quantification_cubes(&quantified));
+        match result {
+            Ok(()) => Ok(relation),
+            Err(error) => {
+                relation.release();
+                Err(error)
+            }
+        }
+    }
+
+    /// A relation with one partition `next_i <-> functions[i]` for every pair of present
+    /// and next-state variables.
+    ///
+    /// # Safety
+    ///
+    /// Same as `PartitionedRelation::new`.
+    pub unsafe fn from_functions(
+        manager: *mut DdManager,
+        pairs: &[(c_int, c_int)],
+        inputs: &[c_int],
+        functions: &[*mut DdNode],
+        options: &PartitionOptions,
+    ) -> Result<PartitionedRelation> {
+        if pairs.len() != functions.len() {
+            let message = format!(
+                "Expected {} next-state functions, but {} were given.",
+                pairs.len(),
+                functions.len()
+            );
+            return Err(invalid_input(message));
+        }
+        let mut partitions = Vec::new();
+        for ((_, next), function) in pairs.iter().zip(functions) {
+            let next = Cudd_bddIthVar(manager, *next);
+            match referenced(Cudd_bddXnor(manager, next, *function)) {
+                Ok(partition) => partitions.push(partition),
+                Err(error) => {
+                    for partition in partitions {
+                        Cudd_RecursiveDeref(manager, partition);
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        let relation = PartitionedRelation::new(manager, pairs, inputs, &partitions, options);
+        for partition in partitions {
+            Cudd_RecursiveDeref(manager, partition);
+        }
+        relation
+    }
+
+    /// The relation of the latches of a circuit built using `Circuit::build` with next-state
+    /// variables (see `CircuitOptions::next_state_vars`), with one partition per latch.
+    ///
+    /// # Safety
+    ///
+    /// Same as `PartitionedRelation::new`, and `circuit` must be built by the `manager`.
+    pub unsafe fn from_circuit(
+        manager: *mut DdManager,
+        circuit: &CircuitBdds,
+        options: &PartitionOptions,
+    ) -> Result<PartitionedRelation> {
+        let pairs = latch_pairs(circuit)?;
+        let functions: Vec<*mut DdNode> = circuit.latches.iter().map(|l| l.function).collect();
+        let inputs: Vec<c_int> = circuit.inputs.iter().map(|(_, index)| *index).collect();
+        PartitionedRelation::from_functions(manager, &pairs, &inputs, &functions, options)
+    }
+
+    /// Conjoin consecutive partitions while the clusters stay below the threshold.
+    unsafe fn cluster(&mut self, parts: Vec<Part>) -> Result<()> {
+        let manager = self.manager;
+        for part in parts {
+            Cudd_Ref(part.node);
+            let previous = match self.clusters.last_mut() {
+                Some(previous) => previous,
+                None => {
+                    self.clusters.push(part.node);
+                    continue;
+                }
+            };
+            let conjunction = match referenced(Cudd_bddAnd(manager, *previous, part.node)) {
+                Ok(conjunction) => conjunction,
+                Err(error) => {
+                    Cudd_RecursiveDeref(manager, part.node);
+                    return Err(error);
+                }
+            };
+            if Cudd_DagSize(conjunction) as usize <= self.options.cluster_threshold {
+                Cudd_RecursiveDeref(manager, *previous);
+                Cudd_RecursiveDeref(manager, part.node);
+                *previous = conjunction;
+            } else {
+                Cudd_RecursiveDeref(manager, conjunction);
+                self.clusters.push(part.node);
+            }
+        }
+        if self.clusters.is_empty() {
+            let one = Cudd_ReadOne(manager);
+            Cudd_Ref(one);
+            self.clusters.push(one);
+        }
+        Ok(())
+    }
+
+    /// Compute the variables quantified after every cluster: the variables which do not
+    /// appear in any later cluster.
+    unsafe fn quantification_cubes(&mut self, quantified: &HashSet<c_int>) -> Result<()> {
+        let clusters = self.cluster_supports()?;
+        let mut cubes = vec![Vec::new(); clusters.len()];
+        for index in quantified {
+            let last = clusters.iter().rposition(|support| support.contains(index));
+            cubes[last.unwrap_or(0)].push(*index);
+        }
+        for mut cube in cubes {
+            cube.sort_unstable();
+            self.cubes.push(indices_cube(self.manager, &cube)?);
+        }
+        Ok(())
+    }
+
+    unsafe fn cluster_supports(&self) -> Result<Vec<HashSet<c_int>>> {
+        let mut supports = Vec::new();
+        for cluster in &self.clusters {
+            supports.push(support(self.manager, *cluster)?.into_iter().collect());
+        }
+        Ok(supports)
+    }
+
+    /// The present-state variables (indices).
+    pub fn present_vars(&self) -> &[c_int] {
+        &self.present
+    }
+
+    /// The next-state variables (indices), in the order of their present-state variables.
+    pub fn next_vars(&self) -> &[c_int] {
+        &self.next
+    }
+
+    /// The input variables (indices).
+    pub fn input_vars(&self) -> &[c_int] {
+        &self.inputs
+    }
+
+    /// The clusters in the order in which they are conjoined (not referenced again).
+    pub fn clusters(&self) -> &[*mut DdNode] {
+        &self.clusters
+    }
+
+    /// The cubes of the variables quantified right after conjoining each cluster (not
+    /// referenced again).
+    pub fn quantification_schedule(&self) -> &[*mut DdNode] {
+        &self.cubes
+    }
+
+    /// The monolithic transition relation (the conjunction of all clusters).
+    ///
+    /// # Safety
+    ///
+    /// The manager of the relation must still be valid.
+    pub unsafe fn monolithic(&self) -> Result<*mut DdNode> {
+        let manager = self.manager;
+        let mut relation = Cudd_ReadOne(manager);
+        Cudd_Ref(relation);
+        for cluster in &self.clusters {
+            let conjunction = referenced(Cudd_bddAnd(manager, relation, *cluster));
+            Cudd_RecursiveDeref(manager, relation);
+            relation = conjunction?;
+        }
+        Ok(relation)
+    }
+
+    /// The states reachable in one step from the `states` (over the present-state
+    /// variables).
+    ///
+    /// # Safety
+    ///
+    /// The manager of the relation must still be valid and `states` must be a BDD of it.
+    pub unsafe fn image(&self, states: *mut DdNode) -> Result<Image> {
+        let manager = self.manager;
+        let mut exact = true;
+        let mut product = states;
+        Cudd_Ref(product);
+        for (cluster, cube) in self.clusters.iter().zip(&self.cubes) {
+            let next = self.and_abstract(product, *cluster, *cube, &mut exact);
+            Cudd_RecursiveDeref(manager, product);
+            product = next?;
+        }
+        let states = swap_vars(manager, product, &self.swap);
+        Cudd_RecursiveDeref(manager, product);
+        Ok(Image {
+            states: referenced(states)?,
+            exact,
+        })
+    }
+
+    /// One step of the image: `Cudd_bddAndAbstract`, or `Cudd_bddAndAbstractLimit` with the
+    /// clipping fallback if a node limit is set.
+    unsafe fn and_abstract(
+        &self,
+        f: *mut DdNode,
+        g: *mut DdNode,
+        cube: *mut DdNode,
+        exact: &mut bool,
+    ) -> Result<*mut DdNode> {
+        let manager = self.manager;
+        let limit = match self.options.node_limit {
+            Some(limit) => limit,
+            None => return referenced(Cudd_bddAndAbstract(manager, f, g, cube)),
+        };
+        let result = Cudd_bddAndAbstractLimit(manager, f, g, cube, limit);
+        if !result.is_null() {
+            return referenced(result);
+        }
+        if Cudd_ReadErrorCode(manager) != Cudd_ErrorType::CUDD_TOO_MANY_NODES {
+            return Err(Error::new(ErrorKind::Other, "CUDD ran out of memory."));
+        }
+        Cudd_ClearErrorCode(manager);
+        match self.options.clipping_depth {
+            Some(depth) => {
+                *exact = false;
+                referenced(Cudd_bddClippingAndAbstract(manager, f, g, cube, depth, 1))
+            }
+            None => {
+                let message = format!("The image exceeded the limit of {} new nodes.", limit);
+                Err(Error::new(ErrorKind::Other, message))
+            }
+        }
+    }
+
+    /// Dereference the clusters and the quantification cubes.
+    ///
+    /// # Safety
+    ///
+    /// The manager of the relation must still be valid.
+    pub unsafe fn release(self) {
+        for node in self.clusters.iter().chain(&self.cubes) {
+            Cudd_RecursiveDeref(self.manager, *node);
+        }
+    }
+}
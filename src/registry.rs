@@ -0,0 +1,234 @@
+use cudd::*;
+use libc::{c_int, c_void};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+use std::ptr::null_mut;
+use {DdManager, DdNode};
+
+/// A mapping between variable names and variable indices.
+///
+/// Variable indices do not change when the variables are reordered, so the mapping stays
+/// valid after `Cudd_ReduceHeap` or `Cudd_ShuffleHeap`. The current level of a variable is
+/// obtained from its index using `Cudd_ReadPerm`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VarNames {
+    names: Vec<Option<String>>,
+    indices: HashMap<String, c_int>,
+}
+
+impl VarNames {
+    /// An empty mapping.
+    pub fn new() -> VarNames {
+        VarNames::default()
+    }
+
+    /// The index of the variable with the given name.
+    pub fn index(&self, name: &str) -> Option<c_int> {
+        self.indices.get(name).cloned()
+    }
+
+    /// The name of the variable with the given index.
+    pub fn name(&self, index: c_int) -> Option<&str> {
+        if index < 0 {
+            return None;
+        }
+        self.names
+            .get(index as usize)
+            .and_then(|name| name.as_deref())
+    }
+
+    /// Name the variable with the given index. The name must not be used by another
+    /// variable. A previous name of the variable is forgotten.
+    pub fn insert(&mut self, index: c_int, name: &str) -> Result<()> {
+        if index < 0 {
+            let message = format!("Invalid variable index {}.", index);
+            return Err(Error::new(ErrorKind::InvalidInput, message));
+        }
+        match self.index(name) {
+            Some(other) if other == index => return Ok(()),
+            Some(other) => {
+                let message = format!("Name `{}` is used by variable {}.", name, other);
+                return Err(Error::new(ErrorKind::InvalidInput, message));
+            }
+            None => (),
+        }
+        let index = index as usize;
+        if self.names.len() <= index {
+            self.names.resize(index + 1, None);
+        }
+        if let Some(old) = self.names[index].replace(name.to_string()) {
+            self.indices.remove(&old);
+        }
+        self.indices.insert(name.to_string(), index as c_int);
+        Ok(())
+    }
+
+    /// The number of named variables.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// True if no variable is named.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// The names of the first `count` variables, indexed by variable index. Unnamed
+    /// variables are named `x{index}`, unless this name is used by another variable.
+    pub fn to_vec(&self, count: usize) -> Vec<String> {
+        (0..count)
+            .map(|index| match self.name(index as c_int) {
+                Some(name) => name.to_string(),
+                None => {
+                    let mut name = format!("x{}", index);
+                    while self.indices.contains_key(&name) {
+                        name.push('_');
+                    }
+                    name
+                }
+            })
+            .collect()
+    }
+}
+
+/// Access to the `VarNames` of a manager, which are stored in its application hook
+/// (`Cudd_SetApplicationHook`). The hook can therefore not be used for other purposes.
+///
+/// The names are created on first use and have to be released using `release_var_names`
+/// before the manager is destroyed by `Cudd_Quit`. When names are registered, they are used
+/// by default by the exporters (`Netlist`, `to_dot`) and by the DDDMP writers.
+pub trait NamedVars {
+    /// The names of the variables of the manager (created if they do not exist yet).
+    ///
+    /// # Safety
+    ///
+    /// The manager must be valid and its application hook must not be used otherwise.
+    /// The returned reference must not outlive the names (see `release_var_names`).
+    unsafe fn var_names_mut<'a>(self) -> &'a mut VarNames;
+
+    /// The names of the variables of the manager, if any were registered.
+    ///
+    /// # Safety
+    ///
+    /// Same as `var_names_mut`.
+    unsafe fn registered_var_names<'a>(self) -> Option<&'a VarNames>;
+
+    /// The BDD variable with the given name. If there is no such variable, a new variable
+    /// is created (`Cudd_bddNewVar`) and named.
+    ///
+    /// # Safety
+    ///
+    /// Same as `var_names_mut`.
+    unsafe fn var(self, name: &str) -> Result<*mut DdNode>;
+
+    /// The index of the variable with the given name.
+    ///
+    /// # Safety
+    ///
+    /// Same as `var_names_mut`.
+    unsafe fn var_index(self, name: &str) -> Option<c_int>;
+
+    /// The current level of the variable with the given name (see `Cudd_ReadPerm`).
+    ///
+    /// # Safety
+    ///
+    /// Same as `var_names_mut`.
+    unsafe fn var_level(self, name: &str) -> Option<c_int>;
+
+    /// Name the variable with the given index.
+    ///
+    /// # Safety
+    ///
+    /// Same as `var_names_mut`.
+    unsafe fn set_var_name(self, index: c_int, name: &str) -> Result<()>;
+
+    /// The names of all variables of the manager, indexed by variable index, or `None`
+    /// if no names were registered (see `VarNames::to_vec`).
+    ///
+    /// # Safety
+    ///
+    /// Same as `var_names_mut`.
+    unsafe fn var_name_list(self) -> Option<Vec<String>>;
+
+    /// The names of all variables of the manager in the current variable order (see
+    /// `Cudd_ReadInvPerm`), or `None` if no names were registered.
+    ///
+    /// # Safety
+    ///
+    /// Same as `var_names_mut`.
+    unsafe fn var_names_by_level(self) -> Option<Vec<String>>;
+
+    /// Free the names stored with the manager and clear its application hook.
+    ///
+    /// # Safety
+    ///
+    /// Same as `var_names_mut`. No references to the names may be used afterwards.
+    unsafe fn release_var_names(self);
+}
+
+impl NamedVars for *mut DdManager {
+    unsafe fn var_names_mut<'a>(self) -> &'a mut VarNames {
+        let mut hook = Cudd_ReadApplicationHook(self) as *mut VarNames;
+        if hook.is_null() {
+            hook = Box::into_raw(Box::new(VarNames::new()));
+            Cudd_SetApplicationHook(self, hook as *mut c_void);
+        }
+        &mut *hook
+    }
+
+    unsafe fn registered_var_names<'a>(self) -> Option<&'a VarNames> {
+        let hook = Cudd_ReadApplicationHook(self) as *const VarNames;
+        if hook.is_null() || (*hook).is_empty() {
+            None
+        } else {
+            Some(&*hook)
+        }
+    }
+
+    unsafe fn var(self, name: &str) -> Result<*mut DdNode> {
+        if let Some(index) = self.var_index(name) {
+            return Ok(Cudd_bddIthVar(self, index));
+        }
+        let var = Cudd_bddNewVar(self);
+        if var.is_null() {
+            return Err(Error::new(ErrorKind::Other, "CUDD ran out of memory."));
+        }
+        self.var_names_mut()
+            .insert(Cudd_NodeReadIndex(var) as c_int, name)?;
+        Ok(var)
+    }
+
+    unsafe fn var_index(self, name: &str) -> Option<c_int> {
+        self.registered_var_names()
+            .and_then(|names| names.index(name))
+    }
+
+    unsafe fn var_level(self, name: &str) -> Option<c_int> {
+        self.var_index(name)
+            .filter(|index| *index < Cudd_ReadSize(self))
+            .map(|index| Cudd_ReadPerm(self, index))
+    }
+
+    unsafe fn set_var_name(self, index: c_int, name: &str) -> Result<()> {
+        self.var_names_mut().insert(index, name)
+    }
+
+    unsafe fn var_name_list(self) -> Option<Vec<String>> {
+        let count = Cudd_ReadSize(self) as usize;
+        self.registered_var_names().map(|names| names.to_vec(count))
+    }
+
+    unsafe fn var_names_by_level(self) -> Option<Vec<String>> {
+        let names = self.var_name_list()?;
+        let levels = (0..names.len() as c_int).map(|level| Cudd_ReadInvPerm(self, level));
+        Some(levels.map(|index| names[index as usize].clone()).collect())
+    }
+
+    unsafe fn release_var_names(self) {
+        let hook = Cudd_ReadApplicationHook(self) as *mut VarNames;
+        if !hook.is_null() {
+            Cudd_SetApplicationHook(self, null_mut());
+            drop(Box::from_raw(hook));
+        }
+    }
+}
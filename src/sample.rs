@@ -0,0 +1,279 @@
+use cudd::*;
+use epd::Epd;
+use libc::c_int;
+use rand_core::RngCore;
+use std::collections::{HashMap, HashSet};
+use std::io::{Error, ErrorKind, Result};
+use {DdManager, DdNode};
+
+/// A random sampler of the satisfying assignments of a BDD.
+///
+/// Unlike `Cudd_bddPickOneMinterm` and friends, which use the global generator of CUDD
+/// (`Cudd_Random`), the sampler draws assignments from any `RngCore`. Every variable is
+/// assigned `true` with its own probability (its weight) and the assignment is conditioned
+/// on satisfying the BDD. With the default weight of `0.5`, all satisfying assignments are
+/// equally likely.
+///
+/// The probability that a random assignment satisfies each node (and its complement) is
+/// computed when the sampler is created, so sampling only walks one path of the BDD. The
+/// probabilities are stored as `Epd`, so that BDDs with a tiny probability (e.g. a single
+/// minterm over thousands of variables) can be sampled as well.
+///
+/// Use a seeded generator (e.g. `SeedableRng::seed_from_u64`) for reproducible samples.
+pub struct Sampler {
+    root: *mut DdNode,
+    variables: Vec<c_int>,
+    weights: HashMap<c_int, f64>,
+    // The probability of the node and of its complement, for every regular node.
+    mass: HashMap<*mut DdNode, (Epd, Epd)>,
+    // The number of satisfying assignments which can be drawn (i.e. have a non-zero
+    // probability).
+    reachable: Epd,
+}
+
+/// The number of consecutive draws without a new assignment after which
+/// `Sampler::distinct_samples` gives up.
+pub const MAX_DRAWS_WITHOUT_PROGRESS: usize = 10_000;
+
+fn invalid_input<S: Into<String>>(message: S) -> Error {
+    Error::new(ErrorKind::InvalidInput, message.into())
+}
+
+/// A random number in `[0, 1)`.
+fn random_unit<R: RngCore>(rng: &mut R) -> f64 {
+    (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+impl Sampler {
+    /// A sampler which draws uniformly from the satisfying assignments of `f` over all
+    /// variables of the manager.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager and `f` a valid BDD of this manager. The BDD
+    /// must stay referenced and the variables must not be reordered while the sampler is used.
+    pub unsafe fn new(manager: *mut DdManager, f: *mut DdNode) -> Result<Sampler> {
+        let variables: Vec<c_int> = (0..Cudd_ReadSize(manager)).collect();
+        let weights = vec![0.5; variables.len()];
+        Sampler::weighted(manager, f, &variables, &weights)
+    }
+
+    /// A sampler which draws the satisfying assignments of `f` over the given `variables`
+    /// (indices), where `weights[i]` is the probability that `variables[i]` is `true`
+    /// (before conditioning on `f`). The support of `f` must be included in the variables.
+    ///
+    /// # Safety
+    ///
+    /// Same as `Sampler::new`.
+    pub unsafe fn weighted(
+        manager: *mut DdManager,
+        f: *mut DdNode,
+        variables: &[c_int],
+        weights: &[f64],
+    ) -> Result<Sampler> {
+        if variables.len() != weights.len() {
+            let message = format!(
+                "Expected {} weights, but {} were given.",
+                variables.len(),
+                weights.len()
+            );
+            return Err(invalid_input(message));
+        }
+        if let Some(weight) = weights.iter().find(|w| !(0.0..=1.0).contains(*w)) {
+            let message = format!("Weight {} is not a probability.", weight);
+            return Err(invalid_input(message));
+        }
+        let mut sampler = Sampler {
+            root: f,
+            variables: variables.to_vec(),
+            weights: variables
+                .iter()
+                .cloned()
+                .zip(weights.iter().cloned())
+                .collect(),
+            mass: HashMap::new(),
+            reachable: Epd::zero(),
+        };
+        if sampler.weights.len() != variables.len() {
+            return Err(invalid_input("A variable is given more than once."));
+        }
+        sampler.compute_mass(Cudd_Regular(f))?;
+        sampler.reachable = sampler.count_reachable(manager)?;
+        Ok(sampler)
+    }
+
+    /// Compute the probabilities of the `node` and of its children.
+    unsafe fn compute_mass(&mut self, node: *mut DdNode) -> Result<(Epd, Epd)> {
+        if let Some(mass) = self.mass.get(&node) {
+            return Ok(*mass);
+        }
+        let mass = if Cudd_IsConstant(node) != 0 {
+            (Epd::from(1.0), Epd::zero())
+        } else {
+            let index = Cudd_NodeReadIndex(node) as c_int;
+            let weight = match self.weights.get(&index) {
+                Some(weight) => *weight,
+                None => {
+                    let message = format!("Variable {} is not sampled.", index);
+                    return Err(invalid_input(message));
+                }
+            };
+            let (then_weight, else_weight) = (Epd::from(weight), Epd::from(1.0 - weight));
+            let then_mass = self.edge_mass(Cudd_T(node))?;
+            let else_mass = self.edge_mass(Cudd_E(node))?;
+            (
+                then_weight * then_mass.0 + else_weight * else_mass.0,
+                then_weight * then_mass.1 + else_weight * else_mass.1,
+            )
+        };
+        self.mass.insert(node, mass);
+        Ok(mass)
+    }
+
+    unsafe fn edge_mass(&mut self, edge: *mut DdNode) -> Result<(Epd, Epd)> {
+        let (positive, negative) = self.compute_mass(Cudd_Regular(edge))?;
+        if Cudd_IsComplement(edge) != 0 {
+            Ok((negative, positive))
+        } else {
+            Ok((positive, negative))
+        }
+    }
+
+    /// The number of satisfying assignments in which no variable has a value of
+    /// probability zero.
+    unsafe fn count_reachable(&self, manager: *mut DdManager) -> Result<Epd> {
+        let mut restricted = self.root;
+        Cudd_Ref(restricted);
+        for (index, weight) in &self.weights {
+            let var = Cudd_bddIthVar(manager, *index);
+            let literal = if *weight == 0.0 {
+                Cudd_Not(var)
+            } else if *weight == 1.0 {
+                var
+            } else {
+                continue;
+            };
+            let next = Cudd_bddAnd(manager, restricted, literal);
+            if next.is_null() {
+                Cudd_RecursiveDeref(manager, restricted);
+                return Err(Error::new(ErrorKind::Other, "CUDD ran out of memory."));
+            }
+            Cudd_Ref(next);
+            Cudd_RecursiveDeref(manager, restricted);
+            restricted = next;
+        }
+        let count = Epd::count_minterm(manager, restricted, self.variables.len() as c_int);
+        Cudd_RecursiveDeref(manager, restricted);
+        count
+    }
+
+    /// The sampled variables; samples contain their values in this order.
+    pub fn variables(&self) -> &[c_int] {
+        &self.variables
+    }
+
+    /// The probability that a random assignment (drawn using the weights) satisfies the BDD.
+    pub fn probability(&self) -> f64 {
+        self.probability_epd().to_f64()
+    }
+
+    /// Same as `probability`, but without rounding tiny probabilities to zero.
+    pub fn probability_epd(&self) -> Epd {
+        unsafe { self.satisfying_mass(self.root) }
+    }
+
+    /// The number of distinct assignments which can be sampled (infinite if it does not fit
+    /// into an `f64`).
+    pub fn reachable_count(&self) -> f64 {
+        self.reachable.to_f64()
+    }
+
+    /// Draw one satisfying assignment, or return `None` if the BDD cannot be satisfied.
+    pub fn sample<R: RngCore>(&self, rng: &mut R) -> Option<Vec<bool>> {
+        if self.probability_epd().is_zero() {
+            return None;
+        }
+        let mut values: HashMap<c_int, bool> = HashMap::new();
+        let mut edge = self.root;
+        unsafe {
+            while Cudd_IsConstant(Cudd_Regular(edge)) == 0 {
+                let node = Cudd_Regular(edge);
+                let complement = Cudd_IsComplement(edge) != 0;
+                let index = Cudd_NodeReadIndex(node) as c_int;
+                let weight = self.weights[&index];
+                let (then_edge, else_edge) = if complement {
+                    (Cudd_Not(Cudd_T(node)), Cudd_Not(Cudd_E(node)))
+                } else {
+                    (Cudd_T(node), Cudd_E(node))
+                };
+                let then_mass = Epd::from(weight) * self.satisfying_mass(then_edge);
+                let else_mass = Epd::from(1.0 - weight) * self.satisfying_mass(else_edge);
+                // The ratio is in `[0, 1]`, so it fits into an `f64` even if the masses do not.
+                let then_ratio = if then_mass.is_zero() {
+                    0.0
+                } else {
+                    (then_mass / (then_mass + else_mass)).to_f64()
+                };
+                let value = random_unit(rng) < then_ratio;
+                values.insert(index, value);
+                edge = if value { then_edge } else { else_edge };
+            }
+        }
+        let sample = self
+            .variables
+            .iter()
+            .map(|index| match values.get(index) {
+                Some(value) => *value,
+                None => random_unit(rng) < self.weights[index],
+            })
+            .collect();
+        Some(sample)
+    }
+
+    unsafe fn satisfying_mass(&self, edge: *mut DdNode) -> Epd {
+        let (positive, negative) = self.mass[&Cudd_Regular(edge)];
+        if Cudd_IsComplement(edge) != 0 {
+            negative
+        } else {
+            positive
+        }
+    }
+
+    /// Draw `count` satisfying assignments (with repetitions).
+    pub fn samples<R: RngCore>(&self, rng: &mut R, count: usize) -> Vec<Vec<bool>> {
+        (0..count).filter_map(|_| self.sample(rng)).collect()
+    }
+
+    /// Draw `count` distinct satisfying assignments, or all of them if there are fewer.
+    /// The samples are returned in the order in which they were drawn, so the result is
+    /// deterministic for a seeded generator.
+    ///
+    /// Assignments with a tiny probability (e.g. due to a weight of `1e-20`) may be
+    /// impossible to draw in practice, so drawing stops after `MAX_DRAWS_WITHOUT_PROGRESS`
+    /// consecutive draws which do not produce a new assignment. Fewer samples are returned
+    /// in that case.
+    pub fn distinct_samples<R: RngCore>(&self, rng: &mut R, count: usize) -> Vec<Vec<bool>> {
+        let count = if Epd::from(count as f64) > self.reachable {
+            self.reachable.to_f64() as usize
+        } else {
+            count
+        };
+        let mut seen = HashSet::new();
+        let mut samples = Vec::new();
+        let mut failed_draws = 0;
+        while samples.len() < count && failed_draws < MAX_DRAWS_WITHOUT_PROGRESS {
+            match self.sample(rng) {
+                Some(sample) => {
+                    if seen.insert(sample.clone()) {
+                        samples.push(sample);
+                        failed_draws = 0;
+                    } else {
+                        failed_draws += 1;
+                    }
+                }
+                None => break,
+            }
+        }
+        samples
+    }
+}
@@ -1,6 +1,29 @@
+use apa::ApaNumber;
+use capture;
+use cfile;
+use circuit::{Circuit, CircuitOptions};
+use ctl::{Ctl, CtlChecker, CtlTrace};
 use cudd::*;
+use dddmp;
+use dddmp::{DddmpError, DddmpFile, DddmpMode, DddmpReader, DddmpWriter, Dddmp_DecompType};
+use dimacs::{from_dimacs, ClauseSchedule, DimacsCnf, DimacsCompiler};
+use dot;
+use dot::DotOptions;
+use epd::Epd;
+use expr::{factored_form, isop_cover, parse_bdd, Expr};
+use groups;
+use groups::{Group, GroupTree};
 use libc::{c_int, c_void};
-use std::ptr::null_mut;
+use mtr::*;
+use netlist::{Netlist, NetlistFormat};
+use order;
+use partition::{PartitionOptions, PartitionedRelation};
+use registry::NamedVars;
+use std::collections::HashMap;
+use std::ptr::{null, null_mut};
+use transition::{FrontierSimplification, TraceStep, TransitionSystem};
+use weighted;
+use {DdNode, MtrHalfWord, MtrNode};
 
 static mut CALLED: bool = false;
 
@@ -60,3 +83,1621 @@ pub fn basic_functionality_test() {
         Cudd_Quit(cudd);
     }
 }
+
+#[test]
+pub fn capture_output_test() {
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let stdout = Cudd_ReadStdout(cudd);
+
+        let a = Cudd_bddIthVar(cudd, 0);
+        let b = Cudd_bddIthVar(cudd, 1);
+        let a_and_b = Cudd_bddAnd(cudd, a, b);
+        Cudd_Ref(a_and_b);
+
+        let minterms = capture::print_minterm(cudd, a_and_b).unwrap();
+        assert_eq!("11 1\n", minterms);
+        let info = capture::print_info(cudd).unwrap();
+        assert!(info.contains("Number of variables"));
+        assert_eq!(stdout, Cudd_ReadStdout(cudd));
+
+        let mut digits = 0;
+        let count = Cudd_ApaCountMinterm(cudd, a_and_b, 10, &mut digits);
+        assert_eq!("256", capture::apa_print_decimal(digits, count).unwrap());
+        Cudd_FreeApaNumber(count);
+
+        Cudd_RecursiveDeref(cudd, a_and_b);
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn cfile_test() {
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let a = Cudd_bddIthVar(cudd, 0);
+        let b = Cudd_bddIthVar(cudd, 1);
+        let mut a_and_b = Cudd_bddAnd(cudd, a, b);
+        Cudd_Ref(a_and_b);
+
+        let mut output: Vec<u8> = Vec::new();
+        cfile::write_with(&mut output, |fp| {
+            Cudd_DumpFactoredForm(cudd, 1, &mut a_and_b, null(), null(), fp)
+        })
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("x0") && output.contains("x1"));
+
+        let groups = "0 2 D\n2 2 F\n";
+        let tree = cfile::read_with(groups.as_bytes(), |fp| Mtr_ReadGroups(fp, 4)).unwrap();
+        assert!(!tree.is_null());
+        Mtr_FreeTree(tree);
+
+        Cudd_RecursiveDeref(cudd, a_and_b);
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn mtr_node_test() {
+    unsafe {
+        let root = Mtr_InitGroupTree(0, 8);
+        // The layout has to match `struct MtrNode` in `mtr.h`.
+        let half_word = std::mem::size_of::<MtrHalfWord>();
+        let pointer = std::mem::size_of::<*mut MtrNode>();
+        let offset = |field: *const u8| field as usize - root as usize;
+        assert_eq!(std::mem::size_of::<MtrNode>(), 4 * half_word + 4 * pointer);
+        assert_eq!(
+            offset(&(*root).index as *const _ as *const u8),
+            3 * half_word
+        );
+        assert_eq!(
+            offset(&(*root).parent as *const _ as *const u8),
+            4 * half_word
+        );
+        assert_eq!(
+            offset(&(*root).younger as *const _ as *const u8),
+            4 * half_word + 3 * pointer
+        );
+        let first = Mtr_MakeGroup(root, 0, 4, MTR_FIXED);
+        let second = Mtr_MakeGroup(root, 4, 4, MTR_DEFAULT);
+        assert!(!first.is_null() && !second.is_null());
+        assert_eq!(((*root).low(), (*root).size()), (0, 8));
+        assert_eq!(((*first).low(), (*first).size()), (0, 4));
+        assert_eq!((*root).child(), first);
+        assert_eq!((*first).younger(), second);
+        assert_eq!((*second).elder(), first);
+        assert_eq!((*second).parent(), root);
+        assert_eq!(Mtr_FindGroup(root, 4, 4), second);
+
+        assert_ne!(MTR_TEST(first, MTR_FIXED), 0);
+        MTR_RESET(first, MTR_FIXED);
+        assert_eq!(MTR_TEST(first, MTR_FIXED), 0);
+        MTR_SET(second, MTR_FIXED);
+        assert_ne!(MTR_TEST(second, MTR_FIXED), 0);
+
+        let mut output = String::new();
+        Mtr_WriteGroups(root, &mut output).unwrap();
+        assert_eq!(output, "(0(0,3)(4,7|F)7)");
+        Mtr_FreeTree(root);
+    }
+}
+
+#[test]
+pub fn dot_export_test() {
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let a = Cudd_bddIthVar(cudd, 0);
+        let b = Cudd_bddIthVar(cudd, 1);
+        let a_or_b = Cudd_bddOr(cudd, a, b);
+        Cudd_Ref(a_or_b);
+
+        let options = DotOptions::new()
+            .input_names(&["a", "b"])
+            .output_names(&["a_or_b"])
+            .complement_color("red")
+            .cluster_levels(true);
+        let dot = dot::to_dot(cudd, &[a_or_b], &options).unwrap();
+        assert!(dot.starts_with("digraph"));
+        assert!(dot.contains("\" a \"") && dot.contains("\" b \""));
+        assert!(dot.contains("a_or_b"));
+        assert!(dot.contains("cluster_0") && dot.contains("cluster_1"));
+        assert!(dot.contains("color = \"red\""));
+
+        let quoted = DotOptions::new().complement_color("red\" penwidth=\"3");
+        let dot = dot::to_dot(cudd, &[a_or_b], &quoted).unwrap();
+        assert!(dot.contains("color = \"red\\\" penwidth=\\\"3\""));
+
+        // A trailing backslash must not escape the closing quote.
+        let escaped = DotOptions::new().input_names(&["a\\", "b\"c"]);
+        let dot = dot::to_dot(cudd, &[a_or_b], &escaped).unwrap();
+        assert!(dot.contains("\" a\\\\ \"") && dot.contains("\" b\\\"c \""));
+
+        let missing_names = DotOptions::new().input_names(&["a"]);
+        assert!(dot::to_dot(cudd, &[a_or_b], &missing_names).is_err());
+
+        Cudd_RecursiveDeref(cudd, a_or_b);
+        Cudd_Quit(cudd);
+    }
+}
+
+/// The `.names` tables of a BLIF model: the inputs and the cover of each output.
+type BlifTables = HashMap<String, (Vec<String>, Vec<String>)>;
+
+/// A minimal reader of combinational BLIF models: returns the inputs, the outputs and
+/// the `.names` tables of the model.
+fn parse_blif(blif: &str) -> (Vec<String>, Vec<String>, BlifTables) {
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    let mut tables = HashMap::new();
+    let mut current: Option<String> = None;
+    for line in blif.replace("\\\n", " ").lines() {
+        let line = line.split('#').next().unwrap().trim();
+        let tokens: Vec<String> = line.split_whitespace().map(|t| t.to_string()).collect();
+        match tokens.first().map(|t| t.as_str()) {
+            None => continue,
+            Some(".inputs") => inputs.extend_from_slice(&tokens[1..]),
+            Some(".outputs") => outputs.extend_from_slice(&tokens[1..]),
+            Some(".names") => {
+                let (output, fanin) = tokens[1..].split_last().unwrap();
+                tables.insert(output.clone(), (fanin.to_vec(), Vec::new()));
+                current = Some(output.clone());
+            }
+            Some(keyword) if keyword.starts_with('.') => current = None,
+            Some(_) => {
+                let table = tables.get_mut(current.as_ref().unwrap()).unwrap();
+                table.1.push(line.to_string());
+            }
+        }
+    }
+    (inputs, outputs, tables)
+}
+
+fn eval_blif(tables: &BlifTables, values: &HashMap<String, bool>, signal: &str) -> bool {
+    if let Some(value) = values.get(signal) {
+        return *value;
+    }
+    let (fanin, cover) = &tables[signal];
+    let fanin: Vec<bool> = fanin.iter().map(|s| eval_blif(tables, values, s)).collect();
+    let mut on_set = true;
+    for row in cover {
+        let parts: Vec<&str> = row.split_whitespace().collect();
+        let (pattern, value) = if parts.len() == 1 {
+            ("", parts[0])
+        } else {
+            (parts[0], parts[1])
+        };
+        on_set = value == "1";
+        let matches = pattern.chars().zip(&fanin).all(|(c, v)| match c {
+            '1' => *v,
+            '0' => !*v,
+            _ => true,
+        });
+        if matches {
+            return on_set;
+        }
+    }
+    // No row matches, so the output is not in the listed set (an empty table is zero).
+    !on_set
+}
+
+#[test]
+pub fn netlist_export_test() {
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let a = Cudd_bddIthVar(cudd, 0);
+        let b = Cudd_bddIthVar(cudd, 1);
+        let c = Cudd_bddIthVar(cudd, 2);
+        let a_xor_b = Cudd_bddXor(cudd, a, b);
+        Cudd_Ref(a_xor_b);
+        let f = Cudd_bddIte(cudd, c, a_xor_b, Cudd_Not(a));
+        Cudd_Ref(f);
+        let roots = [f, a_xor_b];
+
+        for format in &[NetlistFormat::Blif, NetlistFormat::DddmpBlif] {
+            let mut buffer = Vec::new();
+            Netlist::new(*format)
+                .model_name("test")
+                .input_names(&["a", "b", "c"])
+                .output_names(&["f", "a_xor_b"])
+                .write(cudd, &mut buffer, &roots)
+                .unwrap();
+            let blif = String::from_utf8(buffer).unwrap();
+            assert!(blif.contains(".model test"));
+            let (inputs, outputs, tables) = parse_blif(&blif);
+            assert_eq!(vec!["a", "b", "c"], inputs);
+            assert_eq!(vec!["f", "a_xor_b"], outputs);
+            for bits in 0..8 {
+                let mut assignment: Vec<c_int> = (0..3).map(|i| (bits >> i) & 1).collect();
+                let values: HashMap<String, bool> = inputs
+                    .iter()
+                    .zip(&assignment)
+                    .map(|(name, value)| (name.clone(), *value == 1))
+                    .collect();
+                for (root, output) in roots.iter().zip(&outputs) {
+                    let expected = Cudd_Eval(cudd, *root, assignment.as_mut_ptr());
+                    let expected = expected == Cudd_ReadOne(cudd);
+                    assert_eq!(expected, eval_blif(&tables, &values, output));
+                }
+            }
+        }
+
+        let mut smv = Vec::new();
+        Netlist::new(NetlistFormat::Smv)
+            .write(cudd, &mut smv, &roots)
+            .unwrap();
+        assert!(String::from_utf8(smv).unwrap().contains("x0"));
+
+        let invalid = Netlist::new(NetlistFormat::Smv).input_names(&["a", "next", "c"]);
+        assert!(invalid.write(cudd, Vec::new(), &roots).is_err());
+        let invalid = Netlist::new(NetlistFormat::Blif).output_names(&["f", "#g"]);
+        assert!(invalid.write(cudd, Vec::new(), &roots).is_err());
+
+        Cudd_RecursiveDeref(cudd, f);
+        Cudd_RecursiveDeref(cudd, a_xor_b);
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn circuit_import_test() {
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let blif = ".model counter\n.inputs a b\n.outputs f\n.latch n s 0\n\
+                    .names a b f\n11 1\n.names s a n\n00 0\n.end\n";
+        let circuit = Circuit::parse_blif(blif).unwrap();
+        assert_eq!(vec!["a", "b"], circuit.input_names());
+        assert_eq!(vec!["s"], circuit.latch_names());
+        let options = CircuitOptions::new().next_state_vars(true);
+        let bdds = circuit.build(cudd, &options).unwrap();
+        let names: Vec<String> = bdds.var_names().into_iter().map(|(_, n)| n).collect();
+        assert_eq!(vec!["a", "b", "s", "s'"], names);
+        assert_eq!(Some(3), bdds.latches[0].next);
+        assert_eq!(1, Cudd_bddIsNsVar(cudd, 3));
+        assert_eq!(2, Cudd_bddReadPairIndex(cudd, 3));
+        assert_eq!(Some(false), bdds.latches[0].init);
+        let a = Cudd_bddIthVar(cudd, 0);
+        let b = Cudd_bddIthVar(cudd, 1);
+        let s = Cudd_bddIthVar(cudd, 2);
+        let a_and_b = Cudd_bddAnd(cudd, a, b);
+        Cudd_Ref(a_and_b);
+        let s_or_a = Cudd_bddOr(cudd, s, a);
+        Cudd_Ref(s_or_a);
+        assert_eq!(a_and_b, bdds.outputs[0].1);
+        assert_eq!(s_or_a, bdds.latches[0].function);
+        bdds.release(cudd);
+        Cudd_RecursiveDeref(cudd, s_or_a);
+
+        // The same AND gate in the ASCII and binary AIGER formats, with named symbols.
+        let aag = b"aag 3 2 0 2 1\n2\n4\n6\n7\n6 2 4\ni0 x\ni1 y\no0 f\n";
+        let mut aig = b"aig 3 2 0 2 1\n6\n7\n".to_vec();
+        aig.extend_from_slice(&[2, 2]);
+        aig.extend_from_slice(b"i0 x\ni1 y\no0 f\nc\ncomment\n");
+        for bytes in &[&aag[..], &aig[..]] {
+            let circuit = Circuit::parse_aiger(bytes).unwrap();
+            assert_eq!(vec!["x", "y"], circuit.input_names());
+            assert_eq!(vec!["f", "o1"], circuit.output_names());
+            let options = CircuitOptions::new().reordering(None);
+            let bdds = circuit.build(cudd, &options).unwrap();
+            let x = Cudd_bddIthVar(cudd, bdds.inputs[0].1);
+            let y = Cudd_bddIthVar(cudd, bdds.inputs[1].1);
+            let x_and_y = Cudd_bddAnd(cudd, x, y);
+            Cudd_Ref(x_and_y);
+            assert_eq!(x_and_y, bdds.outputs[0].1);
+            assert_eq!(Cudd_Not(x_and_y), bdds.outputs[1].1);
+            Cudd_RecursiveDeref(cudd, x_and_y);
+            bdds.release(cudd);
+        }
+
+        // A BLIF netlist exported by CUDD is imported back.
+        let mut buffer = Vec::new();
+        Netlist::new(NetlistFormat::Blif)
+            .write(cudd, &mut buffer, &[a_and_b])
+            .unwrap();
+        let circuit = Circuit::parse_blif(&String::from_utf8(buffer).unwrap()).unwrap();
+        let bdds = circuit.build(cudd, &CircuitOptions::new()).unwrap();
+        let input = |name: &str| {
+            let index = bdds.inputs.iter().find(|(n, _)| n == name).unwrap().1;
+            Cudd_bddIthVar(cudd, index)
+        };
+        let expected = Cudd_bddAnd(cudd, input("x0"), input("x1"));
+        Cudd_Ref(expected);
+        assert_eq!(expected, bdds.outputs[0].1);
+        Cudd_RecursiveDeref(cudd, expected);
+        bdds.release(cudd);
+
+        assert!(Circuit::parse_blif(".inputs a\n.outputs f\n.names a g f\n11 1\n").is_err());
+        let cyclic =
+            Circuit::parse_blif(".inputs a\n.outputs f\n.names a g f\n11 1\n.names f g\n1 1\n");
+        assert!(cyclic.unwrap().build(cudd, &CircuitOptions::new()).is_err());
+        assert!(Circuit::parse_aiger(b"aag 1 0 0 0 0 0 1\n").is_err());
+        assert!(Circuit::parse_aiger(b"aig 0 1 0 0 0\n").is_err());
+        assert!(Circuit::parse_aiger(b"aig 2 1 1 0 0\n").is_err());
+        assert!(Circuit::parse_aiger(b"aag 18446744073709551615 0 0 0 0\n").is_err());
+        assert!(Circuit::parse_aiger(b"aag 0 0 0 18446744073709551615 1\n").is_err());
+        let inputs = Circuit::parse_aiger(b"aig 800 800 0 1 0\n2\n").unwrap();
+        assert_eq!(inputs.input_names().len(), 800);
+
+        Cudd_RecursiveDeref(cudd, a_and_b);
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn dimacs_compile_test() {
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let dimacs = "c example\np cnf 6 7\n1 -2 0\n2 3\n-4 0 4 5 0\n-5 -6 0\n\
+                      1 6 0\n-1 -3 0\n3 5 6 0\n";
+        let cnf = DimacsCnf::parse(dimacs).unwrap();
+        assert_eq!(6, cnf.var_count);
+        assert_eq!(vec![2, 3, -4], cnf.clauses[1]);
+        let satisfies = |bits: u32, clause: &Vec<c_int>| {
+            clause
+                .iter()
+                .any(|l| ((bits >> (l.abs() - 1)) & 1 == 1) == (*l > 0))
+        };
+        let models = (0..64u32)
+            .filter(|bits| cnf.clauses.iter().all(|c| satisfies(*bits, c)))
+            .count();
+        // Projected models over the variables 1, 3 and 6.
+        let projected = (0..64u32)
+            .filter(|bits| {
+                (0..64u32).any(|other| {
+                    let bits = (bits & 0b100101) | (other & 0b011010);
+                    cnf.clauses.iter().all(|c| satisfies(bits, c))
+                })
+            })
+            .count()
+            / 8;
+
+        let schedules = [
+            ClauseSchedule::Linear,
+            ClauseSchedule::Balanced,
+            ClauseSchedule::BucketElimination,
+            ClauseSchedule::MinWidth,
+        ];
+        for schedule in &schedules {
+            let compiler = DimacsCompiler::new().schedule(*schedule).node_limit(1000);
+            let mut conjunctions = 0;
+            let f = compiler
+                .compile_with_progress(cudd, &cnf, |p| conjunctions = p.conjunctions)
+                .unwrap();
+            assert_eq!(6, conjunctions);
+            assert_eq!(models as f64, Cudd_CountMinterm(cudd, f, 6));
+            Cudd_RecursiveDeref(cudd, f);
+
+            let compiler = compiler.quantify(&[2, 5, 4]);
+            let g = compiler.compile(cudd, &cnf).unwrap();
+            for index in &[1, 3, 4] {
+                let var = Cudd_bddIthVar(cudd, *index);
+                let abstracted = Cudd_bddExistAbstract(cudd, g, var);
+                assert_eq!(g, abstracted);
+            }
+            assert_eq!(projected as f64, Cudd_CountMinterm(cudd, g, 3));
+            Cudd_RecursiveDeref(cudd, g);
+        }
+
+        let unsat = from_dimacs(cudd, &b"p cnf 1 2\n1 0\n-1 0\n"[..]).unwrap();
+        assert_eq!(Cudd_ReadLogicZero(cudd), unsat);
+        Cudd_RecursiveDeref(cudd, unsat);
+        assert!(DimacsCnf::parse("1 2 0\n").is_err());
+        assert!(DimacsCnf::parse("p cnf 2 1\n1 3 0\n").is_err());
+        assert!(DimacsCnf::parse("p cnf 99999999999 0\n").is_err());
+        assert!(DimacsCompiler::new()
+            .quantify(&[7])
+            .compile(cudd, &cnf)
+            .is_err());
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn expr_test() {
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let names = ["a", "b", "c", "d", "e"];
+        let f = parse_bdd(cudd, "a & !b | (c ^ d) -> e", &names).unwrap();
+        let vars: Vec<*mut DdNode> = (0..5).map(|i| Cudd_bddIthVar(cudd, i)).collect();
+        let a_and_not_b = Cudd_bddAnd(cudd, vars[0], Cudd_Not(vars[1]));
+        Cudd_Ref(a_and_not_b);
+        let c_xor_d = Cudd_bddXor(cudd, vars[2], vars[3]);
+        Cudd_Ref(c_xor_d);
+        let left = Cudd_bddOr(cudd, a_and_not_b, c_xor_d);
+        Cudd_Ref(left);
+        let expected = Cudd_bddOr(cudd, Cudd_Not(left), vars[4]);
+        Cudd_Ref(expected);
+        assert_eq!(expected, f);
+
+        // The printed forms are parsed back into the same function.
+        for printed in &[
+            factored_form(cudd, f, &names).unwrap(),
+            isop_cover(cudd, f, &names).unwrap(),
+        ] {
+            let g = parse_bdd(cudd, printed, &names).unwrap();
+            assert_eq!(f, g);
+            Cudd_RecursiveDeref(cudd, g);
+        }
+        let h = parse_bdd(cudd, "a & !b | c | a & c", &names).unwrap();
+        let cover = isop_cover(cudd, h, &names).unwrap();
+        let mut cubes: Vec<&str> = cover.split(" | ").collect();
+        cubes.sort_unstable();
+        assert_eq!(vec!["a & !b", "c"], cubes);
+        Cudd_RecursiveDeref(cudd, h);
+
+        let exists = parse_bdd(cudd, "exists b. a & !b", &names).unwrap();
+        assert_eq!(vars[0], exists);
+        let forall = parse_bdd(cudd, "forall c, d. c ^ d", &names).unwrap();
+        assert_eq!(Cudd_ReadLogicZero(cudd), forall);
+        Cudd_RecursiveDeref(cudd, exists);
+        Cudd_RecursiveDeref(cudd, forall);
+
+        let expr: Expr = "(a -> b) -> !(c | d) & e".parse().unwrap();
+        assert_eq!("(a -> b) -> !(c | d) & e", expr.to_string());
+        assert!(parse_bdd(cudd, "a & unknown", &names).is_err());
+        assert!(Expr::parse("a & (b | c").is_err());
+
+        for node in &[f, expected, left, c_xor_d, a_and_not_b] {
+            Cudd_RecursiveDeref(cudd, *node);
+        }
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn registry_test() {
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        assert_eq!(None, cudd.var_name_list());
+        let a = cudd.var("a").unwrap();
+        let b = cudd.var("b").unwrap();
+        let c = cudd.var("c").unwrap();
+        assert_eq!(a, cudd.var("a").unwrap());
+        assert_eq!(Some(1), cudd.var_index("b"));
+        assert!(cudd.set_var_name(2, "a").is_err());
+
+        // Names are attached to indices, so they survive reordering.
+        let mut permutation = [2, 0, 1];
+        assert_eq!(1, Cudd_ShuffleHeap(cudd, permutation.as_mut_ptr()));
+        assert_eq!(Some(0), cudd.var_level("c"));
+        assert_eq!(Some(2), cudd.var_level("b"));
+        let by_level = cudd.var_names_by_level().unwrap();
+        assert_eq!(vec!["c", "a", "b"], by_level);
+        assert_eq!(c, cudd.var("c").unwrap());
+
+        let f = Cudd_bddAnd(cudd, a, Cudd_Not(b));
+        Cudd_Ref(f);
+        let mut blif = Vec::new();
+        Netlist::new(NetlistFormat::Blif)
+            .write(cudd, &mut blif, &[f])
+            .unwrap();
+        let blif = String::from_utf8(blif).unwrap();
+        assert!(blif.contains(".inputs a b"));
+        let dot = dot::to_dot(cudd, &[f], &DotOptions::new()).unwrap();
+        assert!(dot.contains("\" a \""));
+        let mut stored = Vec::new();
+        DddmpWriter::new().write(cudd, &mut stored, &[f]).unwrap();
+        let header = dddmp::read_header(&stored[..]).unwrap();
+        assert_eq!(
+            Some(vec!["a".to_string(), "b".to_string()]),
+            header.support_names
+        );
+
+        Cudd_RecursiveDeref(cudd, f);
+        cudd.release_var_names();
+        assert!(Cudd_ReadApplicationHook(cudd).is_null());
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn order_test() {
+    unsafe {
+        let cudd = Cudd_Init(4, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        assert_eq!(vec![0, 1, 2, 3], order::current_order(cudd));
+        order::set_order(cudd, &[3, 1, 0, 2]).unwrap();
+        assert_eq!(vec![3, 1, 0, 2], order::current_order(cudd));
+        assert_eq!(0, Cudd_ReadPerm(cudd, 3));
+        assert!(order::set_order(cudd, &[0, 1, 2]).is_err());
+        assert!(order::set_order(cudd, &[0, 1, 1, 2]).is_err());
+        assert!(order::set_order(cudd, &[0, 1, 2, 4]).is_err());
+
+        let mut text = Vec::new();
+        order::write_order(&mut text, &[3, 1, 0, 2], Some(&["a", "b", "c", "d"])).unwrap();
+        assert_eq!(
+            "3 d\n1 b\n0 a\n2 c\n",
+            String::from_utf8(text.clone()).unwrap()
+        );
+        let read = order::read_order(&text[..]).unwrap();
+        assert_eq!((3, Some("d".to_string())), read[0]);
+
+        let path = std::env::temp_dir().join(format!("cudd-sys-order-{}.txt", std::process::id()));
+        cudd.set_var_name(0, "a").unwrap();
+        cudd.set_var_name(1, "b").unwrap();
+        order::save_order(cudd, &path).unwrap();
+        order::set_order(cudd, &[0, 1, 2, 3]).unwrap();
+        order::load_order(cudd, &path).unwrap();
+        assert_eq!(vec![3, 1, 0, 2], order::current_order(cudd));
+        order::set_order(cudd, &[0, 1, 2, 3]).unwrap();
+        // Names take precedence over the saved indices.
+        std::fs::write(&path, "3\n0 b\n1 a\n2\n").unwrap();
+        order::load_order(cudd, &path).unwrap();
+        assert_eq!(vec![3, 1, 0, 2], order::current_order(cudd));
+        std::fs::remove_file(&path).unwrap();
+
+        cudd.release_var_names();
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn group_tree_test() {
+    fn ranges(groups: &[Group]) -> Vec<(std::ops::Range<u32>, bool, usize)> {
+        groups
+            .iter()
+            .map(|group| (group.range(), group.is_fixed(), group.children.len()))
+            .collect()
+    }
+
+    let mut tree = GroupTree::new();
+    tree.group(4..8).group(4..6);
+    tree.group(0..4).fixed();
+    assert_eq!(tree.groups[0].range(), 0..4);
+    assert!(tree.validate().is_ok());
+
+    let mut invalid = GroupTree::new();
+    invalid.group(0..4);
+    invalid.group(2..6);
+    assert!(invalid.validate().is_err());
+    let mut invalid = GroupTree::new();
+    invalid.group(0..4).group(2..6);
+    assert!(invalid.validate().is_err());
+
+    unsafe {
+        let cudd = Cudd_Init(8, 8, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        assert!(GroupTree::read(cudd).is_none());
+        tree.attach(cudd).unwrap();
+        let read = GroupTree::read(cudd).unwrap();
+        assert_eq!(
+            ranges(&read.groups),
+            vec![(0..4, true, 0), (4..8, false, 1)]
+        );
+        assert_eq!(read.groups[1].children[0].range(), 4..6);
+        assert!(invalid.attach(cudd).is_err());
+
+        tree.attach_zdd(cudd).unwrap();
+        let read = GroupTree::read_zdd(cudd).unwrap();
+        assert_eq!(ranges(&read.groups), ranges(&tree.groups));
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn group_spec_test() {
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        for name in &["a", "b", "c", "d", "e", "f"] {
+            cudd.var(name).unwrap();
+        }
+        let spec = "# Two groups.\ngroup a c fixed\ngroup d f {\n    group d e soft\n}\n";
+        let tree = GroupTree::parse_spec(spec, cudd.registered_var_names()).unwrap();
+        assert_eq!(tree.groups[0].range(), 0..3);
+        assert!(tree.groups[0].is_fixed());
+        assert_eq!(tree.groups[1].children[0].range(), 3..5);
+        assert_eq!(tree.groups[1].children[0].flags, MTR_SOFT);
+
+        let mut output = Vec::new();
+        let names = cudd.var_name_list();
+        tree.write_spec(&mut output, names.as_deref()).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            spec.replace("# Two groups.\n", "")
+        );
+        assert!(GroupTree::parse_spec("group a z", cudd.registered_var_names()).is_err());
+        assert!(GroupTree::parse_spec("group 0 3 { group 2 5 }", None).is_err());
+        assert!(GroupTree::parse_spec("group 0 3 {", None).is_err());
+        for name in &["", "x y", "x{", "}", "x#1", "7", "group", "soft"] {
+            let names = ["a", "b", "c", "d", "e", name];
+            assert!(tree.write_spec(Vec::new(), Some(&names[..])).is_err());
+        }
+
+        tree.install(cudd).unwrap();
+        let read = GroupTree::read(cudd).unwrap();
+        assert_eq!(read.groups[0].range(), 0..3);
+        assert_eq!(read.groups[1].children[0].range(), 3..5);
+        assert_eq!(
+            Cudd_ReduceHeap(cudd, Cudd_ReorderingType::CUDD_REORDER_GROUP_SIFT, 0),
+            1
+        );
+
+        let path = std::env::temp_dir().join(format!("cudd-sys-groups-{}.txt", std::process::id()));
+        std::fs::write(&path, "group 0 1\ngroup c f fixed\n").unwrap();
+        groups::load_groups(cudd, &path).unwrap();
+        groups::save_groups(cudd, &path).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(saved, "group a b\ngroup c f fixed\n");
+
+        cudd.release_var_names();
+        Cudd_Quit(cudd);
+
+        // Unnamed variables are saved as indices when only some variables are named.
+        let cudd = Cudd_Init(4, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        cudd.set_var_name(1, "b").unwrap();
+        std::fs::write(&path, "group 0 b\ngroup 2 3 fixed\n").unwrap();
+        groups::load_groups(cudd, &path).unwrap();
+        groups::save_groups(cudd, &path).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(saved, "group 0 b\ngroup 2 3 fixed\n");
+        groups::load_groups(cudd, &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        cudd.release_var_names();
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn epd_test() {
+    let two = Epd::from(2.0);
+    let three = Epd::from(3.0);
+    assert_eq!((two * three).to_f64(), 6.0);
+    assert_eq!((two + three).to_f64(), 5.0);
+    assert_eq!((two - three).to_f64(), -1.0);
+    assert_eq!((three / two).to_f64(), 1.5);
+    assert_eq!(two * three, Epd::from(6.0));
+    assert!(two < three);
+    assert_eq!(three.partial_cmp(&two), Some(std::cmp::Ordering::Greater));
+    assert_eq!(two.partial_cmp(&two), Some(std::cmp::Ordering::Equal));
+    assert!(Epd::from(-1.0) < Epd::zero());
+    assert!(Epd::zero().is_zero() && Epd::from(0.0).is_zero());
+    assert!((Epd::from(1.0) + Epd::from(-1.0)).is_zero());
+    assert!((three - three).is_zero() && (Epd::zero() + two) == two);
+    assert!((Epd::pow2(5000) - Epd::pow2(5000)).is_zero());
+    assert!((Epd::infinity(false) - Epd::infinity(false)).is_nan());
+    assert_eq!(format!("{}", Epd::from(6.0)), "6.000000e+00");
+    assert_eq!(format!("{}", Epd::pow2(100)), "1.267651e+30");
+
+    let huge = Epd::pow2(5000);
+    assert!(!huge.is_inf() && huge.to_f64().is_infinite());
+    assert!(huge > Epd::from(f64::MAX));
+    assert!(Epd::infinity(false) > huge && Epd::infinity(true) < huge);
+    assert!(Epd::infinity(false).is_inf() && Epd::nan().is_nan_or_inf());
+    assert!(Epd::nan() != Epd::nan());
+    assert_eq!(Epd::nan().partial_cmp(&two), None);
+
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let x = Cudd_bddIthVar(cudd, 0);
+        let count = Epd::count_minterm(cudd, x, 2000).unwrap();
+        assert_eq!(count, Epd::pow2(1999));
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn apa_number_test() {
+    let seven = ApaNumber::from(7u128);
+    let big = ApaNumber::from(1u128) << 100;
+    assert_eq!(big.to_u128(), Some(1 << 100));
+    assert_eq!(format!("{}", big), "1267650600228229401496703205376");
+    assert_eq!(format!("{:x}", big), "10000000000000000000000000");
+    assert_eq!((&big + &seven).to_u128(), Some((1 << 100) + 7));
+    assert_eq!(
+        big.checked_sub(&seven).unwrap().to_u128(),
+        Some((1 << 100) - 7)
+    );
+    assert!(seven.checked_sub(&big).is_none());
+    assert_eq!((big.clone() >> 98).to_u128(), Some(4));
+    assert_eq!(ApaNumber::power_of_two(4, 100), big);
+    assert_eq!(ApaNumber::power_of_two(4, 127).to_u128(), Some(1 << 127));
+    assert!(ApaNumber::power_of_two(4, 128).is_zero());
+    assert!(ApaNumber::power_of_two(4, u32::MAX).is_zero());
+    assert_eq!(ApaNumber::power_of_two(1, 0).to_u128(), Some(1));
+    assert!(seven < big && (big.clone() << 100).to_u128().is_none());
+    assert_eq!(
+        big.compare_ratios(4, &(big.clone() >> 1), 2),
+        std::cmp::Ordering::Equal
+    );
+
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let x = Cudd_bddIthVar(cudd, 0);
+        let count = ApaNumber::count_minterm(cudd, x, 300).unwrap();
+        assert_eq!(count, ApaNumber::from(1u128) << 299);
+        let none = ApaNumber::count_minterm(cudd, Cudd_ReadLogicZero(cudd), 300).unwrap();
+        assert!(none.is_zero());
+        assert_eq!(format!("{}", none), "0");
+        Cudd_Quit(cudd);
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+#[test]
+pub fn apa_number_big_uint_test() {
+    use num_bigint::BigUint;
+
+    let big = ApaNumber::from(3u128) << 200;
+    let converted = BigUint::from(&big);
+    assert_eq!(converted, BigUint::from(3u32) << 200usize);
+    assert_eq!(ApaNumber::from(&converted), big);
+    assert_eq!(BigUint::from(&ApaNumber::from(0u128)), BigUint::from(0u32));
+    assert!(ApaNumber::from(&BigUint::from(0u32)).is_zero());
+}
+
+#[cfg(feature = "rand_core")]
+#[test]
+pub fn sampler_test() {
+    use rand_core::{impls, Error, RngCore};
+    use sample::Sampler;
+
+    // A SplitMix64 generator, so that the samples are reproducible.
+    struct TestRng(u64);
+
+    impl RngCore for TestRng {
+        fn next_u32(&mut self) -> u32 {
+            self.next_u64() as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = self.0;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            impls::fill_bytes_via_next(self, dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    unsafe {
+        let cudd = Cudd_Init(3, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let a = Cudd_bddIthVar(cudd, 0);
+        let b = Cudd_bddIthVar(cudd, 1);
+        // a | b has three satisfying assignments over a, b (and six over a, b, c).
+        let a_or_b = Cudd_bddOr(cudd, a, b);
+        Cudd_Ref(a_or_b);
+
+        let sampler = Sampler::new(cudd, a_or_b).unwrap();
+        assert_eq!(sampler.probability(), 0.75);
+        assert_eq!(sampler.reachable_count(), 6.0);
+        let mut rng = TestRng(7);
+        let mut counts = HashMap::new();
+        for sample in sampler.samples(&mut rng, 6000) {
+            assert!(sample[0] || sample[1]);
+            *counts.entry(sample).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 6);
+        assert!(counts.values().all(|count| (800..1200).contains(count)));
+
+        let distinct = sampler.distinct_samples(&mut TestRng(1), 10);
+        assert_eq!(distinct.len(), 6);
+        assert_eq!(distinct, sampler.distinct_samples(&mut TestRng(1), 10));
+
+        // With `b` never set, only `a & !b` remains.
+        let weighted = Sampler::weighted(cudd, a_or_b, &[0, 1], &[0.5, 0.0]).unwrap();
+        assert_eq!(weighted.reachable_count(), 1.0);
+        assert_eq!(
+            weighted.distinct_samples(&mut rng, 5),
+            vec![vec![true, false]]
+        );
+        // `a & b` has a probability of 1e-20 and cannot be drawn in practice.
+        let tiny = Sampler::weighted(cudd, a_or_b, &[0, 1], &[0.5, 1e-20]).unwrap();
+        assert_eq!(tiny.reachable_count(), 3.0);
+        let distinct = tiny.distinct_samples(&mut rng, 3);
+        assert_eq!(distinct, vec![vec![true, false]]);
+        assert!(Sampler::weighted(cudd, a_or_b, &[0], &[0.5]).is_err());
+        assert!(Sampler::weighted(cudd, a_or_b, &[0, 1], &[0.5, 2.0]).is_err());
+
+        let never = Sampler::new(cudd, Cudd_ReadLogicZero(cudd)).unwrap();
+        assert!(never.sample(&mut rng).is_none());
+
+        Cudd_RecursiveDeref(cudd, a_or_b);
+        Cudd_Quit(cudd);
+
+        // The probability of a single minterm over 1100 variables underflows an `f64`.
+        let cudd = Cudd_Init(1100, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let expected: Vec<bool> = (0..1100).map(|i| i % 3 == 0).collect();
+        let mut phases: Vec<c_int> = expected.iter().map(|value| *value as c_int).collect();
+        let mut vars: Vec<*mut DdNode> = (0..1100).map(|i| Cudd_bddIthVar(cudd, i)).collect();
+        let cube = Cudd_bddComputeCube(cudd, vars.as_mut_ptr(), phases.as_mut_ptr(), 1100);
+        Cudd_Ref(cube);
+        let sampler = Sampler::new(cudd, cube).unwrap();
+        assert_eq!(sampler.probability(), 0.0);
+        assert!(!sampler.probability_epd().is_zero());
+        assert_eq!(sampler.reachable_count(), 1.0);
+        assert_eq!(sampler.sample(&mut rng), Some(expected));
+        Cudd_RecursiveDeref(cudd, cube);
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn weighted_count_test() {
+    assert!((Epd::zero() * Epd::from(3.0)).is_zero());
+    assert!((Epd::from(2.0) - Epd::from(2.0)).is_zero());
+    assert_eq!((Epd::zero() + Epd::from(3.0)).to_f64(), 3.0);
+
+    unsafe {
+        let cudd = Cudd_Init(3, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let a = Cudd_bddIthVar(cudd, 0);
+        let b = Cudd_bddIthVar(cudd, 1);
+        let a_or_b = Cudd_bddOr(cudd, a, b);
+        Cudd_Ref(a_or_b);
+
+        let mut probabilities = HashMap::new();
+        probabilities.insert(0, 0.9);
+        probabilities.insert(1, 0.2);
+        let p = weighted::probability(cudd, a_or_b, &probabilities).unwrap();
+        assert!((p - 0.92).abs() < 1e-12);
+        let p = weighted::probability_epd(cudd, Cudd_Not(a_or_b), &probabilities).unwrap();
+        assert!((p.to_f64() - 0.08).abs() < 1e-12);
+        let importance = weighted::birnbaum_importance(cudd, a_or_b, &probabilities).unwrap();
+        assert!((importance[&0] - 0.8).abs() < 1e-12);
+        assert!((importance[&1] - 0.1).abs() < 1e-12);
+        probabilities.remove(&1);
+        assert!(weighted::probability(cudd, a_or_b, &probabilities).is_err());
+
+        // Weights of the negative and of the positive literal of every variable.
+        let mut weights = HashMap::new();
+        weights.insert(0, (1.0, 2.0));
+        weights.insert(1, (3.0, 5.0));
+        weights.insert(2, (1.0, 1.0));
+        let count = weighted::weighted_count(cudd, a_or_b, &weights).unwrap();
+        assert_eq!(count, 42.0);
+        let count = weighted::weighted_count_epd(cudd, a_or_b, &weights).unwrap();
+        assert_eq!(count.to_f64(), 42.0);
+        let count = weighted::weighted_count(cudd, Cudd_ReadOne(cudd), &weights).unwrap();
+        assert_eq!(count, 48.0);
+
+        // Swapping the variables must not change the result.
+        let mut order = vec![2, 1, 0];
+        assert_eq!(Cudd_ShuffleHeap(cudd, order.as_mut_ptr()), 1);
+        let count = weighted::weighted_count(cudd, a_or_b, &weights).unwrap();
+        assert_eq!(count, 42.0);
+
+        Cudd_RecursiveDeref(cudd, a_or_b);
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn transition_system_test() {
+    unsafe {
+        // A two bit counter (x0, x1) which is incremented when the input e is set.
+        let cudd = Cudd_Init(5, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let x0 = Cudd_bddIthVar(cudd, 0);
+        let x1 = Cudd_bddIthVar(cudd, 2);
+        let e = Cudd_bddIthVar(cudd, 4);
+        let f0 = Cudd_bddXor(cudd, x0, e);
+        Cudd_Ref(f0);
+        let carry = Cudd_bddAnd(cudd, x0, e);
+        Cudd_Ref(carry);
+        let f1 = Cudd_bddXor(cudd, x1, carry);
+        Cudd_Ref(f1);
+        let init = Cudd_bddAnd(cudd, Cudd_Not(x0), Cudd_Not(x1));
+        Cudd_Ref(init);
+        let pairs = [(0, 1), (2, 3)];
+        let system = TransitionSystem::from_functions(cudd, &pairs, &[4], &[f0, f1], init)
+            .unwrap()
+            .simplification(FrontierSimplification::LICompaction);
+        assert_eq!(1, Cudd_bddIsPsVar(cudd, 0));
+        assert_eq!(1, Cudd_bddIsNsVar(cudd, 3));
+        assert_eq!(1, Cudd_bddIsPiVar(cudd, 4));
+        assert!(TransitionSystem::new(cudd, &[(0, 1), (1, 2)], &[], init, init).is_err());
+
+        // One step from the initial state reaches states 0 and 1.
+        let image = system.image(init).unwrap();
+        assert_eq!(Cudd_Not(x1), image);
+        let preimage = system.preimage(init).unwrap();
+        let three = system.state(&[true, true]).unwrap();
+        let expected = Cudd_bddOr(cudd, init, three);
+        Cudd_Ref(expected);
+        assert_eq!(expected, preimage);
+
+        // Variables created after the system are kept by the permutation.
+        let y = Cudd_bddNewVar(cudd);
+        let init_y = Cudd_bddAnd(cudd, init, y);
+        Cudd_Ref(init_y);
+        let image_y = system.image(init_y).unwrap();
+        assert_eq!(Cudd_bddAnd(cudd, image, y), image_y);
+        let preimage_y = system.preimage(init_y).unwrap();
+        assert_eq!(Cudd_bddAnd(cudd, preimage, y), preimage_y);
+        Cudd_RecursiveDeref(cudd, image_y);
+        Cudd_RecursiveDeref(cudd, preimage_y);
+        Cudd_RecursiveDeref(cudd, init_y);
+
+        let reachability = system.reachable().unwrap();
+        assert_eq!(3, reachability.steps());
+        assert_eq!(Cudd_ReadOne(cudd), reachability.reached);
+        assert_eq!(init, reachability.rings[0]);
+        assert_eq!(three, reachability.rings[3]);
+        let trace = system.trace(&reachability, three).unwrap().unwrap();
+        let states: Vec<Vec<bool>> = trace.iter().map(|step| step.state.clone()).collect();
+        let expected_states = vec![
+            vec![false, false],
+            vec![true, false],
+            vec![false, true],
+            vec![true, true],
+        ];
+        assert_eq!(expected_states, states);
+        assert!(trace[..3].iter().all(|step| step.inputs == vec![true]));
+        assert!(trace[3].inputs.is_empty());
+        let backward = system.backward_reachable(three).unwrap();
+        assert_eq!(3, backward.steps());
+        assert!(system
+            .trace(&reachability, Cudd_ReadLogicZero(cudd))
+            .unwrap()
+            .is_none());
+
+        reachability.release(cudd);
+        backward.release(cudd);
+        system.release();
+        for node in &[f0, carry, f1, init, image, preimage, three, expected] {
+            Cudd_RecursiveDeref(cudd, *node);
+        }
+        assert_eq!(0, Cudd_CheckZeroRef(cudd));
+
+        // A latch which is set by the input a and then stays set.
+        let blif = ".model latch\n.inputs a b\n.outputs f\n.latch n s 0\n\
+                    .names a b f\n11 1\n.names s a n\n00 0\n.end\n";
+        let circuit = Circuit::parse_blif(blif).unwrap();
+        let bdds = circuit.build(cudd, &CircuitOptions::new()).unwrap();
+        assert!(TransitionSystem::from_circuit(cudd, &bdds).is_err());
+        bdds.release(cudd);
+        let options = CircuitOptions::new().next_state_vars(true);
+        let bdds = circuit.build(cudd, &options).unwrap();
+        let system = TransitionSystem::from_circuit(cudd, &bdds).unwrap();
+        let reachability = system.reachable().unwrap();
+        assert_eq!(1, reachability.steps());
+        let s = Cudd_bddIthVar(cudd, bdds.latches[0].present);
+        let trace = system.trace(&reachability, s).unwrap().unwrap();
+        let expected = vec![
+            TraceStep {
+                state: vec![false],
+                inputs: vec![true, false],
+            },
+            TraceStep {
+                state: vec![true],
+                inputs: vec![],
+            },
+        ];
+        assert_eq!(expected, trace);
+        reachability.release(cudd);
+        system.release();
+        bdds.release(cudd);
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn partitioned_image_test() {
+    unsafe {
+        // A three bit counter which is incremented when the input e is set.
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let blif = ".model counter\n.inputs e\n.outputs c\n\
+                    .latch n0 s0 0\n.latch n1 s1 0\n.latch n2 s2 0\n\
+                    .names e s0 n0\n10 1\n01 1\n.names e s0 c0\n11 1\n\
+                    .names c0 s1 n1\n10 1\n01 1\n.names c0 s1 c1\n11 1\n\
+                    .names c1 s2 n2\n10 1\n01 1\n.names c1 s2 c\n11 1\n.end\n";
+        let circuit = Circuit::parse_blif(blif).unwrap();
+        let options = CircuitOptions::new().next_state_vars(true);
+        let bdds = circuit.build(cudd, &options).unwrap();
+        let system = TransitionSystem::from_circuit(cudd, &bdds).unwrap();
+
+        let unclustered = PartitionOptions::new().cluster_threshold(0);
+        let relations = vec![
+            PartitionedRelation::from_circuit(cudd, &bdds, &unclustered).unwrap(),
+            PartitionedRelation::from_circuit(cudd, &bdds, &PartitionOptions::new()).unwrap(),
+        ];
+        assert_eq!(3, relations[0].clusters().len());
+        assert_eq!(1, relations[1].clusters().len());
+
+        let reachability = system.reachable().unwrap();
+        assert_eq!(7, reachability.steps());
+        let mut sets = reachability.rings.clone();
+        sets.push(reachability.reached);
+        for relation in &relations {
+            let monolithic = relation.monolithic().unwrap();
+            assert_eq!(system.relation(), monolithic);
+            Cudd_RecursiveDeref(cudd, monolithic);
+            for states in &sets {
+                let expected = system.image(*states).unwrap();
+                let image = relation.image(*states).unwrap();
+                assert!(image.exact);
+                assert_eq!(expected, image.states);
+                Cudd_RecursiveDeref(cudd, expected);
+                Cudd_RecursiveDeref(cudd, image.states);
+            }
+        }
+
+        // With a node limit, the image is over-approximated using clipping.
+        let limited = unclustered.node_limit(Some(0)).clipping_depth(Some(1));
+        let relation = PartitionedRelation::from_circuit(cudd, &bdds, &limited).unwrap();
+        for states in &sets {
+            let expected = system.image(*states).unwrap();
+            let image = relation.image(*states).unwrap();
+            assert_eq!(1, Cudd_bddLeq(cudd, expected, image.states));
+            assert!(!image.exact || expected == image.states);
+            Cudd_RecursiveDeref(cudd, expected);
+            Cudd_RecursiveDeref(cudd, image.states);
+        }
+
+        relation.release();
+        for relation in relations {
+            relation.release();
+        }
+        reachability.release(cudd);
+        system.release();
+        bdds.release(cudd);
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn ctl_model_checking_test() {
+    let formula = Ctl::parse("AG (t1 -> AF c1) & E[!c2 U c1 | t2] <-> 1").unwrap();
+    assert_eq!(
+        "AG (t1 -> AF c1) & E[!c2 U c1 | t2] <-> 1",
+        formula.to_string()
+    );
+    let formula: Ctl = "!EX !(p | q) -> A[p U EG q]".parse().unwrap();
+    assert_eq!(formula, Ctl::parse(&formula.to_string()).unwrap());
+    assert!(Ctl::parse("E[p U]").is_err());
+    assert!(Ctl::parse("EX").is_err());
+    assert!(Ctl::parse("p q").is_err());
+
+    unsafe {
+        // Two processes competing for a critical section (idle -> t -> c -> idle). The input
+        // s selects the process which moves, `turn` resolves conflicts and `last` records the
+        // process which moved last.
+        let cudd = Cudd_Init(13, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let names = [
+            "t1", "t1'", "c1", "c1'", "t2", "t2'", "c2", "c2'", "turn", "turn'", "last", "last'",
+            "s",
+        ];
+        let functions = [
+            "(!s & (!t1 & !c1 | t1 & !(!c2 & (!turn | !t2)))) | (s & t1)",
+            "(!s & t1 & !c2 & (!turn | !t2)) | (s & c1)",
+            "(s & (!t2 & !c2 | t2 & !(!c1 & (turn | !t1)))) | (!s & t2)",
+            "(s & t2 & !c1 & (turn | !t1)) | (!s & c2)",
+            "(!s & c1) | (turn & !(s & c2))",
+            "s",
+        ];
+        let functions: Vec<*mut DdNode> = functions
+            .iter()
+            .map(|f| parse_bdd(cudd, f, &names).unwrap())
+            .collect();
+        let init = parse_bdd(cudd, "!t1 & !c1 & !t2 & !c2 & !turn & !last", &names).unwrap();
+        let pairs = [(0, 1), (2, 3), (4, 5), (6, 7), (8, 9), (10, 11)];
+        let system = TransitionSystem::from_functions(cudd, &pairs, &[12], &functions, init);
+        let system = system.unwrap();
+        // Every step of the trace must be a transition of the system.
+        let valid = |trace: &CtlTrace| {
+            let mut successors: Vec<&Vec<bool>> =
+                trace.steps[1..].iter().map(|s| &s.state).collect();
+            if let Some(start) = trace.loop_start {
+                successors.push(&trace.steps[start].state);
+            }
+            trace.steps.iter().zip(successors).all(|(step, successor)| {
+                let state = system.state(&step.state).unwrap();
+                let image = system.image(state).unwrap();
+                let next = system.state(successor).unwrap();
+                let valid = Cudd_bddLeq(cudd, next, image) != 0;
+                for node in &[state, image, next] {
+                    Cudd_RecursiveDeref(cudd, *node);
+                }
+                valid
+            })
+        };
+
+        let checker = CtlChecker::new(&system, &names).unwrap();
+        let check = |checker: &CtlChecker, formula: &str| {
+            checker.holds(&Ctl::parse(formula).unwrap()).unwrap()
+        };
+        assert!(check(&checker, "AG !(c1 & c2)"));
+        assert!(check(&checker, "AG EX true"));
+        assert!(check(&checker, "EF c1 & EF c2"));
+        assert!(!check(&checker, "AG (t1 -> AF c1)"));
+        assert!(checker.holds(&Ctl::parse("x").unwrap()).is_err());
+        assert!(checker.holds(&Ctl::parse("t1'").unwrap()).is_err());
+
+        let witness = checker.witness(&Ctl::parse("E[!c2 U c1]").unwrap());
+        let witness = witness.unwrap().unwrap();
+        let states: Vec<Vec<bool>> = witness.steps.iter().map(|s| s.state.clone()).collect();
+        let idle = vec![false; 6];
+        assert_eq!(
+            vec![idle.clone(), vec![true, false, false, false, false, false]],
+            states[..2].to_vec()
+        );
+        assert_eq!(vec![false, true, false, false, false, false], states[2]);
+        assert_eq!(vec![false], witness.steps[0].inputs);
+        assert!(witness.steps[2].inputs.is_empty());
+        assert!(checker
+            .counterexample(&Ctl::parse("AG !(c1 & c2)").unwrap())
+            .unwrap()
+            .is_none());
+
+        // Without fairness, the first process can be starved.
+        let counterexample = checker.counterexample(&Ctl::parse("AG (t1 -> AF c1)").unwrap());
+        let counterexample = counterexample.unwrap().unwrap();
+        assert!(valid(&counterexample));
+        assert_eq!(idle, counterexample.steps[0].state);
+        let start = counterexample.loop_start.unwrap();
+        assert!(counterexample.steps.iter().any(|step| step.state[0]));
+        assert!(counterexample.steps[start..]
+            .iter()
+            .all(|step| !step.state[1]));
+        let witness = checker
+            .witness(&Ctl::parse("EG !c1").unwrap())
+            .unwrap()
+            .unwrap();
+        assert!(valid(&witness));
+        assert!(witness.loop_start.is_some());
+
+        // With both processes moving infinitely often, the first process is never starved.
+        let fairness = [Ctl::parse("last").unwrap(), Ctl::parse("!last").unwrap()];
+        let checker = checker.with_fairness(&fairness).unwrap();
+        assert!(check(&checker, "AG (t1 -> AF c1)"));
+        assert!(check(&checker, "AG !(c1 & c2)"));
+        assert!(!check(&checker, "EG !c1"));
+        let witness = checker
+            .witness(&Ctl::parse("EG true").unwrap())
+            .unwrap()
+            .unwrap();
+        assert!(valid(&witness));
+        let start = witness.loop_start.unwrap();
+        assert!(witness.steps[start..].iter().any(|step| step.state[5]));
+        assert!(witness.steps[start..].iter().any(|step| !step.state[5]));
+        drop(checker);
+        system.release();
+        for node in functions.iter().chain(&[init]) {
+            Cudd_RecursiveDeref(cudd, *node);
+        }
+        Cudd_Quit(cudd);
+
+        // Four dining philosophers: the selected philosopher starts eating if both neighbours
+        // are not eating, or stops eating.
+        let cudd = Cudd_Init(10, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let names = ["e0", "e0'", "e1", "e1'", "e2", "e2'", "e3", "e3'", "a", "b"];
+        let selected = ["!a & !b", "a & !b", "!a & b", "a & b"];
+        let functions: Vec<*mut DdNode> = (0..4)
+            .map(|i| {
+                let (left, right) = ((i + 3) % 4, (i + 1) % 4);
+                let f = format!(
+                    "({s}) & !e{i} & !e{l} & !e{r} | !({s}) & e{i}",
+                    s = selected[i],
+                    i = i,
+                    l = left,
+                    r = right
+                );
+                parse_bdd(cudd, &f, &names).unwrap()
+            })
+            .collect();
+        let init = parse_bdd(cudd, "!e0 & !e1 & !e2 & !e3", &names).unwrap();
+        let pairs = [(0, 1), (2, 3), (4, 5), (6, 7)];
+        let system = TransitionSystem::from_functions(cudd, &pairs, &[8, 9], &functions, init);
+        let system = system.unwrap();
+        let checker = CtlChecker::new(&system, &names).unwrap();
+        assert!(check(&checker, "AG !(e0 & e1) & AG !(e0 & e3)"));
+        assert!(check(&checker, "EF (e0 & e2) & AG EF e0"));
+        assert!(!check(&checker, "AG AF e0"));
+        let af = checker.states(&Ctl::parse("AF e0").unwrap()).unwrap();
+        let au = checker
+            .states(&Ctl::parse("A[true U e0]").unwrap())
+            .unwrap();
+        assert_eq!(af, au);
+        let witness = checker
+            .witness(&Ctl::parse("EF (e0 & e2)").unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(3, witness.steps.len());
+        assert_eq!(vec![true, false, true, false], witness.steps[2].state);
+        let counterexample = checker.counterexample(&Ctl::parse("AG AF e0").unwrap());
+        let counterexample = counterexample.unwrap().unwrap();
+        let start = counterexample.loop_start.unwrap();
+        assert!(counterexample.steps[start..]
+            .iter()
+            .all(|step| !step.state[0]));
+        drop(checker);
+        system.release();
+        for node in functions.iter().chain(&[init, af, au]) {
+            Cudd_RecursiveDeref(cudd, *node);
+        }
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn dddmp_round_trip_test() {
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let a = Cudd_bddIthVar(cudd, 0);
+        let b = Cudd_bddIthVar(cudd, 1);
+        let c = Cudd_bddIthVar(cudd, 2);
+        let a_and_b = Cudd_bddAnd(cudd, a, b);
+        Cudd_Ref(a_and_b);
+        let a_or_not_c = Cudd_bddOr(cudd, a, Cudd_Not(c));
+        Cudd_Ref(a_or_not_c);
+
+        for mode in &[DddmpMode::Text, DddmpMode::Binary] {
+            let mut buffer = Vec::new();
+            DddmpWriter::new()
+                .mode(*mode)
+                .var_names(&["a", "b", "c"])
+                .root_names(&["f", "g"])
+                .write(cudd, &mut buffer, &[a_and_b, a_or_not_c])
+                .unwrap();
+
+            let roots = DddmpReader::new()
+                .match_names(&["a", "b", "c"])
+                .read(cudd, &buffer[..])
+                .unwrap();
+            assert_eq!(2, roots.len());
+            assert_eq!(Some("f".to_string()), roots[0].name);
+            assert_eq!(Some("g".to_string()), roots[1].name);
+            assert_eq!(a_and_b, roots[0].node);
+            assert_eq!(a_or_not_c, roots[1].node);
+            for root in roots {
+                Cudd_RecursiveDeref(cudd, root.node);
+            }
+        }
+
+        let too_few_names = DddmpWriter::new().var_names(&["a"]);
+        assert!(too_few_names.write(cudd, Vec::new(), &[a_and_b]).is_err());
+
+        Cudd_RecursiveDeref(cudd, a_and_b);
+        Cudd_RecursiveDeref(cudd, a_or_not_c);
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn dddmp_header_test() {
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let a = Cudd_bddIthVar(cudd, 0);
+        let c = Cudd_bddIthVar(cudd, 2);
+        let a_and_c = Cudd_bddAnd(cudd, a, c);
+        Cudd_Ref(a_and_c);
+
+        let mut buffer = Vec::new();
+        DddmpWriter::new()
+            .mode(DddmpMode::Binary)
+            .dd_name("test")
+            .var_names(&["a", "b", "c"])
+            .aux_ids(&[10, 20, 30])
+            .root_names(&["f", "g"])
+            .write(cudd, &mut buffer, &[a_and_c, Cudd_Not(a)])
+            .unwrap();
+
+        let header = dddmp::read_header(&buffer[..]).unwrap();
+        assert_eq!(Dddmp_DecompType::DDDMP_BDD, header.dd_type);
+        assert_eq!(Some("test".to_string()), header.dd_name);
+        assert_eq!(Some(DddmpMode::Binary), header.mode);
+        assert_eq!(3, header.var_count);
+        assert_eq!(2, header.support_count);
+        assert_eq!(
+            Some(vec!["a".to_string(), "c".to_string()]),
+            header.support_names
+        );
+        assert_eq!(Some(vec![0, 2]), header.ids);
+        assert_eq!(Some(vec![10, 30]), header.aux_ids);
+        assert_eq!(2, header.root_count);
+        assert_eq!(
+            Some(vec!["f".to_string(), "g".to_string()]),
+            header.root_names
+        );
+        assert!(DddmpReader::new()
+            .match_aux_ids(&[10, 20, 30])
+            .check_header(&header)
+            .is_ok());
+        assert!(DddmpReader::new()
+            .match_names(&["a", "b"])
+            .check_header(&header)
+            .is_err());
+
+        Cudd_RecursiveDeref(cudd, a_and_c);
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn dddmp_format_test() {
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let vars: Vec<*mut DdNode> = (0..4).map(|i| Cudd_bddIthVar(cudd, i)).collect();
+        let a_xor_b = Cudd_bddXor(cudd, vars[0], vars[1]);
+        Cudd_Ref(a_xor_b);
+        let f = Cudd_bddIte(cudd, vars[3], a_xor_b, Cudd_Not(vars[2]));
+        Cudd_Ref(f);
+        let roots = [f, Cudd_Not(a_xor_b)];
+
+        let mut files = Vec::new();
+        for mode in &[DddmpMode::Text, DddmpMode::Binary] {
+            let mut buffer = Vec::new();
+            DddmpWriter::new()
+                .mode(*mode)
+                .var_names(&["a", "b", "c", "d"])
+                .write(cudd, &mut buffer, &roots)
+                .unwrap();
+            let file = DddmpFile::parse(&buffer).unwrap();
+            assert_eq!(Some(*mode), file.header.mode);
+            assert_eq!(2, file.roots.len());
+            assert_eq!(
+                Cudd_SharingSize(roots.as_ptr() as *mut *mut DdNode, 2) as usize,
+                file.nodes.len()
+            );
+            for bits in 0..16 {
+                let mut inputs: Vec<c_int> = (0..4).map(|i| (bits >> i) & 1).collect();
+                let assignment: Vec<bool> = inputs.iter().map(|v| *v == 1).collect();
+                for (i, root) in roots.iter().enumerate() {
+                    let value = Cudd_Eval(cudd, *root, inputs.as_mut_ptr());
+                    let expected = if value == Cudd_ReadOne(cudd) {
+                        1.0
+                    } else {
+                        0.0
+                    };
+                    assert_eq!(Some(expected), file.eval(i, &assignment));
+                }
+            }
+            files.push(file);
+        }
+        assert_eq!(files[0].nodes, files[1].nodes);
+        assert_eq!(files[0].roots, files[1].roots);
+
+        // Files converted by the Rust writer can be loaded by DDDMP.
+        for mode in &[DddmpMode::Text, DddmpMode::Binary] {
+            let bytes = files[0].to_bytes(*mode).unwrap();
+            assert_eq!(files[0].nodes, DddmpFile::parse(&bytes).unwrap().nodes);
+            // A huge node count must not be trusted for the allocation.
+            let start = bytes.windows(8).position(|w| w == b".nnodes ").unwrap() + 8;
+            let end = start + bytes[start..].iter().position(|b| *b == b'\n').unwrap();
+            let mut huge = bytes[..start].to_vec();
+            huge.extend_from_slice(b"18446744073709551615");
+            huge.extend_from_slice(&bytes[end..]);
+            assert!(DddmpFile::parse(&huge).is_err());
+            let loaded = DddmpReader::new().read(cudd, &bytes[..]).unwrap();
+            assert_eq!(f, loaded[0].node);
+            assert_eq!(Cudd_Not(a_xor_b), loaded[1].node);
+            for root in loaded {
+                Cudd_RecursiveDeref(cudd, root.node);
+            }
+        }
+
+        Cudd_RecursiveDeref(cudd, a_xor_b);
+        Cudd_RecursiveDeref(cudd, f);
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn dddmp_cnf_test() {
+    use dddmp::{CnfVariable, CnfWriter, Dddmp_DecompCnfLoadType, Dddmp_DecompCnfStoreType};
+
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let a = Cudd_bddIthVar(cudd, 0);
+        let b = Cudd_bddIthVar(cudd, 1);
+        let c = Cudd_bddIthVar(cudd, 2);
+        let a_and_b = Cudd_bddAnd(cudd, a, b);
+        Cudd_Ref(a_and_b);
+        let f = Cudd_bddOr(cudd, a_and_b, Cudd_Not(c));
+        Cudd_Ref(f);
+
+        for mode in &[
+            Dddmp_DecompCnfStoreType::DDDMP_CNF_MODE_NODE,
+            Dddmp_DecompCnfStoreType::DDDMP_CNF_MODE_MAXTERM,
+            Dddmp_DecompCnfStoreType::DDDMP_CNF_MODE_BEST,
+        ] {
+            let mut buffer = Vec::new();
+            let info = CnfWriter::new()
+                .mode(*mode)
+                .var_names(&["a", "b", "c"])
+                .write(cudd, &mut buffer, &[f])
+                .unwrap();
+            let text = String::from_utf8(buffer.clone()).unwrap();
+            assert!(text.contains("p cnf"));
+            assert!(info.clause_count > 0);
+            assert_eq!(vec![1, 2, 3], info.cnf_ids);
+            assert_eq!(Some(CnfVariable::Bdd(1)), info.variable(2));
+            if info.new_var_count > 0 {
+                assert_eq!(Some(CnfVariable::Auxiliary(0)), info.variable(4));
+            }
+
+            let roots = DddmpReader::new()
+                .read_cnf(
+                    cudd,
+                    &buffer[..],
+                    Dddmp_DecompCnfLoadType::DDDMP_CNF_MODE_CONJ_QUANT,
+                )
+                .unwrap();
+            assert_eq!(f, roots[0].node);
+            for root in roots {
+                Cudd_RecursiveDeref(cudd, root.node);
+            }
+        }
+
+        let overflowing = CnfWriter::new().cnf_ids(&[1, 2, c_int::MAX]);
+        match overflowing.write(cudd, Vec::new(), &[f]) {
+            Err(DddmpError::InvalidInput(_)) => (),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        // Plain DIMACS without the DDDMP header.
+        let dimacs = "p cnf 2 2\n1 0\n-2 0\n";
+        let roots = DddmpReader::new()
+            .read_cnf(
+                cudd,
+                dimacs.as_bytes(),
+                Dddmp_DecompCnfLoadType::DDDMP_CNF_MODE_CONJ_QUANT,
+            )
+            .unwrap();
+        let expected = Cudd_bddAnd(cudd, a, Cudd_Not(b));
+        Cudd_Ref(expected);
+        assert_eq!(expected, roots[0].node);
+        Cudd_RecursiveDeref(cudd, roots[0].node);
+        Cudd_RecursiveDeref(cudd, expected);
+
+        Cudd_RecursiveDeref(cudd, a_and_b);
+        Cudd_RecursiveDeref(cudd, f);
+        Cudd_Quit(cudd);
+    }
+}
+
+#[test]
+pub fn dddmp_check_macros_test() {
+    fn check(value: c_int) -> Result<c_int, DddmpError> {
+        ::Dddmp_CheckAndReturn!(value < 0, "Negative value.");
+        Ok(value)
+    }
+    assert_eq!(1, check(1).unwrap());
+    match check(-1) {
+        Err(DddmpError::Format(message)) => assert!(message.starts_with("Negative value.")),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+
+    let values = [1, 2, -3, 4];
+    let mut position = 0;
+    let result: Result<(), DddmpError> = 'check: loop {
+        if position == values.len() {
+            break Ok(());
+        }
+        ::Dddmp_CheckAndGotoLabel!(values[position] < 0, "Negative value.", 'check);
+        position += 1;
+    };
+    assert_eq!(2, position);
+    match result {
+        Err(DddmpError::Format(message)) => assert!(message.starts_with("Negative value.")),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+pub fn dddmp_error_test() {
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let a = Cudd_bddIthVar(cudd, 0);
+        let b = Cudd_bddIthVar(cudd, 1);
+        let a_and_b = Cudd_bddAnd(cudd, a, b);
+        Cudd_Ref(a_and_b);
+
+        let mut buffer = Vec::new();
+        DddmpWriter::new()
+            .var_names(&["a", "b"])
+            .write(cudd, &mut buffer, &[a_and_b])
+            .unwrap();
+        let text = String::from_utf8(buffer.clone()).unwrap();
+
+        let old_version = text.replace(".ver DDDMP-2.0", ".ver DDDMP-1.0");
+        match DddmpReader::new().read(cudd, old_version.as_bytes()) {
+            Err(DddmpError::Version(version)) => assert_eq!("DDDMP-1.0", version),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        match DddmpReader::new()
+            .match_names(&["a", "c"])
+            .read(cudd, &buffer[..])
+        {
+            Err(DddmpError::VarMatch(_)) => (),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        match DddmpReader::new().read_add(cudd, &buffer[..]) {
+            Err(DddmpError::Format(_)) => (),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        match DddmpReader::new().read(cudd, &b"not a DDDMP file"[..]) {
+            Err(DddmpError::Format(_)) => (),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        match DddmpReader::new()
+            .match_aux_ids(&[10])
+            .read(cudd, &buffer[..])
+        {
+            Err(DddmpError::InvalidInput(_)) => (),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        match DddmpReader::new()
+            .compose_ids(&[0, -1])
+            .read(cudd, &buffer[..])
+        {
+            Err(DddmpError::InvalidInput(_)) => (),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        // The ids are checked before the header, so plain DIMACS is covered too.
+        match DddmpReader::new().compose_ids(&[1]).read_cnf(
+            cudd,
+            &b"p cnf 2 1\n1 2 0\n"[..],
+            dddmp::Dddmp_DecompCnfLoadType::DDDMP_CNF_MODE_CONJ_QUANT,
+        ) {
+            Err(DddmpError::InvalidInput(_)) => (),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        Cudd_RecursiveDeref(cudd, a_and_b);
+        Cudd_Quit(cudd);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+pub fn dddmp_serde_test() {
+    use bincode::Options;
+    use dddmp::{DddmpDiagram, DddmpSeed};
+    use serde::de::value::{BytesDeserializer, Error, StrDeserializer};
+    use serde::de::DeserializeSeed;
+
+    unsafe {
+        let cudd = Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0);
+        let a = Cudd_bddIthVar(cudd, 0);
+        let b = Cudd_bddIthVar(cudd, 1);
+        let a_xor_b = Cudd_bddXor(cudd, a, b);
+        Cudd_Ref(a_xor_b);
+
+        let writer = DddmpWriter::new().var_names(&["a", "b"]).aux_ids(&[10, 20]);
+        let diagram = DddmpDiagram::bdd(cudd, a_xor_b).writer(writer.clone());
+        let text = String::from_utf8(diagram.to_dddmp().unwrap()).unwrap();
+        assert!(text.contains("a b"));
+        let loaded = DddmpSeed::bdd(cudd)
+            .deserialize(StrDeserializer::<Error>::new(&text))
+            .unwrap();
+        assert_eq!(a_xor_b, loaded.node());
+
+        // The names and auxiliary ids of the header are kept when serialising again.
+        let header = dddmp::read_header(&loaded.to_dddmp().unwrap()[..]).unwrap();
+        assert_eq!(
+            Some(vec!["a".to_string(), "b".to_string()]),
+            header.support_names
+        );
+        assert_eq!(Some(vec![10, 20]), header.aux_ids);
+
+        let diagram = diagram.writer(writer.mode(DddmpMode::Binary));
+        let bytes = diagram.to_dddmp().unwrap();
+        let loaded = DddmpSeed::bdd(cudd)
+            .deserialize(BytesDeserializer::<Error>::new(&bytes))
+            .unwrap();
+        assert_eq!(a_xor_b, loaded.node());
+
+        // bincode is not self-describing, so the diagram has to have a fixed shape.
+        for diagram in &[diagram.clone(), loaded.clone()] {
+            let encoded = bincode::DefaultOptions::new().serialize(diagram).unwrap();
+            let decoded = bincode::DefaultOptions::new()
+                .deserialize_seed(DddmpSeed::bdd(cudd), &encoded)
+                .unwrap();
+            assert_eq!(a_xor_b, decoded.node());
+            let header = dddmp::read_header(&decoded.to_dddmp().unwrap()[..]).unwrap();
+            assert_eq!(Some(DddmpMode::Binary), header.mode);
+            assert_eq!(Some(vec![10, 20]), header.aux_ids);
+        }
+
+        drop(loaded);
+        drop(diagram);
+        Cudd_RecursiveDeref(cudd, a_xor_b);
+        Cudd_Quit(cudd);
+    }
+}
@@ -0,0 +1,623 @@
+use circuit::CircuitBdds;
+use cudd::*;
+use libc::c_int;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+use {DdManager, DdNode};
+
+/// How the frontier of the reachability fixpoint is simplified before its image (or
+/// preimage) is computed. The simplified frontier contains the exact frontier and is
+/// contained in the states reached so far, so the result of the fixpoint is the same.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrontierSimplification {
+    /// Use the states reached for the first time in the previous step.
+    None,
+    /// Simplify the frontier using `Cudd_bddRestrict` with the previously reached states
+    /// as a don't care set.
+    Restrict,
+    /// Simplify the frontier using `Cudd_bddLICompaction` with the previously reached
+    /// states as a don't care set.
+    LICompaction,
+}
+
+/// A finite state machine given by a transition relation over present-state variables,
+/// next-state variables and input variables, together with a set of initial states.
+///
+/// The variables are registered with the manager (`Cudd_bddSetPsVar`, `Cudd_bddSetNsVar`,
+/// `Cudd_bddSetPairIndex` and `Cudd_bddSetPiVar`), so that they can be used by grouped
+/// reordering methods such as `CUDD_REORDER_LAZY_SIFT`.
+///
+/// States are given as BDDs over the present-state variables. All BDDs returned by the
+/// system are referenced, and the system itself has to be released using
+/// `TransitionSystem::release`.
+pub struct TransitionSystem {
+    manager: *mut DdManager,
+    present: Vec<c_int>,
+    next: Vec<c_int>,
+    inputs: Vec<c_int>,
+    relation: *mut DdNode,
+    init: *mut DdNode,
+    // The variables quantified by the image (present and inputs) and by the preimage
+    // (next and inputs), and all state variables (present and next).
+    image_cube: *mut DdNode,
+    preimage_cube: *mut DdNode,
+    state_cube: *mut DdNode,
+    // The permutation which swaps the present and next-state variables.
+    swap: Vec<c_int>,
+    simplification: FrontierSimplification,
+}
+
+/// The result of a reachability fixpoint. All nodes are referenced and can be released
+/// using `Reachability::release`.
+#[derive(Clone, Debug)]
+pub struct Reachability {
+    /// The onion rings of the fixpoint: `rings[i]` are the states which are reached
+    /// in exactly `i` steps (and not in fewer).
+    pub rings: Vec<*mut DdNode>,
+    /// All reached states.
+    pub reached: *mut DdNode,
+}
+
+/// One step of a counterexample trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceStep {
+    /// The values of the present-state variables (see `TransitionSystem::present_vars`).
+    pub state: Vec<bool>,
+    /// The values of the input variables which lead to the next state of the trace
+    /// (see `TransitionSystem::input_vars`). Empty for the last state.
+    pub inputs: Vec<bool>,
+}
+
+fn invalid_input<S: Into<String>>(message: S) -> Error {
+    Error::new(ErrorKind::InvalidInput, message.into())
+}
+
+/// Reference the result of a CUDD operation, or fail if CUDD ran out of memory.
+pub(crate) unsafe fn referenced(node: *mut DdNode) -> Result<*mut DdNode> {
+    if node.is_null() {
+        Err(Error::new(ErrorKind::Other, "CUDD ran out of memory."))
+    } else {
+        Cudd_Ref(node);
+        Ok(node)
+    }
+}
+
+/// The referenced cube of the given variable indices.
+pub(crate) unsafe fn indices_cube(
+    manager: *mut DdManager,
+    indices: &[c_int],
+) -> Result<*mut DdNode> {
+    let mut indices = indices.to_vec();
+    referenced(Cudd_IndicesToCube(
+        manager,
+        indices.as_mut_ptr(),
+        indices.len() as c_int,
+    ))
+}
+
+/// The referenced conjunction of the literals of `vars` with the given `values`.
+pub(crate) unsafe fn literals_cube(
+    manager: *mut DdManager,
+    vars: &[c_int],
+    values: &[bool],
+) -> Result<*mut DdNode> {
+    let mut nodes: Vec<*mut DdNode> = vars.iter().map(|i| Cudd_bddIthVar(manager, *i)).collect();
+    let mut phase: Vec<c_int> = values.iter().map(|value| *value as c_int).collect();
+    referenced(Cudd_bddComputeCube(
+        manager,
+        nodes.as_mut_ptr(),
+        phase.as_mut_ptr(),
+        nodes.len() as c_int,
+    ))
+}
+
+/// The values of `vars` in one satisfying assignment of `f`, which must not be zero. The
+/// assignment is chosen deterministically, preferring `false` values.
+unsafe fn pick(manager: *mut DdManager, f: *mut DdNode, vars: &[c_int]) -> Vec<bool> {
+    let zero = Cudd_ReadLogicZero(manager);
+    let mut values = HashMap::new();
+    let mut edge = f;
+    while Cudd_IsConstant(Cudd_Regular(edge)) == 0 {
+        let node = Cudd_Regular(edge);
+        let (mut then_edge, mut else_edge) = (Cudd_T(node), Cudd_E(node));
+        if Cudd_IsComplement(edge) != 0 {
+            then_edge = Cudd_Not(then_edge);
+            else_edge = Cudd_Not(else_edge);
+        }
+        let value = else_edge == zero;
+        values.insert(Cudd_NodeReadIndex(node) as c_int, value);
+        edge = if value { then_edge } else { else_edge };
+    }
+    vars.iter()
+        .map(|index| values.get(index).cloned().unwrap_or(false))
+        .collect()
+}
+
+/// `Cudd_bddPermute` using the permutation `swap` returned by `register_vars`. CUDD reads
+/// the entry of every variable of the `node`, so the permutation is extended by the
+/// identity for variables which were created after it.
+pub(crate) unsafe fn swap_vars(
+    manager: *mut DdManager,
+    node: *mut DdNode,
+    swap: &[c_int],
+) -> *mut DdNode {
+    let size = Cudd_ReadSize(manager).max(0);
+    if swap.len() >= size as usize {
+        return Cudd_bddPermute(manager, node, swap.as_ptr() as *mut c_int);
+    }
+    let mut extended = swap.to_vec();
+    extended.extend(swap.len() as c_int..size);
+    Cudd_bddPermute(manager, node, extended.as_mut_ptr())
+}
+
+/// Check that the `pairs` of present and next-state variables and the `inputs` are distinct
+/// variables of the manager and register them (`Cudd_bddSetPsVar`, ...). Returns the
+/// permutation which swaps the present and next-state variables.
+pub(crate) unsafe fn register_vars(
+    manager: *mut DdManager,
+    pairs: &[(c_int, c_int)],
+    inputs: &[c_int],
+) -> Result<Vec<c_int>> {
+    let size = Cudd_ReadSize(manager);
+    let mut seen = vec![false; size.max(0) as usize];
+    let all = pairs
+        .iter()
+        .flat_map(|(p, n)| vec![*p, *n])
+        .chain(inputs.iter().cloned());
+    for index in all {
+        match seen.get_mut(index as usize) {
+            Some(seen) if index >= 0 && !*seen => *seen = true,
+            _ => {
+                let message = format!("Variable {} is invalid or used more than once.", index);
+                return Err(invalid_input(message));
+            }
+        }
+    }
+    let mut swap: Vec<c_int> = (0..size).collect();
+    for (p, n) in pairs {
+        Cudd_bddSetPsVar(manager, *p);
+        Cudd_bddSetNsVar(manager, *n);
+        Cudd_bddSetPairIndex(manager, *p, *n);
+        Cudd_bddSetPairIndex(manager, *n, *p);
+        swap[*p as usize] = *n;
+        swap[*n as usize] = *p;
+    }
+    for index in inputs {
+        Cudd_bddSetPiVar(manager, *index);
+    }
+    Ok(swap)
+}
+
+/// The pairs of present and next-state variables of the latches of a circuit.
+pub(crate) fn latch_pairs(circuit: &CircuitBdds) -> Result<Vec<(c_int, c_int)>> {
+    let mut pairs = Vec::new();
+    for latch in &circuit.latches {
+        match latch.next {
+            Some(next) => pairs.push((latch.present, next)),
+            None => {
+                let message = format!("Latch `{}` has no next-state variable.", latch.name);
+                return Err(invalid_input(message));
+            }
+        }
+    }
+    Ok(pairs)
+}
+
+impl TransitionSystem {
+    /// A system with the given pairs of present and next-state variables (indices), input
+    /// variables, transition `relation` and initial states `init`. The `relation` and `init`
+    /// are referenced by the system.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be a valid CUDD manager and must stay valid until the system is
+    /// released. The `relation` and `init` must be BDDs of this manager.
+    pub unsafe fn new(
+        manager: *mut DdManager,
+        pairs: &[(c_int, c_int)],
+        inputs: &[c_int],
+        relation: *mut DdNode,
+        init: *mut DdNode,
+    ) -> Result<TransitionSystem> {
+        let swap = register_vars(manager, pairs, inputs)?;
+        let present: Vec<c_int> = pairs.iter().map(|(p, _)| *p).collect();
+        let next: Vec<c_int> = pairs.iter().map(|(_, n)| *n).collect();
+        let quantified = |vars: &[c_int], other: &[c_int]| -> Vec<c_int> {
+            vars.iter().chain(other.iter()).cloned().collect()
+        };
+        let image_cube = indices_cube(manager, &quantified(&present, inputs))?;
+        let preimage_cube = indices_cube(manager, &quantified(&next, inputs));
+        let state_cube = indices_cube(manager, &quantified(&present, &next));
+        let (preimage_cube, state_cube) = match (preimage_cube, state_cube) {
+            (Ok(preimage_cube), Ok(state_cube)) => (preimage_cube, state_cube),
+            (preimage_cube, state_cube) => {
+                for cube in [Ok(image_cube), preimage_cube, state_cube].iter().flatten() {
+                    Cudd_RecursiveDeref(manager, *cube);
+                }
+                return Err(Error::new(ErrorKind::Other, "CUDD ran out of memory."));
+            }
+        };
+        Cudd_Ref(relation);
+        Cudd_Ref(init);
+        Ok(TransitionSystem {
+            manager,
+            present,
+            next,
+            inputs: inputs.to_vec(),
+            relation,
+            init,
+            image_cube,
+            preimage_cube,
+            state_cube,
+            swap,
+            simplification: FrontierSimplification::Restrict,
+        })
+    }
+
+    /// A system where the next value of `pairs[i]` is given by the function `functions[i]`
+    /// over the present-state and input variables. The relation is the conjunction of
+    /// `next_i <-> functions[i]`.
+    ///
+    /// # Safety
+    ///
+    /// Same as `TransitionSystem::new`.
+    pub unsafe fn from_functions(
+        manager: *mut DdManager,
+        pairs: &[(c_int, c_int)],
+        inputs: &[c_int],
+        functions: &[*mut DdNode],
+        init: *mut DdNode,
+    ) -> Result<TransitionSystem> {
+        if pairs.len() != functions.len() {
+            let message = format!(
+                "Expected {} next-state functions, but {} were given.",
+                pairs.len(),
+                functions.len()
+            );
+            return Err(invalid_input(message));
+        }
+        let mut relation = Cudd_ReadOne(manager);
+        Cudd_Ref(relation);
+        for ((_, next), function) in pairs.iter().zip(functions) {
+            let next = Cudd_bddIthVar(manager, *next);
+            let conjunct = Cudd_bddXnor(manager, next, *function);
+            let conjunct = match referenced(conjunct) {
+                Ok(conjunct) => conjunct,
+                Err(error) => {
+                    Cudd_RecursiveDeref(manager, relation);
+                    return Err(error);
+                }
+            };
+            let conjunction = referenced(Cudd_bddAnd(manager, relation, conjunct));
+            Cudd_RecursiveDeref(manager, conjunct);
+            Cudd_RecursiveDeref(manager, relation);
+            relation = conjunction?;
+        }
+        let system = TransitionSystem::new(manager, pairs, inputs, relation, init);
+        Cudd_RecursiveDeref(manager, relation);
+        system
+    }
+
+    /// The system of the latches of a circuit built using `Circuit::build` with next-state
+    /// variables (see `CircuitOptions::next_state_vars`). Latches without an initial value
+    /// can start in both values.
+    ///
+    /// # Safety
+    ///
+    /// Same as `TransitionSystem::new`, and `circuit` must be built by the `manager`.
+    pub unsafe fn from_circuit(
+        manager: *mut DdManager,
+        circuit: &CircuitBdds,
+    ) -> Result<TransitionSystem> {
+        let pairs = latch_pairs(circuit)?;
+        let functions: Vec<*mut DdNode> = circuit.latches.iter().map(|l| l.function).collect();
+        let mut init_vars = Vec::new();
+        let mut init_values = Vec::new();
+        for latch in &circuit.latches {
+            if let Some(value) = latch.init {
+                init_vars.push(latch.present);
+                init_values.push(value);
+            }
+        }
+        let inputs: Vec<c_int> = circuit.inputs.iter().map(|(_, index)| *index).collect();
+        let init = literals_cube(manager, &init_vars, &init_values)?;
+        let system = TransitionSystem::from_functions(manager, &pairs, &inputs, &functions, init);
+        Cudd_RecursiveDeref(manager, init);
+        system
+    }
+
+    /// Set the simplification of the frontier used by the reachability fixpoints
+    /// (`FrontierSimplification::Restrict` by default).
+    pub fn simplification(mut self, simplification: FrontierSimplification) -> TransitionSystem {
+        self.simplification = simplification;
+        self
+    }
+
+    /// The manager of the system.
+    pub fn manager(&self) -> *mut DdManager {
+        self.manager
+    }
+
+    /// The present-state variables (indices).
+    pub fn present_vars(&self) -> &[c_int] {
+        &self.present
+    }
+
+    /// The next-state variables (indices), in the order of their present-state variables.
+    pub fn next_vars(&self) -> &[c_int] {
+        &self.next
+    }
+
+    /// The input variables (indices).
+    pub fn input_vars(&self) -> &[c_int] {
+        &self.inputs
+    }
+
+    /// The transition relation (not referenced again).
+    pub fn relation(&self) -> *mut DdNode {
+        self.relation
+    }
+
+    /// The initial states (not referenced again).
+    pub fn init(&self) -> *mut DdNode {
+        self.init
+    }
+
+    /// The state with the given values of the present-state variables, as a cube.
+    ///
+    /// # Safety
+    ///
+    /// The manager of the system must still be valid.
+    pub unsafe fn state(&self, values: &[bool]) -> Result<*mut DdNode> {
+        if values.len() != self.present.len() {
+            let message = format!(
+                "Expected {} values, but {} were given.",
+                self.present.len(),
+                values.len()
+            );
+            return Err(invalid_input(message));
+        }
+        literals_cube(self.manager, &self.present, values)
+    }
+
+    /// The states reachable in one step from the `states`.
+    ///
+    /// # Safety
+    ///
+    /// The manager of the system must still be valid and `states` must be a BDD of it.
+    pub unsafe fn image(&self, states: *mut DdNode) -> Result<*mut DdNode> {
+        let manager = self.manager;
+        let next = referenced(Cudd_bddAndAbstract(
+            manager,
+            self.relation,
+            states,
+            self.image_cube,
+        ))?;
+        let result = referenced(swap_vars(manager, next, &self.swap));
+        Cudd_RecursiveDeref(manager, next);
+        result
+    }
+
+    /// The states from which the `states` are reachable in one step.
+    ///
+    /// # Safety
+    ///
+    /// The manager of the system must still be valid and `states` must be a BDD of it.
+    pub unsafe fn preimage(&self, states: *mut DdNode) -> Result<*mut DdNode> {
+        let manager = self.manager;
+        let next = referenced(swap_vars(manager, states, &self.swap))?;
+        let result = referenced(Cudd_bddAndAbstract(
+            manager,
+            self.relation,
+            next,
+            self.preimage_cube,
+        ));
+        Cudd_RecursiveDeref(manager, next);
+        result
+    }
+
+    /// The states reachable from the initial states.
+    ///
+    /// # Safety
+    ///
+    /// The manager of the system must still be valid.
+    pub unsafe fn reachable(&self) -> Result<Reachability> {
+        self.fixpoint(self.init, TransitionSystem::image)
+    }
+
+    /// The states from which the `target` states are reachable. The rings contain the
+    /// states at the given distance from the target.
+    ///
+    /// # Safety
+    ///
+    /// The manager of the system must still be valid and `target` must be a BDD of it.
+    pub unsafe fn backward_reachable(&self, target: *mut DdNode) -> Result<Reachability> {
+        self.fixpoint(target, TransitionSystem::preimage)
+    }
+
+    unsafe fn fixpoint(
+        &self,
+        start: *mut DdNode,
+        step: unsafe fn(&TransitionSystem, *mut DdNode) -> Result<*mut DdNode>,
+    ) -> Result<Reachability> {
+        let manager = self.manager;
+        Cudd_Ref(start);
+        Cudd_Ref(start);
+        let mut result = Reachability {
+            rings: vec![start],
+            reached: start,
+        };
+        loop {
+            let frontier = result.rings[result.rings.len() - 1];
+            let new = self
+                .simplify(frontier, result.reached)
+                .and_then(|source| {
+                    let image = step(self, source);
+                    Cudd_RecursiveDeref(manager, source);
+                    image
+                })
+                .and_then(|image| {
+                    let new = Cudd_bddAnd(manager, image, Cudd_Not(result.reached));
+                    let new = referenced(new);
+                    Cudd_RecursiveDeref(manager, image);
+                    new
+                });
+            let new = match new {
+                Ok(new) => new,
+                Err(error) => {
+                    result.release(manager);
+                    return Err(error);
+                }
+            };
+            if new == Cudd_ReadLogicZero(manager) {
+                Cudd_RecursiveDeref(manager, new);
+                return Ok(result);
+            }
+            result.rings.push(new);
+            let reached = match referenced(Cudd_bddOr(manager, result.reached, new)) {
+                Ok(reached) => reached,
+                Err(error) => {
+                    result.release(manager);
+                    return Err(error);
+                }
+            };
+            Cudd_RecursiveDeref(manager, result.reached);
+            result.reached = reached;
+        }
+    }
+
+    /// The (referenced) set of states whose image is computed instead of the `frontier`.
+    unsafe fn simplify(&self, frontier: *mut DdNode, reached: *mut DdNode) -> Result<*mut DdNode> {
+        let manager = self.manager;
+        let simplify = match self.simplification {
+            FrontierSimplification::None => {
+                Cudd_Ref(frontier);
+                return Ok(frontier);
+            }
+            FrontierSimplification::Restrict => Cudd_bddRestrict,
+            FrontierSimplification::LICompaction => Cudd_bddLICompaction,
+        };
+        // The frontier only has to be preserved outside of the previously reached states.
+        let care = referenced(Cudd_bddOr(manager, frontier, Cudd_Not(reached)))?;
+        let result = referenced(simplify(manager, frontier, care));
+        Cudd_RecursiveDeref(manager, care);
+        result
+    }
+
+    /// A shortest trace from an initial state to one of the `target` states, using the
+    /// result of `TransitionSystem::reachable`, or `None` if no target state is reachable.
+    ///
+    /// # Safety
+    ///
+    /// The manager of the system must still be valid and `target` must be a BDD of it.
+    pub unsafe fn trace(
+        &self,
+        reachability: &Reachability,
+        target: *mut DdNode,
+    ) -> Result<Option<Vec<TraceStep>>> {
+        let manager = self.manager;
+        // The first ring which intersects the target.
+        let last = match reachability
+            .rings
+            .iter()
+            .position(|ring| Cudd_bddLeq(manager, *ring, Cudd_Not(target)) == 0)
+        {
+            Some(last) => last,
+            None => return Ok(None),
+        };
+        let hit = referenced(Cudd_bddAnd(manager, reachability.rings[last], target))?;
+        let mut states = vec![pick(manager, hit, &self.present)];
+        Cudd_RecursiveDeref(manager, hit);
+        // Walk back through the rings, picking a predecessor in every ring.
+        let mut steps = Vec::new();
+        for ring in reachability.rings[..last].iter().rev() {
+            let successor = literals_cube(manager, &self.next, &states[states.len() - 1])?;
+            let predecessors =
+                Cudd_bddAndAbstract(manager, self.relation, successor, self.preimage_cube);
+            let candidates = referenced(predecessors).and_then(|predecessors| {
+                let candidates = Cudd_bddAnd(manager, predecessors, *ring);
+                Cudd_RecursiveDeref(manager, predecessors);
+                referenced(candidates)
+            });
+            let candidates = match candidates {
+                Ok(candidates) => candidates,
+                Err(error) => {
+                    Cudd_RecursiveDeref(manager, successor);
+                    return Err(error);
+                }
+            };
+            let state = pick(manager, candidates, &self.present);
+            Cudd_RecursiveDeref(manager, candidates);
+            let inputs = self.transition_inputs(&state, successor);
+            Cudd_RecursiveDeref(manager, successor);
+            steps.push(TraceStep {
+                state: state.clone(),
+                inputs: inputs?,
+            });
+            states.push(state);
+        }
+        steps.reverse();
+        steps.push(TraceStep {
+            state: states[0].clone(),
+            inputs: Vec::new(),
+        });
+        Ok(Some(steps))
+    }
+
+    /// The values of the inputs which lead from the `state` to the `successor` (a cube over
+    /// the next-state variables).
+    pub(crate) unsafe fn transition_inputs(
+        &self,
+        state: &[bool],
+        successor: *mut DdNode,
+    ) -> Result<Vec<bool>> {
+        let manager = self.manager;
+        let present = self.state(state)?;
+        let transition = Cudd_bddAnd(manager, present, successor);
+        Cudd_RecursiveDeref(manager, present);
+        let transition = referenced(transition)?;
+        let inputs = Cudd_bddAndAbstract(manager, self.relation, transition, self.state_cube);
+        Cudd_RecursiveDeref(manager, transition);
+        let inputs = referenced(inputs)?;
+        let values = pick(manager, inputs, &self.inputs);
+        Cudd_RecursiveDeref(manager, inputs);
+        Ok(values)
+    }
+
+    /// Dereference the relation, the initial states and the internal cubes.
+    ///
+    /// # Safety
+    ///
+    /// The manager of the system must still be valid.
+    pub unsafe fn release(self) {
+        let nodes = [
+            self.relation,
+            self.init,
+            self.image_cube,
+            self.preimage_cube,
+            self.state_cube,
+        ];
+        for node in nodes.iter() {
+            Cudd_RecursiveDeref(self.manager, *node);
+        }
+    }
+}
+
+impl Reachability {
+    /// The number of steps after which no new states were reached.
+    pub fn steps(&self) -> usize {
+        self.rings.len() - 1
+    }
+
+    /// Dereference the rings and the reached states.
+    ///
+    /// # Safety
+    ///
+    /// The `manager` must be the manager of the system which computed the result.
+    pub unsafe fn release(self, manager: *mut DdManager) {
+        for ring in self.rings {
+            Cudd_RecursiveDeref(manager, ring);
+        }
+        Cudd_RecursiveDeref(manager, self.reached);
+    }
+}
@@ -0,0 +1,259 @@
+use cudd::*;
+use epd::Epd;
+use libc::{c_int, c_void};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+use std::ops::{Add, Mul};
+use {DdManager, DdNode};
+
+fn invalid_input<S: Into<String>>(message: S) -> Error {
+    Error::new(ErrorKind::InvalidInput, message.into())
+}
+
+/// The probability that `f` is true when every variable `i` is true with probability
+/// `probabilities[i]`, independently of the other variables. Every variable in the support of
+/// `f` must have a probability.
+///
+/// The probability is computed in a single memoised pass over the BDD. Use
+/// `probability_epd` when the result can be smaller than `f64::MIN_POSITIVE`.
+///
+/// # Safety
+///
+/// The `manager` must be a valid CUDD manager and `f` a valid BDD of this manager.
+pub unsafe fn probability(
+    manager: *mut DdManager,
+    f: *mut DdNode,
+    probabilities: &HashMap<c_int, f64>,
+) -> Result<f64> {
+    WeightedPass::new(manager, &literal_probabilities(probabilities)?).evaluate(f)
+}
+
+/// Same as `probability`, but computed using extended double precision numbers.
+///
+/// # Safety
+///
+/// Same as `probability`.
+pub unsafe fn probability_epd(
+    manager: *mut DdManager,
+    f: *mut DdNode,
+    probabilities: &HashMap<c_int, f64>,
+) -> Result<Epd> {
+    WeightedPass::new(manager, &literal_probabilities(probabilities)?).evaluate(f)
+}
+
+/// The weighted model count of `f`: the sum over the satisfying assignments of the product
+/// of the weights of their literals. `weights[i]` is the pair of the weights of the negative
+/// and of the positive literal of variable `i`.
+///
+/// The assignments range over all variables in `weights`, which must include the support
+/// of `f`. With all weights equal to `(1.0, 1.0)`, this is the number of minterms.
+///
+/// # Safety
+///
+/// Same as `probability`.
+pub unsafe fn weighted_count(
+    manager: *mut DdManager,
+    f: *mut DdNode,
+    weights: &HashMap<c_int, (f64, f64)>,
+) -> Result<f64> {
+    WeightedPass::new(manager, weights).evaluate(f)
+}
+
+/// Same as `weighted_count`, but computed using extended double precision numbers, so that
+/// counts over many variables neither overflow nor underflow.
+///
+/// # Safety
+///
+/// Same as `probability`.
+pub unsafe fn weighted_count_epd(
+    manager: *mut DdManager,
+    f: *mut DdNode,
+    weights: &HashMap<c_int, (f64, f64)>,
+) -> Result<Epd> {
+    WeightedPass::new(manager, weights).evaluate(f)
+}
+
+/// The Birnbaum importance of every variable in the support of `f`: the probability that
+/// the value of `f` depends on the variable (the probability of `Cudd_bddBooleanDiff`). For
+/// monotone functions, this is also the sensitivity `P(f | x) - P(f | !x)`.
+///
+/// # Safety
+///
+/// Same as `probability`.
+pub unsafe fn birnbaum_importance(
+    manager: *mut DdManager,
+    f: *mut DdNode,
+    probabilities: &HashMap<c_int, f64>,
+) -> Result<HashMap<c_int, f64>> {
+    let weights = literal_probabilities(probabilities)?;
+    let mut pass = WeightedPass::new(manager, &weights);
+    let mut importance = HashMap::new();
+    for index in support(manager, f)? {
+        let difference = Cudd_bddBooleanDiff(manager, f, index);
+        if difference.is_null() {
+            return Err(Error::new(ErrorKind::Other, "CUDD ran out of memory."));
+        }
+        Cudd_Ref(difference);
+        let result = pass.evaluate(difference);
+        Cudd_RecursiveDeref(manager, difference);
+        importance.insert(index, result?);
+    }
+    Ok(importance)
+}
+
+fn literal_probabilities(
+    probabilities: &HashMap<c_int, f64>,
+) -> Result<HashMap<c_int, (f64, f64)>> {
+    let mut weights = HashMap::new();
+    for (index, probability) in probabilities {
+        if !(0.0..=1.0).contains(probability) {
+            let message = format!(
+                "Probability {} of variable {} is invalid.",
+                probability, index
+            );
+            return Err(invalid_input(message));
+        }
+        weights.insert(*index, (1.0 - probability, *probability));
+    }
+    Ok(weights)
+}
+
+/// The indices of the variables in the support of `f` (see `Cudd_SupportIndices`).
+pub(crate) unsafe fn support(manager: *mut DdManager, f: *mut DdNode) -> Result<Vec<c_int>> {
+    let mut indices = std::ptr::null_mut();
+    let count = Cudd_SupportIndices(manager, f, &mut indices);
+    if count < 0 {
+        return Err(Error::new(ErrorKind::Other, "CUDD ran out of memory."));
+    }
+    let mut result = Vec::new();
+    if !indices.is_null() {
+        result.extend_from_slice(std::slice::from_raw_parts(indices, count as usize));
+        libc::free(indices as *mut c_void);
+    }
+    Ok(result)
+}
+
+/// A bottom-up evaluation of BDDs, where every node is mapped to the weighted count of the
+/// node and of its complement over the variables at its level and below.
+struct WeightedPass<T> {
+    // The weights of the literals, ordered by the level of their variable.
+    weights: Vec<(T, T)>,
+    // The position of every variable (index) in `weights`.
+    positions: HashMap<c_int, usize>,
+    // `products[k][i]` is the total weight of the variables at positions `i..i + 2^k`.
+    products: Vec<Vec<T>>,
+    counts: HashMap<*mut DdNode, (T, T)>,
+}
+
+impl<T> WeightedPass<T>
+where
+    T: Copy + Add<Output = T> + Mul<Output = T> + From<f64>,
+{
+    unsafe fn new(
+        manager: *mut DdManager,
+        weights: &HashMap<c_int, (f64, f64)>,
+    ) -> WeightedPass<T> {
+        let mut variables: Vec<(c_int, (f64, f64))> = weights
+            .iter()
+            .map(|(index, weights)| (*index, *weights))
+            .collect();
+        variables.sort_by_key(|(index, _)| {
+            if *index < Cudd_ReadSize(manager) {
+                Cudd_ReadPerm(manager, *index)
+            } else {
+                *index
+            }
+        });
+        let positions = variables
+            .iter()
+            .enumerate()
+            .map(|(position, (index, _))| (*index, position))
+            .collect();
+        let weights: Vec<(T, T)> = variables
+            .iter()
+            .map(|(_, (negative, positive))| (T::from(*negative), T::from(*positive)))
+            .collect();
+        let mut products = vec![weights.iter().map(|(n, p)| *n + *p).collect::<Vec<T>>()];
+        let mut width = 1;
+        while 2 * width <= weights.len() {
+            let last = &products[products.len() - 1];
+            let next = (0..=weights.len() - 2 * width)
+                .map(|i| last[i] * last[i + width])
+                .collect();
+            products.push(next);
+            width *= 2;
+        }
+        WeightedPass {
+            weights,
+            positions,
+            products,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// The total weight of the variables at positions `from..to`.
+    fn gap(&self, mut from: usize, to: usize) -> T {
+        let mut result = T::from(1.0);
+        while from < to {
+            let level = (0..self.products.len())
+                .rev()
+                .find(|k| from + (1 << k) <= to)
+                .unwrap_or(0);
+            result = result * self.products[level][from];
+            from += 1 << level;
+        }
+        result
+    }
+
+    unsafe fn position(&self, node: *mut DdNode) -> Result<usize> {
+        if Cudd_IsConstant(node) != 0 {
+            return Ok(self.weights.len());
+        }
+        let index = Cudd_NodeReadIndex(node) as c_int;
+        match self.positions.get(&index) {
+            Some(position) => Ok(*position),
+            None => Err(invalid_input(format!("Variable {} has no weight.", index))),
+        }
+    }
+
+    /// The counts of the regular `node` and of its complement.
+    unsafe fn count(&mut self, node: *mut DdNode) -> Result<(T, T)> {
+        if let Some(count) = self.counts.get(&node) {
+            return Ok(*count);
+        }
+        let count = if Cudd_IsConstant(node) != 0 {
+            (T::from(1.0), T::from(0.0))
+        } else {
+            let position = self.position(node)?;
+            let (negative, positive) = self.weights[position];
+            let then_count = self.edge_count(Cudd_T(node), position + 1)?;
+            let else_count = self.edge_count(Cudd_E(node), position + 1)?;
+            (
+                positive * then_count.0 + negative * else_count.0,
+                positive * then_count.1 + negative * else_count.1,
+            )
+        };
+        self.counts.insert(node, count);
+        Ok(count)
+    }
+
+    /// The counts of the function of the `edge` over the variables at positions `from..`.
+    unsafe fn edge_count(&mut self, edge: *mut DdNode, from: usize) -> Result<(T, T)> {
+        let node = Cudd_Regular(edge);
+        let gap = self.gap(from, self.position(node)?);
+        let (count, complement_count) = self.count(node)?;
+        if Cudd_IsComplement(edge) != 0 {
+            Ok((gap * complement_count, gap * count))
+        } else {
+            Ok((gap * count, gap * complement_count))
+        }
+    }
+
+    /// The weighted count of `f`. The counts of the nodes are forgotten afterwards, since
+    /// the nodes can be garbage collected before the next evaluation.
+    unsafe fn evaluate(&mut self, f: *mut DdNode) -> Result<T> {
+        let result = self.edge_count(f, 0);
+        self.counts.clear();
+        Ok(result?.0)
+    }
+}